@@ -18,7 +18,13 @@ fn copy_rules_dir() -> std::io::Result<()> {
 }
 
 fn main() {
-    let _build = cxx_build::bridge("src/lib.rs")
+    // The core library and the command-line checker only use the shared types of `src/lib.rs`,
+    // which need no C++ on the Rust side.
+    if std::env::var_os("CARGO_FEATURE_PLUGIN").is_none() {
+        return;
+    }
+
+    let _build = cxx_build::bridges(["src/lib.rs", "src/plugin.rs"])
         .flag("/std:c++20")
         .flag("/permissive-")
         .flag("/W4")
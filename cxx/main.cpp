@@ -2,6 +2,7 @@
 #include "esfpc/cxx/util.hpp"
 #include "esfpc/include/EuroScopePlugIn.hpp"
 #include "esfpc/src/lib.rs.h"
+#include "esfpc/src/plugin.rs.h"
 #include "rust/cxx.h"
 #include <format>
 #include <memory>
@@ -61,12 +62,13 @@ void EsPlugin::handleTagClick() {
     return;
   }
 
-  try {
-    ffi::Action action = checkFlightPlan(flight_plan);
-    this->DisplayUserMessage(PLUGIN_NAME, nullptr, "Check succeded", true, true,
-                             false, false, false);
-  } catch (rust::Error &e) {
-    std::string msg = std::format("Check failed: {}", e.what());
+  // The action shown followed by the evaluated condition of the deciding rule.
+  // Evaluation errors are part of the lines.
+  rust::Vec<rust::String> lines =
+      ffi::explain_flightplan(getFlightPlan(flight_plan));
+  std::string callsign = flight_plan.GetCallsign();
+  for (const rust::String &line : lines) {
+    std::string msg = std::format("{}: {}", callsign, std::string(line));
     this->DisplayUserMessage(PLUGIN_NAME, nullptr, msg.c_str(), true, true,
                              false, false, false);
   }
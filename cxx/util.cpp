@@ -113,19 +113,31 @@ ffi::FlightRule getFlightRule(const char *flight_rule) {
   }
 }
 
+std::string getEquipment(const char *aircraft_info) {
+  // ICAO format: "A320/M-SDE2E3FGIJ1RWY/LB1"
+  std::string info = std::string(aircraft_info);
+  size_t pos = info.find('-');
+  if (pos == std::string::npos) {
+    return "";
+  }
+  return info.substr(pos + 1);
+}
+
 ffi::FlightPlan getFlightPlan(EuroScopePlugIn::CFlightPlan flight_plan) {
   EuroScopePlugIn::CFlightPlanData fp_data = flight_plan.GetFlightPlanData();
 
   ffi::WakeTurbulenceCategory wtc =
       getWakeTurbulenceCategory(fp_data.GetAircraftWtc());
   ffi::AircraftType ac_typ = getAircraftType(fp_data.GetAircraftType());
+  rust::String icao = fp_data.GetAircraftFPType();
   ffi::FaaEquipmentCode equip_code =
       getFaaEquipmentCode(fp_data.GetCapibilities());
+  rust::String equip = getEquipment(fp_data.GetAircraftInfo());
   ffi::EngineType eng_typ = getEngineType(fp_data.GetEngineType());
   rust::u8 eng_count = static_cast<uint8_t>(fp_data.GetEngineNumber());
   bool is_rvsm_capable = fp_data.IsRvsm();
-  ffi::Aircraft ac = {ac_typ,  wtc,       equip_code,
-                      eng_typ, eng_count, is_rvsm_capable};
+  ffi::Aircraft ac = {ac_typ,  icao,      wtc,           equip_code,
+                      equip,   eng_typ,   eng_count,     is_rvsm_capable};
 
   ffi::FlightRule rule = getFlightRule(fp_data.GetPlanType());
   rust::u32 cfl = flight_plan.GetClearedAltitude();
@@ -135,7 +147,9 @@ ffi::FlightPlan getFlightPlan(EuroScopePlugIn::CFlightPlan flight_plan) {
   rust::String arr = fp_data.GetDestination();
   rust::String sid = fp_data.GetSidName();
   rust::String route = fp_data.GetRoute();
+  rust::String remarks = fp_data.GetRemarks();
 
-  ffi::FlightPlan fp = {ac, rule, cfl, rfl, dep, dep_rwy, arr, sid, route};
+  ffi::FlightPlan fp = {ac,      rule, cfl, rfl,   dep,
+                        dep_rwy, arr,  sid, route, remarks};
   return fp;
 }
\ No newline at end of file
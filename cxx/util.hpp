@@ -2,6 +2,7 @@
 
 #include "esfpc/include/EuroScopePlugIn.hpp"
 #include <cstdint>
+#include <string>
 
 namespace ffi {
 enum class AircraftType : uint8_t;
@@ -27,5 +28,10 @@ ffi::EngineType getEngineType(char);
 /// Convert C string to FFI enum.
 ffi::FlightRule getFlightRule(const char *flight_rule);
 
+/// Extract the ICAO item 10 equipment from the unextracted aircraft info.
+///
+/// Returns an empty string if the aircraft info is not in ICAO format.
+std::string getEquipment(const char *aircraft_info);
+
 /// Build flight plan struct from EuroScope flight plan.
 ffi::FlightPlan getFlightPlan(EuroScopePlugIn::CFlightPlan flight_plan);
\ No newline at end of file
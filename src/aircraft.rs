@@ -0,0 +1,115 @@
+//! Aircraft performance database, keyed by ICAO type designator.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde::Deserialize;
+
+/// Performance data of an aircraft type.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct AircraftType {
+    /// ICAO type designator, e.g. `B738`.
+    pub icao: String,
+    /// Service ceiling in feet.
+    pub ceiling: u32,
+    /// Typical cruise speed in knots TAS.
+    pub cruise_speed: u32,
+    /// ICAO approach category, `A` to `E`.
+    pub category: String,
+    /// RECAT-EU wake turbulence group, `A` to `F`.
+    pub recat: String,
+}
+
+#[derive(Debug, Default)]
+pub struct AircraftDatabase {
+    types: HashMap<String, AircraftType>,
+}
+
+impl AircraftDatabase {
+    /// Load a `.csv` or `.json` file, depending on its extension.
+    ///
+    /// CSV files have the columns `icao,ceiling,cruise_speed,category,recat`. A header line, empty
+    /// lines and lines starting with `#` are ignored, as are malformed lines. JSON files contain an
+    /// array of objects with the same fields.
+    pub fn load(&mut self, path: &Path) -> Result<(), std::io::Error> {
+        let input = std::fs::read_to_string(path)?;
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase());
+        match extension.as_deref() {
+            Some("csv") => self.parse_csv(&input),
+            Some("json") => {
+                let types: Vec<AircraftType> = serde_json::from_str(&input)?;
+                types.into_iter().for_each(|t| self.add(t));
+            }
+            _ => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("Unknown aircraft file type: {}", path.display()),
+                ))
+            }
+        }
+        Ok(())
+    }
+
+    pub fn parse_csv(&mut self, input: &str) {
+        for line in input.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with("icao,") {
+                continue;
+            }
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            let [icao, ceiling, cruise_speed, category, recat] = fields[..] else {
+                continue;
+            };
+            let (Ok(ceiling), Ok(cruise_speed)) = (ceiling.parse(), cruise_speed.parse()) else {
+                continue;
+            };
+            self.add(AircraftType {
+                icao: icao.into(),
+                ceiling,
+                cruise_speed,
+                category: category.into(),
+                recat: recat.into(),
+            });
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.types.is_empty()
+    }
+
+    /// Add a type, replacing any previous entry with the same designator.
+    pub fn add(&mut self, typ: AircraftType) {
+        self.types.insert(typ.icao.clone(), typ);
+    }
+
+    pub fn get(&self, icao: &str) -> Option<&AircraftType> {
+        self.types.get(icao)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv() {
+        let mut db = AircraftDatabase::default();
+        db.parse_csv("icao,ceiling,cruise_speed,category,recat\n# jets\nB738,41000,450,C,D\n\nAT76, 25000 ,275,B,E\nC172,x,120,A,F\nA320\n");
+        assert_eq!(
+            Some(&AircraftType {
+                icao: "B738".into(),
+                ceiling: 41000,
+                cruise_speed: 450,
+                category: "C".into(),
+                recat: "D".into(),
+            }),
+            db.get("B738")
+        );
+        assert_eq!(25000, db.get("AT76").unwrap().ceiling);
+        assert_eq!(None, db.get("C172"));
+        assert_eq!(None, db.get("A320"));
+    }
+}
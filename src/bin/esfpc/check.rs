@@ -0,0 +1,264 @@
+//! `esfpc check`: check flight plans against a rules directory and print all findings.
+
+use std::io::Read;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use esfpc::config::{Action, ActionType};
+use esfpc::{datafeed, ffi, fpl, Checker, Finding, RuleTrace};
+
+use crate::Args;
+
+const USAGE: &str = "\
+Usage: esfpc check [options] [FILE...]
+
+Checks the flight plans in the files, which contain a JSON object, an array of JSON objects, a
+VATSIM datafeed or ICAO FPL messages. Without files, a single flight plan is built from the options.
+
+Options:
+  --rules DIR     Rules directory, settings are read from esfpc.json next to it [default: rules]
+  --callsign CS   Callsign shown in the output
+  --explain true  Also print the rules evaluated for the action shown, with the value of every
+                  sub-expression of their conditions
+
+Flight plan fields, which replace the ones of every flight plan read:
+  --rule I|V|Y|Z  --rfl FEET|FL350  --cfl FEET|FL070  --dep ICAO  --dep-rwy RWY  --arr ICAO
+  --sid SID  --route ROUTE  --remarks ITEM18
+  --ac-icao TYPE  --ac-type L  --ac-wtc M  --ac-faa-equip-code Q  --ac-equip ITEM10
+  --ac-eng-type J  --ac-eng-count N  --ac-is-rvsm-capable true|false";
+
+/// Options which set a field of the flight plan.
+pub(crate) const FIELDS: &[&str] = &[
+    "rule",
+    "rfl",
+    "cfl",
+    "dep",
+    "dep-rwy",
+    "arr",
+    "sid",
+    "route",
+    "remarks",
+    "ac-icao",
+    "ac-type",
+    "ac-wtc",
+    "ac-faa-equip-code",
+    "ac-equip",
+    "ac-eng-type",
+    "ac-eng-count",
+    "ac-is-rvsm-capable",
+];
+
+pub fn run(args: impl IntoIterator<Item = String>) -> Result<u8, String> {
+    let known: Vec<&str> = ["rules", "callsign", "explain"]
+        .iter()
+        .chain(FIELDS)
+        .copied()
+        .collect();
+    let args = Args::parse(args, &known)?;
+    if args.help {
+        println!("{USAGE}");
+        return Ok(0);
+    }
+
+    let explain = match args.value("explain") {
+        Some(value) => value
+            .parse()
+            .map_err(|_| format!("Invalid value for --explain: {value}"))?,
+        None => false,
+    };
+    let rules_dir = Path::new(args.value("rules").unwrap_or("rules"));
+    let checker = Checker::load(rules_dir)
+        .map_err(|e| format!("Failed to load {}: {e}", rules_dir.display()))?;
+
+    let mut flight_plans = vec![];
+    for path in &args.positional {
+        let input = read_input(path).map_err(|e| format!("Failed to read {path}: {e}"))?;
+        flight_plans.extend(parse_input(&input).map_err(|e| format!("{path}: {e}"))?);
+    }
+    if args.positional.is_empty() {
+        flight_plans.push((String::new(), empty_flight_plan()));
+    }
+
+    let mut code = 0;
+    for (callsign, mut fp) in flight_plans {
+        for (name, value) in &args.options {
+            set_field(&mut fp, name, value)?;
+        }
+        let callsign = args.value("callsign").unwrap_or(&callsign);
+        code = code.max(print_findings(callsign, &fp, &checker.findings(&fp)));
+        if explain {
+            print_traces(&checker.explain(&fp));
+        }
+    }
+    Ok(code)
+}
+
+pub(crate) fn read_input(path: &str) -> std::io::Result<String> {
+    if path == "-" {
+        let mut input = String::new();
+        std::io::stdin().read_to_string(&mut input)?;
+        return Ok(input);
+    }
+    std::fs::read_to_string(path)
+}
+
+/// Flight plans with their callsigns from JSON, a VATSIM datafeed or FPL messages.
+pub(crate) fn parse_input(input: &str) -> Result<Vec<(String, ffi::FlightPlan)>, String> {
+    let input = input.trim();
+    if input.starts_with('{') || input.starts_with('[') {
+        let json: serde_json::Value = serde_json::from_str(input).map_err(|e| e.to_string())?;
+        if json.get("pilots").is_some() || json.get("prefiles").is_some() {
+            let messages = datafeed::parse(input).map_err(|e| e.to_string())?;
+            return Ok(messages
+                .into_iter()
+                .map(|msg| (msg.callsign, msg.flight_plan))
+                .collect());
+        }
+        let inputs: Vec<Input> = match json.is_array() {
+            true => serde_json::from_value(json),
+            false => serde_json::from_value(json).map(|fp| vec![fp]),
+        }
+        .map_err(|e| e.to_string())?;
+        return Ok(inputs.into_iter().map(|i| (i.callsign, i.fp)).collect());
+    }
+
+    // Messages are enclosed in parentheses, or one per line without them
+    let messages: Vec<&str> = match input.contains('(') {
+        true => input
+            .split('(')
+            .map(|msg| msg.split(')').next().unwrap_or_default().trim())
+            .filter(|msg| !msg.is_empty())
+            .collect(),
+        false => input
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .collect(),
+    };
+    messages
+        .into_iter()
+        .map(|msg| {
+            fpl::parse(msg)
+                .map(|msg| (msg.callsign, msg.flight_plan))
+                .map_err(|e| format!("{e}: {msg}"))
+        })
+        .collect()
+}
+
+/// JSON flight plan with its callsign.
+#[derive(Deserialize)]
+struct Input {
+    #[serde(default)]
+    callsign: String,
+    #[serde(flatten)]
+    fp: ffi::FlightPlan,
+}
+
+/// Flight plan with all fields omitted.
+pub(crate) fn empty_flight_plan() -> ffi::FlightPlan {
+    serde_json::from_str("{}").unwrap()
+}
+
+/// Level in feet, also accepted as flight level, e.g. `FL350` or `F350`.
+fn parse_level(value: &str) -> Option<u32> {
+    match value.strip_prefix("FL").or_else(|| value.strip_prefix('F')) {
+        Some(fl) => fl.parse::<u32>().ok().map(|fl| fl * 100),
+        None => value.parse().ok(),
+    }
+}
+
+pub(crate) fn set_field(fp: &mut ffi::FlightPlan, name: &str, value: &str) -> Result<(), String> {
+    let invalid = || format!("Invalid value for --{name}: {value}");
+    match name {
+        "rule" => fp.rule = value.parse()?,
+        "rfl" => fp.rfl = parse_level(value).ok_or_else(invalid)?,
+        "cfl" => fp.cfl = parse_level(value).ok_or_else(invalid)?,
+        "dep" => fp.dep = value.into(),
+        "dep-rwy" => fp.dep_rwy = value.into(),
+        "arr" => fp.arr = value.into(),
+        "sid" => fp.sid = value.into(),
+        "route" => fp.route = value.into(),
+        "remarks" => fp.remarks = value.into(),
+        "ac-icao" => fp.ac.icao = value.into(),
+        "ac-type" => fp.ac.typ = value.parse()?,
+        "ac-wtc" => fp.ac.wtc = value.parse()?,
+        "ac-faa-equip-code" => fp.ac.faa_equip_code = value.parse()?,
+        "ac-equip" => fp.ac.equip = value.into(),
+        "ac-eng-type" => fp.ac.eng_typ = value.parse()?,
+        "ac-eng-count" => fp.ac.eng_count = value.parse().map_err(|_| invalid())?,
+        "ac-is-rvsm-capable" => fp.ac.is_rvsm_capable = value.parse().map_err(|_| invalid())?,
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Exit code of an action, see the usage.
+fn exit_code(action: &Result<Action, &'static str>) -> u8 {
+    match action {
+        Ok(action) => match action.typ {
+            ActionType::Success | ActionType::Info => 0,
+            ActionType::Warning => 1,
+            ActionType::Error => 2,
+        },
+        Err(_) => 3,
+    }
+}
+
+/// The action shown in EuroScope, which is the first finding.
+pub(crate) fn shown_action(findings: &[Finding]) -> Result<Action, &'static str> {
+    match findings.first() {
+        Some(finding) => finding.action.clone(),
+        None => Ok(Action::new(ActionType::Warning, "UNK".into())),
+    }
+}
+
+/// `DLH123 EDDF-EDDS`
+pub(crate) fn label(callsign: &str, fp: &ffi::FlightPlan) -> String {
+    match callsign {
+        "" => format!("{}-{}", fp.dep, fp.arr),
+        _ => format!("{callsign} {}-{}", fp.dep, fp.arr),
+    }
+}
+
+/// `error RFL`
+pub(crate) fn fmt_action(action: &Result<Action, &'static str>) -> String {
+    match action {
+        Ok(action) => format!("{} {}", action.typ, action.msg),
+        Err(e) => format!("failed: {e}"),
+    }
+}
+
+/// `error    RFL    Route flight level direction`, or `error    PNT    unknown_points: CINDI`
+pub(crate) fn fmt_finding(finding: &Finding) -> String {
+    let name = finding.name.as_deref().unwrap_or("-");
+    match (&finding.action, &finding.detail) {
+        (Ok(action), Some(detail)) => {
+            format!("{:<8} {:<6} {name}: {detail}", action.typ, action.msg)
+        }
+        (Ok(action), None) => format!("{:<8} {:<6} {name}", action.typ, action.msg),
+        (Err(e), _) => format!("{:<8} {:<6} {name}: {e}", "failed", ""),
+    }
+}
+
+/// Prints the findings of a flight plan, the first one being the action shown in EuroScope, and
+/// returns the exit code.
+pub(crate) fn print_findings(callsign: &str, fp: &ffi::FlightPlan, findings: &[Finding]) -> u8 {
+    let result = shown_action(findings);
+    println!("{}: {}", label(callsign, fp), fmt_action(&result));
+    for finding in findings {
+        println!("  {}", fmt_finding(finding));
+    }
+    exit_code(&result)
+}
+
+/// Prints the rules evaluated for the action shown with their evaluated conditions.
+pub(crate) fn print_traces(traces: &[RuleTrace]) {
+    for trace in traces {
+        let name = trace.name.as_deref().unwrap_or("-");
+        println!("  {name} ({} {}):", trace.action.typ, trace.action.msg);
+        for line in trace.trace.lines() {
+            println!("    {line}");
+        }
+    }
+}
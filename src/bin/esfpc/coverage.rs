@@ -0,0 +1,86 @@
+//! `esfpc coverage`: find rules which never fire, are shadowed or overlap with other rules.
+
+use std::path::Path;
+
+use esfpc::coverage::{self, Coverage};
+use esfpc::Checker;
+
+use crate::check::{parse_input, read_input};
+use crate::Args;
+
+const USAGE: &str = "\
+Usage: esfpc coverage [options] [FILE...]
+
+Compares the conditions of all rules, as far as they are simple comparisons like `rfl > 24000` or
+`arr in ['EDDM', 'EDDN']`, and checks the flight plans in the files, which are read like by
+`esfpc check`. Prints how many flight plans every rule matched and decided, followed by the rules
+which never fire, are always shadowed by earlier checks or rules, or match flight plans together
+with an earlier rule with a different action.
+
+Options:
+  --rules DIR         Rules directory, settings are read from esfpc.json next to it [default: rules]
+  --format text|json  Output format [default: text]
+
+Exit codes:
+  0  no findings
+  1  findings
+  4  invalid arguments, input or rules";
+
+pub fn run(args: impl IntoIterator<Item = String>) -> Result<u8, String> {
+    let args = Args::parse(args, &["rules", "format"])?;
+    if args.help {
+        println!("{USAGE}");
+        return Ok(0);
+    }
+    let json = match args.value("format").unwrap_or("text") {
+        "text" => false,
+        "json" => true,
+        format => return Err(format!("Unknown format {format}")),
+    };
+
+    let rules_dir = Path::new(args.value("rules").unwrap_or("rules"));
+    let checker = Checker::load(rules_dir)
+        .map_err(|e| format!("Failed to load {}: {e}", rules_dir.display()))?;
+    let mut flight_plans = vec![];
+    for path in &args.positional {
+        let input = read_input(path).map_err(|e| format!("Failed to read {path}: {e}"))?;
+        let inputs = parse_input(&input).map_err(|e| format!("{path}: {e}"))?;
+        flight_plans.extend(inputs.into_iter().map(|(_, fp)| fp));
+    }
+
+    let coverage = coverage::analyze(&checker, &flight_plans);
+    if json {
+        let output = serde_json::to_string_pretty(&coverage).map_err(|e| e.to_string())?;
+        println!("{output}");
+    } else {
+        print_coverage(&coverage);
+    }
+    let code = if coverage.diagnostics.is_empty() {
+        0
+    } else {
+        1
+    };
+    Ok(code)
+}
+
+fn print_coverage(coverage: &Coverage) {
+    if coverage.flight_plans > 0 {
+        println!("{} flight plans", coverage.flight_plans);
+        println!();
+        println!("  {:>7} {:>7}  Rule", "matched", "decided");
+        for rule in &coverage.rules {
+            let name = rule.name.as_deref().unwrap_or("-");
+            println!(
+                "  {:>7} {:>7}  {}: rule {} ({name})",
+                rule.matched,
+                rule.decided,
+                rule.file.display(),
+                rule.rule
+            );
+        }
+        println!();
+    }
+    for diagnostic in &coverage.diagnostics {
+        println!("{diagnostic}");
+    }
+}
@@ -0,0 +1,90 @@
+//! `esfpc datafeed`: check all flight plans of a VATSIM datafeed snapshot and summarize them.
+
+use std::path::Path;
+
+use esfpc::datafeed::{self, Counts, Summary};
+use esfpc::Checker;
+
+use crate::Args;
+
+const USAGE: &str = "\
+Usage: esfpc datafeed [options] FILE
+
+Checks the flight plans of all pilots and prefiles of a saved VATSIM datafeed (v3) and prints the
+number of flight plans by the action shown for them, by rule and by departure airport.
+
+Options:
+  --rules DIR         Rules directory, settings are read from esfpc.json next to it [default: rules]
+  --format text|json  Output format [default: text]";
+
+pub fn run(args: impl IntoIterator<Item = String>) -> Result<u8, String> {
+    let args = Args::parse(args, &["rules", "format"])?;
+    if args.help {
+        println!("{USAGE}");
+        return Ok(0);
+    }
+    let json = match args.value("format").unwrap_or("text") {
+        "text" => false,
+        "json" => true,
+        format => return Err(format!("Unknown format {format}")),
+    };
+    let [path] = args.positional.as_slice() else {
+        return Err(USAGE.to_string());
+    };
+
+    let rules_dir = Path::new(args.value("rules").unwrap_or("rules"));
+    let checker = Checker::load(rules_dir)
+        .map_err(|e| format!("Failed to load {}: {e}", rules_dir.display()))?;
+    let input = std::fs::read_to_string(path).map_err(|e| format!("Failed to read {path}: {e}"))?;
+    let messages = datafeed::parse(&input).map_err(|e| format!("{path}: {e}"))?;
+
+    let mut summary = Summary::default();
+    for msg in &messages {
+        summary.add(&msg.flight_plan, &checker.findings(&msg.flight_plan));
+    }
+
+    if json {
+        let output = serde_json::to_string_pretty(&summary).map_err(|e| e.to_string())?;
+        println!("{output}");
+    } else {
+        print_summary(&summary);
+    }
+    Ok(0)
+}
+
+fn fmt_counts(counts: &Counts) -> String {
+    format!(
+        "{:>6} {:>8} {:>6} {:>8} {:>6} {:>7}",
+        counts.total(),
+        counts.success,
+        counts.info,
+        counts.warning,
+        counts.error,
+        counts.failed
+    )
+}
+
+fn print_summary(summary: &Summary) {
+    println!("{} flight plans", summary.total.total());
+    println!();
+
+    // Most frequent first
+    let mut rules: Vec<_> = summary.rules.iter().collect();
+    rules.sort_by(|(_, a), (_, b)| b.cmp(a));
+    println!("Rules:");
+    for (rule, count) in rules {
+        println!("  {count:>6}  {rule}");
+    }
+    println!();
+
+    let mut airports: Vec<_> = summary.airports.iter().collect();
+    airports.sort_by_key(|(_, counts)| std::cmp::Reverse(counts.total()));
+    println!(
+        "  {:<8}{:>6} {:>8} {:>6} {:>8} {:>6} {:>7}",
+        "Airport", "total", "success", "info", "warning", "error", "failed"
+    );
+    for (airport, counts) in airports {
+        println!("  {airport:<8}{}", fmt_counts(counts));
+    }
+    println!("  {:<8}{}", "", fmt_counts(&summary.total));
+}
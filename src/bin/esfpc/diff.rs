@@ -0,0 +1,68 @@
+//! `esfpc diff`: compare the findings of two rule sets for the same flight plans.
+
+use std::path::Path;
+
+use esfpc::Checker;
+
+use crate::check::{fmt_action, fmt_finding, label, parse_input, read_input, shown_action};
+use crate::Args;
+
+const USAGE: &str = "\
+Usage: esfpc diff OLD_RULES_DIR NEW_RULES_DIR FILE...
+
+Checks the flight plans in the files against both rule sets and prints every flight plan whose
+findings differ, with the action shown before and after, followed by the findings only of the old
+(-) and only of the new (+) rules. The files are read like by `esfpc check`.
+
+Exit codes:
+  0  no flight plan differs
+  1  a flight plan differs
+  4  invalid arguments, input or rules";
+
+pub fn run(args: impl IntoIterator<Item = String>) -> Result<u8, String> {
+    let args = Args::parse(args, &[])?;
+    if args.help {
+        println!("{USAGE}");
+        return Ok(0);
+    }
+    let [old_dir, new_dir, paths @ ..] = args.positional.as_slice() else {
+        return Err(USAGE.to_string());
+    };
+    if paths.is_empty() {
+        return Err(USAGE.to_string());
+    }
+    let load =
+        |dir: &str| Checker::load(Path::new(dir)).map_err(|e| format!("Failed to load {dir}: {e}"));
+    let old = load(old_dir)?;
+    let new = load(new_dir)?;
+
+    let mut flight_plans = vec![];
+    for path in paths {
+        let input = read_input(path).map_err(|e| format!("Failed to read {path}: {e}"))?;
+        flight_plans.extend(parse_input(&input).map_err(|e| format!("{path}: {e}"))?);
+    }
+
+    let mut changed = 0;
+    for (callsign, fp) in &flight_plans {
+        let before = old.findings(fp);
+        let after = new.findings(fp);
+        if before == after {
+            continue;
+        }
+        changed += 1;
+        println!(
+            "{}: {} -> {}",
+            label(callsign, fp),
+            fmt_action(&shown_action(&before)),
+            fmt_action(&shown_action(&after))
+        );
+        for finding in before.iter().filter(|f| !after.contains(f)) {
+            println!("  - {}", fmt_finding(finding));
+        }
+        for finding in after.iter().filter(|f| !before.contains(f)) {
+            println!("  + {}", fmt_finding(finding));
+        }
+    }
+    println!("{changed} of {} flight plans changed", flight_plans.len());
+    Ok(if changed > 0 { 1 } else { 0 })
+}
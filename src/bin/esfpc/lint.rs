@@ -0,0 +1,59 @@
+//! `esfpc lint`: find mistakes in rule files before they are used in EuroScope.
+
+use std::path::Path;
+
+use esfpc::lint::{self, Severity};
+
+use crate::Args;
+
+const USAGE: &str = "\
+Usage: esfpc lint [options] [RULES_DIR]
+
+Checks all rule files of the rules directory [default: rules] for parse and type errors, unknown
+identifiers and functions, duplicate rule names, messages too long for the tag, unreachable rules
+and unused tables.
+
+Options:
+  --format text|json  Output format [default: text]
+
+Exit codes:
+  0  no findings
+  1  only warnings
+  2  errors
+  4  invalid arguments or rules directory";
+
+pub fn run(args: impl IntoIterator<Item = String>) -> Result<u8, String> {
+    let args = Args::parse(args, &["format"])?;
+    if args.help {
+        println!("{USAGE}");
+        return Ok(0);
+    }
+    let json = match args.value("format").unwrap_or("text") {
+        "text" => false,
+        "json" => true,
+        format => return Err(format!("Unknown format {format}")),
+    };
+    let rules_dir = match args.positional.as_slice() {
+        [] => Path::new("rules"),
+        [dir] => Path::new(dir),
+        _ => return Err(USAGE.to_string()),
+    };
+
+    let diagnostics = lint::lint_dir(rules_dir)
+        .map_err(|e| format!("Failed to read {}: {e}", rules_dir.display()))?;
+    if json {
+        let output = serde_json::to_string_pretty(&diagnostics).map_err(|e| e.to_string())?;
+        println!("{output}");
+    } else {
+        for diagnostic in &diagnostics {
+            println!("{diagnostic}");
+        }
+    }
+
+    let code = match diagnostics.iter().map(|d| d.severity).max() {
+        Some(Severity::Error) => 2,
+        Some(Severity::Warning) => 1,
+        None => 0,
+    };
+    Ok(code)
+}
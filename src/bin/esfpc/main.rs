@@ -0,0 +1,110 @@
+//! Command-line interface to check flight plans without EuroScope.
+
+mod check;
+mod coverage;
+mod datafeed;
+mod diff;
+mod lint;
+mod repl;
+mod test;
+
+use std::process::ExitCode;
+
+const USAGE: &str = "\
+Usage: esfpc <command> [options]
+
+Commands:
+  check [FILE...]    Check flight plans from JSON files or ICAO FPL messages, `-` for stdin
+  coverage [FILE...] Find rules which never fire, are shadowed or overlap, also for the files
+  datafeed FILE      Check the flight plans of a saved VATSIM datafeed and summarize the findings
+  diff OLD NEW FILE  Compare the findings of two rules directories for the flight plans of files
+  lint [RULES_DIR]   Find mistakes in rule files, see `esfpc lint --help` for its exit codes
+  repl [FILE]        Evaluate expressions read from stdin against a flight plan
+  test [RULES_DIR]   Run the tests of rule files, see `esfpc test --help` for its exit codes
+
+Run `esfpc <command> --help` for the options of a command.
+
+Exit codes:
+  0  all flight plans are valid or only have infos
+  1  a flight plan has a warning
+  2  a flight plan has an error
+  3  a rule could not be evaluated
+  4  invalid arguments, input or rules";
+
+/// Exit code for invalid arguments, input or rules.
+const EXIT_USAGE: u8 = 4;
+
+/// Command-line arguments of a command, split into options with values and positional arguments.
+#[derive(Debug, Default)]
+struct Args {
+    options: Vec<(String, String)>,
+    positional: Vec<String>,
+    help: bool,
+}
+
+impl Args {
+    /// Every option takes a value, either as `--name value` or `--name=value`.
+    fn parse(args: impl IntoIterator<Item = String>, known: &[&str]) -> Result<Self, String> {
+        let mut parsed = Self::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            if arg == "-h" || arg == "--help" {
+                parsed.help = true;
+                continue;
+            }
+            let Some(option) = arg.strip_prefix("--") else {
+                parsed.positional.push(arg);
+                continue;
+            };
+            let (name, value) = match option.split_once('=') {
+                Some((name, value)) => (name.to_string(), value.to_string()),
+                None => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| format!("Missing value for --{option}"))?;
+                    (option.to_string(), value)
+                }
+            };
+            if !known.contains(&name.as_str()) {
+                return Err(format!("Unknown option --{name}"));
+            }
+            parsed.options.push((name, value));
+        }
+        Ok(parsed)
+    }
+
+    /// The last value of an option.
+    fn value(&self, name: &str) -> Option<&str> {
+        self.options
+            .iter()
+            .rev()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+    let result = match args.next().as_deref() {
+        Some("check") => check::run(args),
+        Some("coverage") => coverage::run(args),
+        Some("datafeed") => datafeed::run(args),
+        Some("diff") => diff::run(args),
+        Some("lint") => lint::run(args),
+        Some("repl") => repl::run(args),
+        Some("test") => test::run(args),
+        Some("-h" | "--help") => {
+            println!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        Some(command) => Err(format!("Unknown command {command}\n\n{USAGE}")),
+        None => Err(USAGE.to_string()),
+    };
+    match result {
+        Ok(code) => ExitCode::from(code),
+        Err(msg) => {
+            eprintln!("{msg}");
+            ExitCode::from(EXIT_USAGE)
+        }
+    }
+}
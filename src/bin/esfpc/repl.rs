@@ -0,0 +1,151 @@
+//! `esfpc repl`: evaluate expressions against a flight plan while writing rules.
+
+use std::io::{BufRead, IsTerminal, Write};
+use std::path::Path;
+
+use esfpc::{ffi, Checker};
+
+use crate::check::{
+    empty_flight_plan, parse_input, print_findings, print_traces, read_input, set_field, FIELDS,
+};
+use crate::Args;
+
+const USAGE: &str = "\
+Usage: esfpc repl [options] [FILE]
+
+Reads expressions from stdin and prints their value for a flight plan, which is the first one of
+the file, read like by `esfpc check`, or built from the options. The tables of all rule files can
+be used in expressions.
+
+Options:
+  --rules DIR  Rules directory, settings are read from esfpc.json next to it [default: rules]
+
+Flight plan fields, see `esfpc check --help`, e.g. --rfl FL350 or --ac-equip SDE2E3FGIJ1RWY/LB1
+
+Commands:
+  :set FIELD VALUE  Set a field like the options, e.g. `:set rfl 24000` or `:set dep-rwy 25C`
+  :fp               Print the flight plan as JSON
+  :rules            Print the findings for the flight plan, the first one being the action shown
+  :explain          Print the rules evaluated for the action shown
+  :load DIR         Load another rules directory
+  :help             Print this help
+  :quit             Exit, like the end of the input";
+
+pub fn run(args: impl IntoIterator<Item = String>) -> Result<u8, String> {
+    let known: Vec<&str> = ["rules"].iter().chain(FIELDS).copied().collect();
+    let args = Args::parse(args, &known)?;
+    if args.help {
+        println!("{USAGE}");
+        return Ok(0);
+    }
+
+    let mut checker = load(args.value("rules").unwrap_or("rules"))?;
+    let mut fp = match args.positional.as_slice() {
+        [] => empty_flight_plan(),
+        [path] => {
+            let input = read_input(path).map_err(|e| format!("Failed to read {path}: {e}"))?;
+            let flight_plans = parse_input(&input).map_err(|e| format!("{path}: {e}"))?;
+            match flight_plans.into_iter().next() {
+                Some((_, fp)) => fp,
+                None => return Err(format!("{path}: No flight plan")),
+            }
+        }
+        _ => return Err(USAGE.to_string()),
+    };
+    for (name, value) in &args.options {
+        set_field(&mut fp, name, value)?;
+    }
+
+    let stdin = std::io::stdin();
+    let interactive = stdin.is_terminal();
+    let mut lines = stdin.lock().lines();
+    loop {
+        if interactive {
+            print!("> ");
+            std::io::stdout().flush().map_err(|e| e.to_string())?;
+        }
+        let Some(line) = lines.next() else {
+            break;
+        };
+        let line = line.map_err(|e| format!("Failed to read stdin: {e}"))?;
+        let line = line.trim();
+        let Some(command) = line.strip_prefix(':') else {
+            if !line.is_empty() {
+                eval(&checker, &fp, line);
+            }
+            continue;
+        };
+        let (command, arg) = command.split_once(' ').unwrap_or((command, ""));
+        match command {
+            "set" => {
+                if let Err(e) = set(&mut fp, arg) {
+                    println!("error: {e}");
+                }
+            }
+            "fp" => print_flight_plan(&fp),
+            "rules" => {
+                print_findings("", &fp, &checker.findings(&fp));
+            }
+            "explain" => print_traces(&checker.explain(&fp)),
+            "load" => match load(arg.trim()) {
+                Ok(loaded) => checker = loaded,
+                Err(e) => println!("error: {e}"),
+            },
+            "help" => println!("{USAGE}"),
+            "quit" => break,
+            _ => println!("error: Unknown command :{command}, see :help"),
+        }
+    }
+    Ok(0)
+}
+
+fn load(dir: &str) -> Result<Checker, String> {
+    Checker::load(Path::new(dir)).map_err(|e| format!("Failed to load {dir}: {e}"))
+}
+
+/// Sets a field given as `FIELD VALUE`, where the field is named like the option, or like in JSON.
+fn set(fp: &mut ffi::FlightPlan, field: &str) -> Result<(), String> {
+    let field = field.trim();
+    let (name, value) = field.split_once(' ').unwrap_or((field, ""));
+    let name = name.replace('_', "-");
+    if !FIELDS.contains(&name.as_str()) {
+        return Err(format!("Unknown field {name}"));
+    }
+    set_field(fp, &name, value.trim())
+}
+
+fn print_flight_plan(fp: &ffi::FlightPlan) {
+    match serde_json::to_string_pretty(fp) {
+        Ok(json) => println!("{json}"),
+        Err(e) => println!("error: {e}"),
+    }
+}
+
+/// Prints the value of an expression, or the error with the part of the input it refers to:
+///
+/// ```text
+/// error: Got EOF but expected further token
+///   dep == 'EDDF' and
+///                    ^
+/// ```
+fn eval(checker: &Checker, fp: &ffi::FlightPlan, input: &str) {
+    let trace = match checker.eval(input, fp) {
+        Ok(trace) => trace,
+        Err(e) => {
+            let start = input[..e.span.start].chars().count();
+            let len = input[e.span.clone()].chars().count().max(1);
+            println!("error: {}", e.error);
+            println!("  {input}");
+            println!("  {}{}", " ".repeat(start), "^".repeat(len));
+            return;
+        }
+    };
+    match (&trace.value, trace.error()) {
+        (Ok(value), _) => println!("{value}"),
+        (Err(e), Some(failed)) if failed.expr != trace.expr => {
+            println!("error: {e}");
+            println!("  in {}", failed.expr);
+        }
+        (Err(e), _) => println!("error: {e}"),
+    }
+}
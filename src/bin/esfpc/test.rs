@@ -0,0 +1,52 @@
+//! `esfpc test`: run the test cases of the rule files.
+
+use std::path::Path;
+
+use esfpc::{rule_tests, Checker};
+
+use crate::Args;
+
+const USAGE: &str = "\
+Usage: esfpc test [options] [RULES_DIR]
+
+Runs the tests of all rule files of the rules directory [default: rules], which compare the first
+finding for a flight plan with the expected one, and prints the failed tests.
+
+Options:
+  --verbose true  Also print the passed tests
+
+Exit codes:
+  0  all tests passed
+  1  a test failed
+  4  invalid arguments or rules";
+
+pub fn run(args: impl IntoIterator<Item = String>) -> Result<u8, String> {
+    let args = Args::parse(args, &["verbose"])?;
+    if args.help {
+        println!("{USAGE}");
+        return Ok(0);
+    }
+    let verbose = match args.value("verbose") {
+        Some(value) => value
+            .parse()
+            .map_err(|_| format!("Invalid value for --verbose: {value}"))?,
+        None => false,
+    };
+    let rules_dir = match args.positional.as_slice() {
+        [] => Path::new("rules"),
+        [dir] => Path::new(dir),
+        _ => return Err(USAGE.to_string()),
+    };
+    let checker = Checker::load(rules_dir)
+        .map_err(|e| format!("Failed to load {}: {e}", rules_dir.display()))?;
+
+    let results = rule_tests::run(&checker);
+    let failed = results.iter().filter(|result| !result.passed()).count();
+    for result in &results {
+        if verbose || !result.passed() {
+            println!("{result}");
+        }
+    }
+    println!("{} passed, {failed} failed", results.len() - failed);
+    Ok(if failed > 0 { 1 } else { 0 })
+}
@@ -0,0 +1,437 @@
+//! Built-in checks, which are common enough to not be re-written as rules per airport.
+
+use crate::aircraft::AircraftDatabase;
+use crate::config::{self, Action};
+use crate::ffi;
+use crate::geo;
+use crate::item18::Item18;
+use crate::levels;
+use crate::navdata::NavDatabase;
+use crate::route::Route;
+
+/// Run all enabled built-in checks and return the action of the first failing one.
+///
+/// Checks which need a navigation or aircraft database are skipped if there is none.
+pub fn check(
+    checks: &config::Checks,
+    fp: &ffi::FlightPlan,
+    navdata: Option<&NavDatabase>,
+    aircraft: Option<&AircraftDatabase>,
+) -> Option<Action> {
+    check_all(checks, fp, navdata, aircraft)
+        .into_iter()
+        .next()
+        .map(|failure| failure.action)
+}
+
+/// A failing built-in check.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Failure {
+    /// Name of the check in the config, e.g. `rvsm`.
+    pub name: &'static str,
+    pub action: Action,
+    /// What failed, e.g. the unknown points, which would not fit into the tag item.
+    pub detail: Option<String>,
+}
+
+impl Failure {
+    fn new(name: &'static str, action: Action) -> Self {
+        Self {
+            name,
+            action,
+            detail: None,
+        }
+    }
+}
+
+/// Run all enabled built-in checks and return all failing ones.
+pub fn check_all(
+    checks: &config::Checks,
+    fp: &ffi::FlightPlan,
+    navdata: Option<&NavDatabase>,
+    aircraft: Option<&AircraftDatabase>,
+) -> Vec<Failure> {
+    let mut failures = vec![];
+    if let Some(action) = checks.rvsm.as_ref().and_then(|rvsm| check_rvsm(rvsm, fp)) {
+        failures.push(Failure::new("rvsm", action));
+    }
+    if let (Some(check), Some(navdata)) = (&checks.unknown_points, navdata) {
+        if let Some(unknown) = check_unknown_points(fp, navdata) {
+            failures.push(Failure {
+                detail: Some(unknown),
+                ..Failure::new("unknown_points", check.action.clone())
+            });
+        }
+    }
+    if let (Some(check), Some(navdata)) = (&checks.airways, navdata) {
+        if let Some(segment) = check_airways(fp, navdata) {
+            failures.push(Failure {
+                detail: Some(segment),
+                ..Failure::new("airways", check.action.clone())
+            });
+        }
+    }
+    if let (Some(check), Some(navdata)) = (&checks.detour, navdata) {
+        if let Some(action) = check_detour(check, fp, navdata) {
+            failures.push(Failure::new("detour", action));
+        }
+    }
+    if let (Some(check), Some(aircraft)) = (&checks.ceiling, aircraft) {
+        if let Some(action) = check_ceiling(check, fp, aircraft) {
+            failures.push(Failure::new("ceiling", action));
+        }
+    }
+    for check in &checks.short_hops {
+        if let Some(action) = check_short_hop(check, fp, navdata) {
+            failures.push(Failure::new("short_hops", action));
+        }
+    }
+    failures
+}
+
+fn check_rvsm(check: &config::RvsmCheck, fp: &ffi::FlightPlan) -> Option<Action> {
+    if fp.ac.is_rvsm_capable || !levels::is_in_rvsm_band(fp.rfl) {
+        return None;
+    }
+    let sts = Item18::parse(&fp.remarks).sts();
+    if sts.iter().any(|reason| check.exempt_sts.contains(reason)) {
+        return None;
+    }
+    Some(check.action.clone())
+}
+
+/// The unknown points and airways, e.g. `CINDI Z47`.
+fn check_unknown_points(fp: &ffi::FlightPlan, navdata: &NavDatabase) -> Option<String> {
+    let route = Route::parse(&fp.route);
+    let mut unknown = navdata.unknown_points(&route);
+    unknown.extend(navdata.unknown_airways(&route));
+    (!unknown.is_empty()).then(|| unknown.join(" "))
+}
+
+/// The first broken airway segment, e.g. `CINDY Z74 ROKIL`.
+fn check_airways(fp: &ffi::FlightPlan, navdata: &NavDatabase) -> Option<String> {
+    let segment = navdata.broken_segment(&Route::parse(&fp.route))?;
+    Some(segment.to_string())
+}
+
+/// Unknown aircraft types are not checked.
+fn check_ceiling(
+    check: &config::CeilingCheck,
+    fp: &ffi::FlightPlan,
+    aircraft: &AircraftDatabase,
+) -> Option<Action> {
+    let typ = aircraft.get(&fp.ac.icao)?;
+    (fp.rfl > typ.ceiling).then(|| check.action.clone())
+}
+
+fn check_detour(
+    check: &config::DetourCheck,
+    fp: &ffi::FlightPlan,
+    navdata: &NavDatabase,
+) -> Option<Action> {
+    let coords = navdata.route_coords(&fp.dep, &Route::parse(&fp.route), &fp.arr)?;
+    let direct = geo::distance_nm(coords[0], coords[coords.len() - 1]);
+    if direct == 0.0 {
+        return None;
+    }
+    (geo::path_distance_nm(&coords) / direct > check.max_ratio).then(|| check.action.clone())
+}
+
+/// Without a navigation database, checks with a maximum distance are skipped.
+fn check_short_hop(
+    check: &config::ShortHopCheck,
+    fp: &ffi::FlightPlan,
+    navdata: Option<&NavDatabase>,
+) -> Option<Action> {
+    if fp.rfl <= check.max_rfl
+        || check.dep.as_ref().is_some_and(|dep| *dep != fp.dep)
+        || check.arr.as_ref().is_some_and(|arr| *arr != fp.arr)
+    {
+        return None;
+    }
+    if let Some(max_distance) = check.max_distance_nm {
+        let dep = navdata?.airport(&fp.dep)?.coord;
+        let arr = navdata?.airport(&fp.arr)?.coord;
+        if geo::distance_nm(dep, arr) > max_distance {
+            return None;
+        }
+    }
+    Some(check.action.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ActionType;
+
+    fn rvsm_checks() -> config::Checks {
+        serde_json::from_str(r#"{ "rvsm": {} }"#).unwrap()
+    }
+
+    fn fp(is_rvsm_capable: bool, rfl: u32, remarks: &str) -> ffi::FlightPlan {
+        ffi::FlightPlan {
+            ac: ffi::Aircraft {
+                is_rvsm_capable,
+                ..Default::default()
+            },
+            rfl,
+            remarks: remarks.into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn rvsm() {
+        let checks = rvsm_checks();
+        let error = Some(Action::new(ActionType::Error, "RVSM".into()));
+        assert_eq!(error, check(&checks, &fp(false, 35000, ""), None, None));
+        assert_eq!(
+            error,
+            check(&checks, &fp(false, 29000, "STS/HOSP"), None, None)
+        );
+        assert_eq!(None, check(&checks, &fp(true, 35000, ""), None, None));
+        assert_eq!(None, check(&checks, &fp(false, 28000, ""), None, None));
+        assert_eq!(None, check(&checks, &fp(false, 43000, ""), None, None));
+        assert_eq!(
+            None,
+            check(&checks, &fp(false, 35000, "STS/NONRVSM"), None, None)
+        );
+        assert_eq!(
+            None,
+            check(&checks, &fp(false, 35000, "STS/HOSP STATE"), None, None)
+        );
+    }
+
+    #[test]
+    fn rvsm_configured() {
+        let checks: config::Checks = serde_json::from_str(
+            r#"{ "rvsm": { "exempt_sts": ["HOSP"], "action": { "typ": "warning", "msg": "NONRVSM" } } }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            Some(Action::new(ActionType::Warning, "NONRVSM".into())),
+            check(&checks, &fp(false, 35000, "STS/STATE"), None, None)
+        );
+        assert_eq!(
+            None,
+            check(&checks, &fp(false, 35000, "STS/HOSP"), None, None)
+        );
+    }
+
+    #[test]
+    fn disabled() {
+        let checks = config::Checks::default();
+        assert_eq!(None, check(&checks, &fp(false, 35000, ""), None, None));
+    }
+
+    #[test]
+    fn unknown_points() {
+        let checks: config::Checks = serde_json::from_str(r#"{ "unknown_points": {} }"#).unwrap();
+        let mut navdata = NavDatabase::default();
+        crate::navdata::sector::parse_sct(
+            "[FIXES]\nCINDY N049.48.00.000 E008.58.00.000\nHAREM N049.40.00.000 E009.30.00.000\nROKIL N049.25.00.000 E010.20.00.000\n[HIGH AIRWAY]\nT104 HAREM HAREM ROKIL ROKIL\n",
+            &mut navdata,
+        );
+        let fp = |route: &str| ffi::FlightPlan {
+            route: route.into(),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            None,
+            check(
+                &checks,
+                &fp("CINDY DCT HAREM T104 ROKIL"),
+                Some(&navdata),
+                None
+            )
+        );
+        let failures = check_all(
+            &checks,
+            &fp("CINDI Z74 HAREM T104 ROKIL"),
+            Some(&navdata),
+            None,
+        );
+        assert_eq!(
+            vec![Failure {
+                detail: Some("CINDI Z74".into()),
+                ..Failure::new(
+                    "unknown_points",
+                    Action::new(ActionType::Error, "PNT".into())
+                )
+            }],
+            failures
+        );
+        // Skipped without navigation database
+        assert_eq!(None, check(&checks, &fp("CINDI Z74 HAREM"), None, None));
+    }
+
+    #[test]
+    fn airways() {
+        let checks: config::Checks = serde_json::from_str(r#"{ "airways": {} }"#).unwrap();
+        let mut navdata = NavDatabase::default();
+        crate::navdata::airways::parse_csv("T104,HAREM,ROKIL\nZ74,CINDY,HAREM,F\n", &mut navdata);
+        let fp = |route: &str| ffi::FlightPlan {
+            route: route.into(),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            None,
+            check(
+                &checks,
+                &fp("CINDY Z74 HAREM T104 ROKIL"),
+                Some(&navdata),
+                None
+            )
+        );
+        let failure = |segment: &str| {
+            vec![Failure {
+                detail: Some(segment.into()),
+                ..Failure::new("airways", Action::new(ActionType::Error, "AWY".into()))
+            }]
+        };
+        assert_eq!(
+            failure("CINDY Z74 ROKIL"),
+            check_all(
+                &checks,
+                &fp("CINDY Z74 ROKIL T104 HAREM"),
+                Some(&navdata),
+                None
+            )
+        );
+        assert_eq!(
+            failure("HAREM Z74 CINDY"),
+            check_all(
+                &checks,
+                &fp("ROKIL T104 HAREM Z74 CINDY"),
+                Some(&navdata),
+                None
+            )
+        );
+    }
+
+    #[test]
+    fn ceiling() {
+        let checks: config::Checks = serde_json::from_str(r#"{ "ceiling": {} }"#).unwrap();
+        let mut aircraft = AircraftDatabase::default();
+        aircraft.parse_csv("AT76,25000,275,B,E\n");
+        let fp = |icao: &str, rfl: u32| ffi::FlightPlan {
+            ac: ffi::Aircraft {
+                icao: icao.into(),
+                ..Default::default()
+            },
+            rfl,
+            ..Default::default()
+        };
+
+        let warning = Some(Action::new(ActionType::Warning, "CEIL".into()));
+        assert_eq!(
+            None,
+            check(&checks, &fp("AT76", 25000), None, Some(&aircraft))
+        );
+        assert_eq!(
+            warning,
+            check(&checks, &fp("AT76", 26000), None, Some(&aircraft))
+        );
+        assert_eq!(
+            None,
+            check(&checks, &fp("B738", 45000), None, Some(&aircraft))
+        );
+        assert_eq!(None, check(&checks, &fp("AT76", 26000), None, None));
+    }
+
+    #[test]
+    fn all_failing() {
+        let checks: config::Checks =
+            serde_json::from_str(r#"{ "rvsm": {}, "ceiling": {} }"#).unwrap();
+        let mut aircraft = AircraftDatabase::default();
+        aircraft.parse_csv("A320,39000,450,C,D\n");
+
+        let failures = check_all(&checks, &fp(false, 41000, ""), None, Some(&aircraft));
+        assert_eq!(
+            vec![
+                Failure::new("rvsm", Action::new(ActionType::Error, "RVSM".into())),
+                Failure::new("ceiling", Action::new(ActionType::Warning, "CEIL".into())),
+            ],
+            failures
+        );
+        assert!(check_all(&checks, &fp(true, 35000, ""), None, Some(&aircraft)).is_empty());
+    }
+
+    fn airports() -> NavDatabase {
+        let mut navdata = NavDatabase::default();
+        crate::navdata::sector::parse_sct(
+            "[AIRPORT]\nEDDF 118.700 N050.01.59.000 E008.34.14.000 D\nEDDS 118.805 N048.41.24.000 E009.13.19.000 D\nEDDM 118.700 N048.21.14.000 E011.47.10.000 D\n[FIXES]\nROKIL N049.25.00.000 E010.20.00.000\nSOBRA N050.20.00.000 E007.50.00.000\n",
+            &mut navdata,
+        );
+        navdata
+    }
+
+    #[test]
+    fn detour() {
+        let checks: config::Checks = serde_json::from_str(r#"{ "detour": {} }"#).unwrap();
+        let navdata = airports();
+        let fp = |route: &str, arr: &str| ffi::FlightPlan {
+            dep: "EDDF".into(),
+            arr: arr.into(),
+            route: route.into(),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            None,
+            check(&checks, &fp("ROKIL", "EDDM"), Some(&navdata), None)
+        );
+        assert_eq!(
+            Some(Action::new(ActionType::Warning, "RTE".into())),
+            check(&checks, &fp("SOBRA ROKIL", "EDDS"), Some(&navdata), None)
+        );
+        // Unknown destination
+        assert_eq!(
+            None,
+            check(&checks, &fp("SOBRA ROKIL", "LSZH"), Some(&navdata), None)
+        );
+        assert_eq!(None, check(&checks, &fp("SOBRA ROKIL", "EDDS"), None, None));
+    }
+
+    #[test]
+    fn short_hops() {
+        let checks: config::Checks = serde_json::from_str(
+            r#"{ "short_hops": [
+                { "dep": "EDDF", "arr": "EDDS", "max_rfl": 24000 },
+                { "max_distance_nm": 200, "max_rfl": 30000, "action": { "typ": "warning", "msg": "SHORT" } }
+            ] }"#,
+        )
+        .unwrap();
+        let navdata = airports();
+        let fp = |arr: &str, rfl: u32| ffi::FlightPlan {
+            dep: "EDDF".into(),
+            arr: arr.into(),
+            rfl,
+            ..Default::default()
+        };
+
+        let error = Some(Action::new(ActionType::Error, "RFL".into()));
+        let warning = Some(Action::new(ActionType::Warning, "SHORT".into()));
+        assert_eq!(
+            None,
+            check(&checks, &fp("EDDS", 24000), Some(&navdata), None)
+        );
+        assert_eq!(
+            error,
+            check(&checks, &fp("EDDS", 25000), Some(&navdata), None)
+        );
+        assert_eq!(
+            None,
+            check(&checks, &fp("EDDM", 30000), Some(&navdata), None)
+        );
+        assert_eq!(
+            warning,
+            check(&checks, &fp("EDDM", 31000), Some(&navdata), None)
+        );
+        // Airport pairs are checked without navigation database, distances are not
+        assert_eq!(error, check(&checks, &fp("EDDS", 25000), None, None));
+        assert_eq!(None, check(&checks, &fp("EDDM", 31000), None, None));
+    }
+}
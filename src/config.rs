@@ -1,18 +1,302 @@
-use serde::{Deserialize, Deserializer};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::decision_table;
+use crate::ffi;
 use crate::lang::ast;
 use crate::lang::parser;
+use crate::lang::types;
+use crate::vfpc;
+
+/// Plugin settings, read from `esfpc.json` next to the rules directory.
+#[derive(Default, Deserialize)]
+pub struct Settings {
+    /// EuroScope `.sct` and `.ese` files, relative to the settings file.
+    #[serde(default)]
+    pub sector_files: Vec<PathBuf>,
+    /// Aircraft performance `.csv` and `.json` files, relative to the settings file.
+    #[serde(default)]
+    pub aircraft_files: Vec<PathBuf>,
+    /// Magnetic variation in degrees, positive for east. Overrides the sector file.
+    pub magnetic_variation: Option<f64>,
+}
 
 #[derive(Deserialize)]
 pub struct Config {
+    #[serde(default)]
+    pub checks: Checks,
+    /// Lookup tables, which rules of this file can index, e.g. `max_rfl[arr]`.
+    #[serde(default, deserialize_with = "deserialize_tables")]
+    pub tables: HashMap<String, ast::Table>,
     pub rules: Vec<Rule>,
+    /// Files with a higher priority are checked first, see [`sort`].
+    #[serde(default)]
+    pub priority: i32,
+    /// Flight plans with the finding expected for them, run by `esfpc test`.
+    #[serde(default)]
+    pub tests: Vec<Test>,
+}
+
+impl Config {
+    /// Load a rule file, either `.json`, `.yaml`, `.toml`, a `.csv` decision table or a VFPC
+    /// `Sid.json` file.
+    pub fn load(path: &Path) -> Result<Self, std::io::Error> {
+        let invalid = |e: &dyn std::fmt::Display| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("{}: {}", path.display(), e),
+            )
+        };
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase());
+        match extension.as_deref() {
+            Some("json") => {
+                let input = std::fs::read_to_string(path)?;
+                match vfpc::is_sid_json(&input) {
+                    true => vfpc::parse(&input).map_err(|e| invalid(&e)),
+                    false => serde_json::from_str(&input).map_err(|e| invalid(&e)),
+                }
+            }
+            Some("yaml" | "yml") => {
+                let file = std::fs::File::open(path)?;
+                let reader = std::io::BufReader::new(file);
+                serde_norway::from_reader(reader).map_err(|e| invalid(&e))
+            }
+            Some("toml") => {
+                let input = std::fs::read_to_string(path)?;
+                toml::from_str(&input).map_err(|e| invalid(&e))
+            }
+            Some("csv") => {
+                let input = std::fs::read_to_string(path)?;
+                decision_table::parse(&input).map_err(|e| invalid(&e))
+            }
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("Unknown rule file type: {}", path.display()),
+            )),
+        }
+    }
+
+    /// The rules with their index in the file, in the order they are checked: rules with a higher
+    /// priority first, rules with the same priority in the order of the file.
+    pub fn ordered_rules(&self) -> Vec<(usize, &Rule)> {
+        let mut rules: Vec<_> = self.rules.iter().enumerate().collect();
+        // Stable, so rules with the same priority keep their order
+        rules.sort_by_key(|(_, rule)| std::cmp::Reverse(rule.priority));
+        rules
+    }
+}
+
+/// Rule files of a rules directory, sorted by path, which does not depend on the file system.
+pub fn rule_files(rules_dir: &Path) -> Result<Vec<PathBuf>, std::io::Error> {
+    let mut files = vec![];
+    for entry in rules_dir.read_dir()? {
+        files.push(entry?.path());
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Sorts rule files into the order they are checked: files with a higher priority first, files
+/// with the same priority by path like [`rule_files`]. Files without a priority have priority 0.
+pub fn sort(configs: &mut [(PathBuf, Config)]) {
+    configs.sort_by(|(a_path, a), (b_path, b)| {
+        b.priority.cmp(&a.priority).then_with(|| a_path.cmp(b_path))
+    });
+}
+
+/// Value of a table entry.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TableValue {
+    Bool(bool),
+    Int(i64),
+    Text(String),
+    Array(Vec<TableValue>),
+}
+
+impl From<TableValue> for ast::Expr {
+    fn from(value: TableValue) -> Self {
+        match value {
+            TableValue::Bool(b) => ast::Expr::Lit(ast::Lit::Bool(b)),
+            TableValue::Int(i) => ast::Expr::Lit(ast::Lit::Int(i)),
+            TableValue::Text(s) => ast::Expr::Lit(ast::Lit::Text(s)),
+            TableValue::Array(values) => {
+                ast::Expr::Array(values.into_iter().map(Self::from).collect())
+            }
+        }
+    }
+}
+
+fn deserialize_tables<'de, D>(deserializer: D) -> Result<HashMap<String, ast::Table>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let tables = HashMap::<String, HashMap<String, TableValue>>::deserialize(deserializer)?;
+    // E.g. a table `rfl` would shadow the identifier in `rfl[arr]` and `arr in rfl`, but not in
+    // `rfl > 24000`
+    if let Some(name) = tables.keys().find(|name| types::ident_type(name).is_some()) {
+        return Err(serde::de::Error::custom(format!(
+            "Table {name} is named like a built-in identifier"
+        )));
+    }
+    Ok(tables
+        .into_iter()
+        .map(|(name, table)| {
+            let table = table.into_iter().map(|(k, v)| (k, v.into())).collect();
+            (name, table)
+        })
+        .collect())
+}
+
+/// Built-in checks, which are evaluated before any rule.
+#[derive(Default, Deserialize)]
+pub struct Checks {
+    pub rvsm: Option<RvsmCheck>,
+    pub unknown_points: Option<UnknownPointsCheck>,
+    pub airways: Option<AirwayCheck>,
+    pub detour: Option<DetourCheck>,
+    pub ceiling: Option<CeilingCheck>,
+    #[serde(default)]
+    pub short_hops: Vec<ShortHopCheck>,
+}
+
+impl Checks {
+    /// Actions of the enabled checks, with the name of the check.
+    pub fn actions(&self) -> Vec<(&'static str, &Action)> {
+        let mut actions = vec![];
+        if let Some(check) = &self.rvsm {
+            actions.push(("rvsm", &check.action));
+        }
+        if let Some(check) = &self.unknown_points {
+            actions.push(("unknown_points", &check.action));
+        }
+        if let Some(check) = &self.airways {
+            actions.push(("airways", &check.action));
+        }
+        if let Some(check) = &self.detour {
+            actions.push(("detour", &check.action));
+        }
+        if let Some(check) = &self.ceiling {
+            actions.push(("ceiling", &check.action));
+        }
+        for check in &self.short_hops {
+            actions.push(("short_hops", &check.action));
+        }
+        actions
+    }
+}
+
+/// Flags non-RVSM aircraft filed within the RVSM band (FL290 - FL410).
+#[derive(Deserialize)]
+pub struct RvsmCheck {
+    /// Item 18 STS/ reasons which exempt a flight from the check.
+    #[serde(default = "default_rvsm_exempt_sts")]
+    pub exempt_sts: Vec<String>,
+    #[serde(default = "default_rvsm_action")]
+    pub action: Action,
+}
+
+/// Flags route points and airways which are not in the navigation database.
+///
+/// The unknown names are reported with the finding, not in the action message, which is shown in
+/// the tag.
+#[derive(Deserialize)]
+pub struct UnknownPointsCheck {
+    #[serde(default = "default_unknown_points_action")]
+    pub action: Action,
+}
+
+/// Flags routes which are much longer than the direct distance between the airports.
+#[derive(Deserialize)]
+pub struct DetourCheck {
+    /// Maximum ratio of route distance to direct distance.
+    #[serde(default = "default_detour_max_ratio")]
+    pub max_ratio: f64,
+    #[serde(default = "default_detour_action")]
+    pub action: Action,
+}
+
+/// Flags flights between nearby airports which are filed above a maximum level.
+///
+/// The check applies if the departure and destination match, if given, and the direct distance
+/// is at most `max_distance_nm`, if given.
+#[derive(Deserialize)]
+pub struct ShortHopCheck {
+    pub dep: Option<String>,
+    pub arr: Option<String>,
+    pub max_distance_nm: Option<f64>,
+    /// Maximum requested flight level in feet.
+    pub max_rfl: u32,
+    #[serde(default = "default_short_hop_action")]
+    pub action: Action,
+}
+
+/// Flags flights filed above the service ceiling of their aircraft type.
+#[derive(Deserialize)]
+pub struct CeilingCheck {
+    #[serde(default = "default_ceiling_action")]
+    pub action: Action,
+}
+
+fn default_ceiling_action() -> Action {
+    Action::new(ActionType::Warning, "CEIL".into())
+}
+
+fn default_detour_max_ratio() -> f64 {
+    1.5
+}
+
+fn default_detour_action() -> Action {
+    Action::new(ActionType::Warning, "RTE".into())
+}
+
+fn default_short_hop_action() -> Action {
+    Action::new(ActionType::Error, "RFL".into())
+}
+
+/// Flags the first airway segment of the route which is not entered and left at connected points
+/// on that airway.
+///
+/// The segment is reported with the finding, not in the action message.
+#[derive(Deserialize)]
+pub struct AirwayCheck {
+    #[serde(default = "default_airway_action")]
+    pub action: Action,
+}
+
+fn default_airway_action() -> Action {
+    Action::new(ActionType::Error, "AWY".into())
+}
+
+fn default_unknown_points_action() -> Action {
+    Action::new(ActionType::Error, "PNT".into())
+}
+
+fn default_rvsm_exempt_sts() -> Vec<String> {
+    vec!["NONRVSM".into(), "STATE".into()]
+}
+
+fn default_rvsm_action() -> Action {
+    Action::new(ActionType::Error, "RVSM".into())
 }
 
 #[derive(Deserialize)]
 pub struct Rule {
+    /// Describes the rule in findings, e.g. `CINDY destination`.
+    #[serde(default)]
+    pub name: Option<String>,
     #[serde(deserialize_with = "deserialize_condition")]
     pub condition: ast::Expr,
     pub action: Action,
+    /// Rules with a higher priority are checked before the other rules of the file, see
+    /// [`Config::ordered_rules`].
+    #[serde(default)]
+    pub priority: i32,
 }
 
 fn deserialize_condition<'de, D>(deserializer: D) -> Result<ast::Expr, D::Error>
@@ -20,10 +304,31 @@ where
     D: Deserializer<'de>,
 {
     let s = String::deserialize(deserializer)?;
-    parser::parse(s).map_err(serde::de::Error::custom)
+    parser::parse_spanned(&s)
+        .map_err(|e| serde::de::Error::custom(format!("{e} in condition `{s}`")))
 }
 
-#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+/// Test case of a rule file, see [`crate::rule_tests`].
+#[derive(Deserialize)]
+pub struct Test {
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Fields of the flight plan, the others are empty or unknown.
+    pub fp: ffi::FlightPlan,
+    pub expect: Expected,
+}
+
+/// Expected first finding of a flight plan, which is the action shown in EuroScope.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Expected {
+    pub typ: ActionType,
+    pub msg: String,
+    /// Name of the rule or check, which is not compared if omitted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
 pub struct Action {
     pub typ: ActionType,
     pub msg: String,
@@ -35,7 +340,7 @@ impl Action {
     }
 }
 
-#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 pub enum ActionType {
     Error,
@@ -43,3 +348,15 @@ pub enum ActionType {
     Info,
     Success,
 }
+
+/// The name used in rule files, e.g. `error`.
+impl std::fmt::Display for ActionType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad(match self {
+            Self::Success => "success",
+            Self::Info => "info",
+            Self::Warning => "warning",
+            Self::Error => "error",
+        })
+    }
+}
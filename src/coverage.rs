@@ -0,0 +1,348 @@
+//! Which rules fire, as only the first matching rule decides the action shown: rules which never
+//! fire, rules shadowed by earlier ones and rules whose conditions overlap with an earlier rule with
+//! a different action.
+//!
+//! The conditions are compared symbolically, which only understands simple comparisons like
+//! `rfl > 24000` or `arr in ['EDDM', 'EDDN']`, and checked against a corpus of flight plans, if
+//! there is one.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::config::Rule;
+use crate::lang::ast;
+use crate::lang::constraints::Constraints;
+use crate::lint::{Diagnostic, Severity};
+use crate::{checks, ffi, Checker};
+
+/// How often a rule matched the flight plans of the corpus.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RuleCoverage {
+    pub file: PathBuf,
+    /// 1-based number of the rule in its file.
+    pub rule: usize,
+    pub name: Option<String>,
+    /// Flight plans for which the condition is true.
+    pub matched: usize,
+    /// Flight plans for which the rule decided the action shown.
+    pub decided: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Coverage {
+    /// Number of flight plans of the corpus.
+    pub flight_plans: usize,
+    /// All rules in the order they are checked, without counts if there is no corpus.
+    pub rules: Vec<RuleCoverage>,
+    /// Warnings with the codes `never-fires`, `shadowed` and `overlap`.
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// A rule with its file and number, in the order they are checked.
+struct Entry<'a> {
+    /// Index of the config, whose tables the rule uses.
+    config: usize,
+    file: &'a Path,
+    number: usize,
+    rule: &'a Rule,
+    constraints: Constraints<'a>,
+}
+
+impl Entry<'_> {
+    /// Whether every flight plan matching this rule matches `other`.
+    fn implies(&self, other: &Entry) -> bool {
+        self.constraints
+            .implies(&other.constraints, self.config == other.config)
+    }
+
+    fn diagnostic(&self, code: &'static str, msg: String) -> Diagnostic {
+        Diagnostic {
+            file: self.file.to_path_buf(),
+            rule: Some(self.number),
+            name: self.rule.name.clone(),
+            severity: Severity::Warning,
+            code,
+            msg,
+        }
+    }
+
+    /// `rule 2 (CINDY destination) in rules/eddf.json`
+    fn describe(&self) -> String {
+        match &self.rule.name {
+            Some(name) => format!("rule {} ({}) in {}", self.number, name, self.file.display()),
+            None => format!("rule {} in {}", self.number, self.file.display()),
+        }
+    }
+}
+
+/// Analyzes the rules of a checker symbolically and, if there are any flight plans, by checking
+/// them. A rule is reported at most once per code, findings of the symbolic analysis first.
+pub fn analyze(checker: &Checker, flight_plans: &[ffi::FlightPlan]) -> Coverage {
+    let mut entries = vec![];
+    let mut tables = vec![];
+    for (index, (config, file)) in checker.configs().iter().zip(checker.files()).enumerate() {
+        for (i, rule) in config.ordered_rules() {
+            entries.push(Entry {
+                config: index,
+                file,
+                number: i + 1,
+                rule,
+                constraints: Constraints::new(&rule.condition),
+            });
+            tables.push(&config.tables);
+        }
+    }
+
+    let mut diagnostics = vec![];
+    // Reported rules and pairs of rules, as indices of the entries
+    let mut never_fires = vec![false; entries.len()];
+    let mut shadowed = vec![false; entries.len()];
+    let mut overlaps = BTreeSet::new();
+    for (i, entry) in entries.iter().enumerate() {
+        if !entry.constraints.can_be_true() {
+            never_fires[i] = true;
+            diagnostics.push(entry.diagnostic("never-fires", "Condition is always false".into()));
+            continue;
+        }
+        let earlier = &entries[..i];
+        if let Some(other) = earlier.iter().find(|other| entry.implies(other)) {
+            shadowed[i] = true;
+            diagnostics.push(entry.diagnostic(
+                "shadowed",
+                format!(
+                    "Every flight plan matching it matches {} first",
+                    other.describe()
+                ),
+            ));
+            continue;
+        }
+        for (j, other) in earlier.iter().enumerate() {
+            if conflicts(other, entry)
+                && entry.constraints.overlaps(&other.constraints) == Some(true)
+            {
+                overlaps.insert((j, i));
+                diagnostics.push(entry.diagnostic(
+                    "overlap",
+                    format!(
+                        "Some flight plans match it and {}, which has a different action",
+                        other.describe()
+                    ),
+                ));
+            }
+        }
+    }
+
+    let mut rules: Vec<RuleCoverage> = entries
+        .iter()
+        .map(|entry| RuleCoverage {
+            file: entry.file.to_path_buf(),
+            rule: entry.number,
+            name: entry.rule.name.clone(),
+            matched: 0,
+            decided: 0,
+        })
+        .collect();
+    if flight_plans.is_empty() {
+        return Coverage {
+            flight_plans: 0,
+            rules,
+            diagnostics,
+        };
+    }
+
+    // Flight plans matching both rules of a pair with different actions, by the indices of the
+    // entries
+    let mut corpus_overlaps: BTreeMap<(usize, usize), usize> = BTreeMap::new();
+    for fp in flight_plans {
+        let checked = checker.configs().iter().any(|config| {
+            checks::check(
+                &config.checks,
+                fp,
+                checker.loaded_navdata(),
+                checker.loaded_aircraft(),
+            )
+            .is_some()
+        });
+        let mut decided = checked;
+        let mut matched = vec![];
+        for (i, entry) in entries.iter().enumerate() {
+            let ctx = ast::Context {
+                navdata: checker.loaded_navdata(),
+                aircraft: checker.loaded_aircraft(),
+                tables: Some(tables[i]),
+            };
+            let result = ast::eval_cond(&entry.rule.condition, fp, &ctx);
+            if !decided && !matches!(result, Ok(false)) {
+                decided = true;
+                rules[i].decided += 1;
+            }
+            if result == Ok(true) {
+                rules[i].matched += 1;
+                matched.push(i);
+            }
+        }
+        for (n, &i) in matched.iter().enumerate() {
+            for &j in &matched[..n] {
+                if conflicts(&entries[j], &entries[i]) {
+                    *corpus_overlaps.entry((j, i)).or_default() += 1;
+                }
+            }
+        }
+    }
+
+    for (i, (entry, coverage)) in entries.iter().zip(&rules).enumerate() {
+        if never_fires[i] || shadowed[i] {
+            continue;
+        }
+        if coverage.matched == 0 {
+            diagnostics.push(entry.diagnostic(
+                "never-fires",
+                format!("Matches none of the {} flight plans", flight_plans.len()),
+            ));
+        } else if coverage.decided == 0 {
+            diagnostics.push(entry.diagnostic(
+                "shadowed",
+                format!(
+                    "Matches {} flight plans, but earlier checks or rules match all of them first",
+                    coverage.matched
+                ),
+            ));
+        }
+    }
+    for ((j, i), count) in corpus_overlaps {
+        if overlaps.contains(&(j, i)) || shadowed[i] {
+            continue;
+        }
+        diagnostics.push(entries[i].diagnostic(
+            "overlap",
+            format!(
+                "{} flight plans match it and {}, which has a different action",
+                count,
+                entries[j].describe()
+            ),
+        ));
+    }
+
+    Coverage {
+        flight_plans: flight_plans.len(),
+        rules,
+        diagnostics,
+    }
+}
+
+/// Whether an overlap of the rules is worth reporting, as they have different actions and the
+/// later one is not a more general fallback of the earlier one, e.g. `true`.
+fn conflicts(earlier: &Entry, later: &Entry) -> bool {
+    earlier.rule.action != later.rule.action && !earlier.implies(later)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    fn checker(rules: &str) -> Checker {
+        checker_files(&[rules])
+    }
+
+    /// Checker with rule files named a.json, b.json, ...
+    fn checker_files(files: &[&str]) -> Checker {
+        Checker {
+            configs: files
+                .iter()
+                .map(|rules| serde_json::from_str::<Config>(rules).unwrap())
+                .collect(),
+            files: (0..files.len())
+                .map(|i| PathBuf::from(format!("{}.json", (b'a' + i as u8) as char)))
+                .collect(),
+            navdata: Default::default(),
+            aircraft: Default::default(),
+        }
+    }
+
+    fn codes(coverage: &Coverage) -> Vec<(Option<usize>, &'static str)> {
+        coverage
+            .diagnostics
+            .iter()
+            .map(|d| (d.rule, d.code))
+            .collect()
+    }
+
+    const RULES: &str = r#"{ "rules": [
+        { "name": "High", "condition": "rfl > 30000", "action": { "typ": "error", "msg": "RFL" } },
+        { "name": "Higher", "condition": "rfl > 35000 and dep == 'EDDF'", "action": { "typ": "error", "msg": "RFL" } },
+        { "name": "Never", "condition": "rfl < 5000 and rfl > 6000", "action": { "typ": "error", "msg": "RFL" } },
+        { "name": "Low", "condition": "rfl < 32000", "action": { "typ": "info", "msg": "LOW" } },
+        { "name": "CINDY", "condition": "sidwpt == 'CINDY' and rfl % 2000 == 0", "action": { "typ": "error", "msg": "DST" } },
+        { "name": "OK", "condition": "true", "action": { "typ": "success", "msg": "OK" } }
+    ] }"#;
+
+    #[test]
+    fn symbolic() {
+        let coverage = analyze(&checker(RULES), &[]);
+        assert_eq!(
+            vec![
+                (Some(2), "shadowed"),
+                (Some(3), "never-fires"),
+                (Some(4), "overlap"),
+            ],
+            codes(&coverage)
+        );
+        assert_eq!(
+            "a.json: warning: rule 4 (Low): Some flight plans match it and rule 1 (High) in a.json, which has a different action [overlap]",
+            coverage.diagnostics[2].to_string()
+        );
+        assert_eq!(6, coverage.rules.len());
+    }
+
+    #[test]
+    fn corpus() {
+        let fp = |rfl, sid: &str| ffi::FlightPlan {
+            rfl,
+            sid: sid.into(),
+            ..serde_json::from_str("{}").unwrap()
+        };
+        let flight_plans = [fp(34000, "CINDY4S"), fp(24000, "CINDY4S"), fp(24000, "")];
+        let coverage = analyze(&checker(RULES), &flight_plans);
+
+        let counts: Vec<_> = coverage
+            .rules
+            .iter()
+            .map(|rule| (rule.matched, rule.decided))
+            .collect();
+        assert_eq!(vec![(1, 1), (0, 0), (0, 0), (2, 2), (2, 0), (3, 0)], counts);
+        assert_eq!(
+            vec![
+                (Some(2), "shadowed"),
+                (Some(3), "never-fires"),
+                (Some(4), "overlap"),
+                (Some(5), "shadowed"),
+                (Some(6), "shadowed"),
+                (Some(5), "overlap"),
+                (Some(5), "overlap"),
+            ],
+            codes(&coverage)
+        );
+        assert_eq!(
+            "a.json: warning: rule 5 (CINDY): 1 flight plans match it and rule 4 (Low) in a.json, which has a different action [overlap]",
+            coverage.diagnostics[6].to_string()
+        );
+    }
+
+    #[test]
+    fn tables_of_files() {
+        let a = r#"{ "tables": { "max_rfl": { "EDDM": 20000 } }, "rules": [
+            { "name": "Max", "condition": "rfl > max_rfl[arr]", "action": { "typ": "error", "msg": "RFL" } },
+            { "name": "Same", "condition": "rfl > max_rfl[arr]", "action": { "typ": "error", "msg": "RFL" } }
+        ] }"#;
+        let b = r#"{ "tables": { "max_rfl": { "EDDM": 30000 } }, "rules": [
+            { "name": "Other", "condition": "rfl > max_rfl[arr]", "action": { "typ": "warning", "msg": "RFL" } }
+        ] }"#;
+        let coverage = analyze(&checker_files(&[a, b]), &[]);
+        // The same condition only shadows within a file, as the tables of b.json differ
+        assert_eq!(vec![(Some(2), "shadowed")], codes(&coverage));
+        assert_eq!(PathBuf::from("a.json"), coverage.diagnostics[0].file);
+    }
+}
@@ -0,0 +1,308 @@
+//! VATSIM datafeed (v3) snapshots, e.g. a saved `https://data.vatsim.net/v3/vatsim-data.json`,
+//! and a summary of checking their flight plans.
+//!
+//! Like FPL messages, the datafeed doesn't contain the aircraft type (landplane, seaplane, ...)
+//! and the engines, which are unknown.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::{Action, ActionType};
+use crate::ffi;
+use crate::fpl::{self, Message};
+use crate::Finding;
+
+#[derive(Deserialize)]
+struct Datafeed {
+    #[serde(default)]
+    pilots: Vec<Pilot>,
+    #[serde(default)]
+    prefiles: Vec<Pilot>,
+}
+
+/// Connected pilot or prefiled flight plan.
+#[derive(Deserialize)]
+struct Pilot {
+    callsign: String,
+    flight_plan: Option<FlightPlan>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct FlightPlan {
+    flight_rules: String,
+    /// ICAO type, wake turbulence category and equipment, e.g. `A320/M-SDE2E3FGIJ1RWY/LB1`.
+    aircraft: String,
+    departure: String,
+    arrival: String,
+    altitude: String,
+    route: String,
+    remarks: String,
+}
+
+/// Parses a datafeed snapshot into the flight plans of the pilots, followed by the prefiled ones.
+/// Pilots without a flight plan are skipped.
+pub fn parse(json: &str) -> Result<Vec<Message>, serde_json::Error> {
+    let datafeed: Datafeed = serde_json::from_str(json)?;
+    Ok(datafeed
+        .pilots
+        .into_iter()
+        .chain(datafeed.prefiles)
+        .filter_map(|pilot| {
+            let flight_plan = convert(pilot.flight_plan?);
+            Some(Message {
+                callsign: pilot.callsign,
+                flight_plan,
+            })
+        })
+        .collect())
+}
+
+fn convert(fp: FlightPlan) -> ffi::FlightPlan {
+    let (typ, equip) = fp.aircraft.split_once('-').unwrap_or((&fp.aircraft, ""));
+    let mut typ = typ.split('/');
+    let icao = typ.next().unwrap_or_default();
+    let wtc = typ
+        .next()
+        .and_then(|wtc| wtc.parse().ok())
+        .unwrap_or(ffi::WakeTurbulenceCategory::Unknown);
+    let is_rvsm_capable = fpl::is_rvsm_capable(equip);
+    let faa_equip_code = match equip {
+        "" => ffi::FaaEquipmentCode::Unknown,
+        _ => fpl::faa_equip_code(equip, is_rvsm_capable),
+    };
+    let (sid, dep_rwy) = fpl::departure_procedure(&fp.route);
+
+    ffi::FlightPlan {
+        ac: ffi::Aircraft {
+            typ: ffi::AircraftType::Unknown,
+            icao: icao.into(),
+            wtc,
+            faa_equip_code,
+            equip: equip.into(),
+            eng_typ: ffi::EngineType::Unknown,
+            eng_count: 0,
+            is_rvsm_capable,
+        },
+        rule: fp.flight_rules.parse().unwrap_or(ffi::FlightRule::Ifr),
+        cfl: 0,
+        rfl: altitude(&fp.altitude),
+        dep: fp.departure,
+        dep_rwy,
+        arr: fp.arrival,
+        sid,
+        route: fp.route,
+        remarks: fp.remarks,
+    }
+}
+
+/// Filed altitude in feet, which is mostly given in feet, but also as `FL350`, `F350`, `A045` or
+/// in hundreds of feet. Unknown formats are 0.
+fn altitude(altitude: &str) -> u32 {
+    let altitude = altitude.trim();
+    let hundreds = altitude
+        .strip_prefix("FL")
+        .or_else(|| altitude.strip_prefix('F'))
+        .or_else(|| altitude.strip_prefix('A'));
+    match hundreds {
+        Some(hundreds) => hundreds.parse().map_or(0, |h: u32| h * 100),
+        None => match altitude.parse() {
+            Ok(feet) if feet < 1000 => feet * 100,
+            Ok(feet) => feet,
+            Err(_) => 0,
+        },
+    }
+}
+
+/// Number of flight plans by the action shown for them.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct Counts {
+    pub success: usize,
+    pub info: usize,
+    pub warning: usize,
+    pub error: usize,
+    /// A rule could not be evaluated.
+    pub failed: usize,
+}
+
+impl Counts {
+    fn add(&mut self, action: &Result<Action, &'static str>) {
+        match action {
+            Ok(action) => match action.typ {
+                ActionType::Success => self.success += 1,
+                ActionType::Info => self.info += 1,
+                ActionType::Warning => self.warning += 1,
+                ActionType::Error => self.error += 1,
+            },
+            Err(_) => self.failed += 1,
+        }
+    }
+
+    pub fn total(&self) -> usize {
+        self.success + self.info + self.warning + self.error + self.failed
+    }
+}
+
+/// Summary of the actions shown for the flight plans of a snapshot.
+#[derive(Debug, Default, Serialize)]
+pub struct Summary {
+    pub total: Counts,
+    /// Number of flight plans by their first finding, e.g. `error RFL (Route flight level)`.
+    pub rules: BTreeMap<String, usize>,
+    /// Actions by departure airport.
+    pub airports: BTreeMap<String, Counts>,
+}
+
+impl Summary {
+    /// Adds a flight plan with its findings, the first one being the action shown in EuroScope.
+    pub fn add(&mut self, fp: &ffi::FlightPlan, findings: &[Finding]) {
+        let (action, rule) = match findings.first() {
+            Some(finding) => {
+                let rule = match (&finding.action, &finding.name) {
+                    (Ok(action), Some(name)) => format!("{} {} ({})", action.typ, action.msg, name),
+                    (Ok(action), None) => format!("{} {}", action.typ, action.msg),
+                    (Err(e), Some(name)) => format!("failed ({}): {}", name, e),
+                    (Err(e), None) => format!("failed: {}", e),
+                };
+                (finding.action.clone(), rule)
+            }
+            // Nothing matched, see `check_configs`
+            None => (
+                Ok(Action::new(ActionType::Warning, "UNK".into())),
+                "warning UNK".into(),
+            ),
+        };
+        self.total.add(&action);
+        *self.rules.entry(rule).or_default() += 1;
+        self.airports
+            .entry(fp.dep.clone())
+            .or_default()
+            .add(&action);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DATAFEED: &str = r#"{
+        "general": { "version": 3, "update_timestamp": "2024-01-01T12:00:00.0000000Z" },
+        "pilots": [
+            {
+                "cid": 1000000,
+                "callsign": "DLH123",
+                "altitude": 4000,
+                "flight_plan": {
+                    "flight_rules": "I",
+                    "aircraft": "A320/M-SDE2E3FGIJ1RWY/LB1",
+                    "aircraft_faa": "A320/L",
+                    "aircraft_short": "A320",
+                    "departure": "EDDF",
+                    "arrival": "EDDS",
+                    "alternate": "EDDM",
+                    "cruise_tas": "450",
+                    "altitude": "35000",
+                    "deptime": "1200",
+                    "enroute_time": "0045",
+                    "fuel_time": "0200",
+                    "remarks": "PBN/A1B1C1D1S2 /V/",
+                    "route": "ANEKI1L/25C ANEKI Y163 NATOR N850 ABESI",
+                    "revision_id": 1,
+                    "assigned_transponder": "1000"
+                }
+            },
+            { "cid": 1000001, "callsign": "TEST", "flight_plan": null }
+        ],
+        "prefiles": [
+            {
+                "cid": 1000002,
+                "callsign": "DLH456",
+                "flight_plan": {
+                    "flight_rules": "V",
+                    "aircraft": "C172/L-SDFGY/S",
+                    "departure": "EDFE",
+                    "arrival": "EDFE",
+                    "altitude": "FL055",
+                    "route": "DCT",
+                    "remarks": ""
+                }
+            }
+        ],
+        "controllers": []
+    }"#;
+
+    #[test]
+    fn flight_plans() {
+        let messages = parse(DATAFEED).unwrap();
+        assert_eq!(2, messages.len());
+
+        let fp = &messages[0].flight_plan;
+        assert_eq!("DLH123", messages[0].callsign);
+        assert_eq!("A320", fp.ac.icao);
+        assert_eq!(ffi::WakeTurbulenceCategory::Medium, fp.ac.wtc);
+        assert_eq!(ffi::FaaEquipmentCode::Q, fp.ac.faa_equip_code);
+        assert_eq!("SDE2E3FGIJ1RWY/LB1", fp.ac.equip);
+        assert!(fp.ac.is_rvsm_capable);
+        assert_eq!(ffi::FlightRule::Ifr, fp.rule);
+        assert_eq!(35000, fp.rfl);
+        assert_eq!("ANEKI1L", fp.sid);
+        assert_eq!("25C", fp.dep_rwy);
+
+        let fp = &messages[1].flight_plan;
+        assert_eq!("DLH456", messages[1].callsign);
+        assert_eq!(ffi::FlightRule::Vfr, fp.rule);
+        assert_eq!(5500, fp.rfl);
+        assert!(!fp.ac.is_rvsm_capable);
+    }
+
+    #[test]
+    fn altitudes() {
+        assert_eq!(35000, altitude("35000"));
+        assert_eq!(35000, altitude("FL350"));
+        assert_eq!(35000, altitude("F350"));
+        assert_eq!(35000, altitude("350"));
+        assert_eq!(4500, altitude("A045"));
+        assert_eq!(0, altitude("VFR"));
+    }
+
+    #[test]
+    fn summary() {
+        let messages = parse(DATAFEED).unwrap();
+        let finding = |name: Option<&str>, typ, msg: &str| Finding {
+            name: name.map(String::from),
+            action: Ok(Action::new(typ, msg.into())),
+            detail: None,
+        };
+        let mut summary = Summary::default();
+        summary.add(
+            &messages[0].flight_plan,
+            &[finding(Some("Valid"), ActionType::Success, "OK")],
+        );
+        summary.add(
+            &messages[0].flight_plan,
+            &[
+                finding(Some("RFL"), ActionType::Error, "RFL"),
+                finding(Some("Valid"), ActionType::Success, "OK"),
+            ],
+        );
+        summary.add(&messages[1].flight_plan, &[]);
+
+        assert_eq!(3, summary.total.total());
+        assert_eq!(1, summary.total.error);
+        assert_eq!(
+            vec![
+                ("error RFL (RFL)", 1),
+                ("success OK (Valid)", 1),
+                ("warning UNK", 1)
+            ],
+            summary
+                .rules
+                .iter()
+                .map(|(rule, count)| (rule.as_str(), *count))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(2, summary.airports["EDDF"].total());
+        assert_eq!(1, summary.airports["EDFE"].warning);
+    }
+}
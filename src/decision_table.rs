@@ -0,0 +1,334 @@
+//! Decision tables in CSV format, where every row is a rule.
+//!
+//! The first row names the columns. `typ` and `msg` are the action of a row, `typ` defaults to
+//! `error`. `name` names the rule and `condition` is an expression in the rule language. Every other
+//! column is a predicate on the identifier of the same name, all of which have to hold for the row
+//! to apply:
+//!
+//! - `CINDY` in column `sidwpt` is `sidwpt == 'CINDY'`. Several values separated by spaces match
+//!   any of them, values containing `*` or `?` are wildcard patterns, e.g. `ED*`.
+//! - A leading `!` negates the predicate, e.g. `!P T` in column `ac_eng_type`.
+//! - Columns prefixed with `min_` or `max_` compare numbers, e.g. `23000` in column `max_rfl` is
+//!   `rfl <= 23000`.
+//!
+//! Empty cells always match. Empty lines and lines starting with `#` are ignored. Fields containing
+//! commas can be quoted with `"`.
+
+use std::error::Error;
+use std::fmt::Display;
+
+use crate::config::{Action, ActionType, Checks, Config, Rule};
+use crate::lang::ast::{BinOp, Expr, Lit, UnOp};
+use crate::lang::parser;
+
+/// Error in a decision table, with 1-based row and column numbers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecisionTableError {
+    pub row: usize,
+    pub column: usize,
+    pub msg: String,
+}
+
+impl Display for DecisionTableError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "row {}, column {}: {}", self.row, self.column, self.msg)
+    }
+}
+
+impl Error for DecisionTableError {}
+
+pub fn parse(input: &str) -> Result<Config, DecisionTableError> {
+    let mut lines = input
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'));
+
+    let (header_row, header) = lines.next().ok_or(DecisionTableError {
+        row: 1,
+        column: 1,
+        msg: "Missing header".into(),
+    })?;
+    let header = split_row(header).map_err(|(column, msg)| error(header_row, column, msg))?;
+    for (i, name) in header.iter().enumerate() {
+        if !is_column_name(name) {
+            return Err(error(header_row, i + 1, "Invalid column name"));
+        }
+    }
+    let msg_column = header
+        .iter()
+        .position(|name| name == "msg")
+        .ok_or_else(|| error(header_row, header.len() + 1, "Missing column msg"))?;
+
+    let mut rules = vec![];
+    for (row, line) in lines {
+        let fields = split_row(line).map_err(|(column, msg)| error(row, column, msg))?;
+        if fields.len() != header.len() {
+            let column = fields.len().min(header.len()) + 1;
+            return Err(error(row, column, "Wrong number of columns"));
+        }
+        rules.push(
+            parse_rule(&header, &fields, msg_column)
+                .map_err(|(column, msg)| error(row, column, msg))?,
+        );
+    }
+
+    Ok(Config {
+        checks: Checks::default(),
+        tables: Default::default(),
+        priority: 0,
+        tests: vec![],
+        rules,
+    })
+}
+
+fn error(row: usize, column: usize, msg: impl Into<String>) -> DecisionTableError {
+    DecisionTableError {
+        row,
+        column,
+        msg: msg.into(),
+    }
+}
+
+fn is_column_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+/// Split a row into its fields. Errors contain the 1-based column.
+fn split_row(line: &str) -> Result<Vec<String>, (usize, String)> {
+    let mut fields = vec![];
+    let mut field = String::new();
+    let mut chars = line.chars().peekable();
+    let mut quoted = false;
+    while let Some(c) = chars.next() {
+        match (c, quoted) {
+            ('"', false) if field.trim().is_empty() => {
+                field.clear();
+                quoted = true;
+            }
+            ('"', true) if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            ('"', true) => quoted = false,
+            (',', false) => fields.push(std::mem::take(&mut field).trim().to_string()),
+            (c, _) => field.push(c),
+        }
+    }
+    if quoted {
+        return Err((fields.len() + 1, "Unterminated quote".into()));
+    }
+    fields.push(field.trim().to_string());
+    Ok(fields)
+}
+
+/// Errors contain the 1-based column.
+fn parse_rule(
+    header: &[String],
+    fields: &[String],
+    msg_column: usize,
+) -> Result<Rule, (usize, String)> {
+    let mut typ = ActionType::Error;
+    let mut rule_name = None;
+    let mut predicates = vec![];
+    for (i, (name, value)) in header.iter().zip(fields).enumerate() {
+        let column = i + 1;
+        if value.is_empty() {
+            continue;
+        }
+        match name.as_str() {
+            "msg" => {}
+            "name" => rule_name = Some(value.clone()),
+            "typ" => {
+                typ = parse_action_type(value).ok_or((column, "Invalid action type".into()))?
+            }
+            "condition" => {
+                predicates.push(parser::parse(value).map_err(|e| (column, e.to_string()))?)
+            }
+            _ => predicates.push(predicate(name, value).map_err(|msg| (column, msg.into()))?),
+        }
+    }
+
+    let msg = &fields[msg_column];
+    if msg.is_empty() {
+        return Err((msg_column + 1, "Missing message".into()));
+    }
+    let condition = predicates
+        .into_iter()
+        .reduce(|lhs, rhs| Expr::Binary(BinOp::And, Box::new(lhs), Box::new(rhs)))
+        .unwrap_or(Expr::Lit(Lit::Bool(true)));
+    Ok(Rule {
+        name: rule_name,
+        condition,
+        action: Action::new(typ, msg.clone()),
+        priority: 0,
+    })
+}
+
+fn parse_action_type(s: &str) -> Option<ActionType> {
+    match s {
+        "error" => Some(ActionType::Error),
+        "warning" => Some(ActionType::Warning),
+        "info" => Some(ActionType::Info),
+        "success" => Some(ActionType::Success),
+        _ => None,
+    }
+}
+
+fn literal(value: &str) -> Expr {
+    match value.parse() {
+        Ok(i) => Expr::Lit(Lit::Int(i)),
+        Err(_) => Expr::Lit(Lit::Text(value.into())),
+    }
+}
+
+/// Predicate of a cell in a column named after an identifier.
+fn predicate(name: &str, value: &str) -> Result<Expr, &'static str> {
+    let bound = |ident: &str, op: BinOp| -> Result<Expr, &'static str> {
+        let bound: i64 = value.parse().map_err(|_| "Expected a number")?;
+        Ok(Expr::Binary(
+            op,
+            Box::new(Expr::Ident(ident.into())),
+            Box::new(Expr::Lit(Lit::Int(bound))),
+        ))
+    };
+    if let Some(ident) = name.strip_prefix("min_") {
+        return bound(ident, BinOp::Ge);
+    }
+    if let Some(ident) = name.strip_prefix("max_") {
+        return bound(ident, BinOp::Le);
+    }
+
+    let (negated, value) = match value.strip_prefix('!') {
+        Some(value) => (true, value),
+        None => (false, value),
+    };
+    let ident = || Box::new(Expr::Ident(name.into()));
+    let (patterns, values): (Vec<&str>, Vec<&str>) = value
+        .split_whitespace()
+        .partition(|v| v.contains(['*', '?']));
+    let mut alternatives: Vec<Expr> = patterns
+        .into_iter()
+        .map(|pattern| {
+            Expr::Call(
+                Box::new(Expr::Ident("matches".into())),
+                vec![*ident(), Expr::Lit(Lit::Text(pattern.into()))],
+            )
+        })
+        .collect();
+    match values[..] {
+        [] => {}
+        [value] => alternatives.push(Expr::Binary(BinOp::Eq, ident(), Box::new(literal(value)))),
+        _ => alternatives.push(Expr::Binary(
+            BinOp::In,
+            ident(),
+            Box::new(Expr::Array(values.into_iter().map(literal).collect())),
+        )),
+    }
+    let expr = alternatives
+        .into_iter()
+        .reduce(|lhs, rhs| Expr::Binary(BinOp::Or, Box::new(lhs), Box::new(rhs)))
+        .ok_or("Missing value")?;
+    Ok(match negated {
+        true => Expr::Unary(UnOp::Not, Box::new(expr)),
+        false => expr,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split() {
+        assert_eq!(
+            Ok(vec!["a".into(), "".into(), "b".into()]),
+            split_row("a,, b ")
+        );
+        assert_eq!(
+            Ok(vec!["x, \"y\"".into(), "z".into()]),
+            split_row(r#""x, ""y""",z"#)
+        );
+        assert_eq!(Err((2, "Unterminated quote".into())), split_row(r#"a,"b"#));
+    }
+
+    #[test]
+    fn rules() {
+        let config = parse(
+            "name,sidwpt,arr,ac_eng_type,max_rfl,typ,msg\n\
+             # comment\n\
+             CINDY prop,CINDY,,!P T,,,ENG\n\
+             \n\
+             ANEKI,ANEKI,EDDM EDDN,,,warning,DST\n\
+             Domestic,,ED*,,23000,,RFL\n",
+        )
+        .unwrap();
+        assert_eq!(3, config.rules.len());
+        assert_eq!(
+            parser::parse("sidwpt == 'CINDY' and !(ac_eng_type in ['P', 'T'])").unwrap(),
+            config.rules[0].condition
+        );
+        assert_eq!(
+            Action::new(ActionType::Error, "ENG".into()),
+            config.rules[0].action
+        );
+        assert_eq!(
+            parser::parse("sidwpt == 'ANEKI' and arr in ['EDDM', 'EDDN']").unwrap(),
+            config.rules[1].condition
+        );
+        assert_eq!(ActionType::Warning, config.rules[1].action.typ);
+        assert_eq!(Some("ANEKI".into()), config.rules[1].name);
+        assert_eq!(
+            parser::parse("matches(arr, 'ED*') and rfl <= 23000").unwrap(),
+            config.rules[2].condition
+        );
+    }
+
+    #[test]
+    fn condition_column() {
+        let config =
+            parse("condition,msg\n\"rfl > 9000 and sidwpt in ['MTR', 'RID']\",RFL\n,ALL\n")
+                .unwrap();
+        assert_eq!(
+            parser::parse("rfl > 9000 and sidwpt in ['MTR', 'RID']").unwrap(),
+            config.rules[0].condition
+        );
+        assert_eq!(Expr::Lit(Lit::Bool(true)), config.rules[1].condition);
+    }
+
+    #[test]
+    fn errors() {
+        let err = |input: &str| match parse(input) {
+            Ok(_) => panic!("Expected an error for {input:?}"),
+            Err(e) => e,
+        };
+        assert_eq!(error(1, 1, "Missing header"), err("# only a comment\n"));
+        assert_eq!(error(1, 3, "Missing column msg"), err("sidwpt,arr\n"));
+        assert_eq!(error(1, 2, "Invalid column name"), err("sidwpt,Arr,msg\n"));
+        assert_eq!(
+            error(3, 3, "Wrong number of columns"),
+            err("sidwpt,msg\nCINDY,ENG\nCINDY,ENG,X\n")
+        );
+        assert_eq!(
+            error(2, 2, "Expected a number"),
+            err("sidwpt,max_rfl,msg\nCINDY,FL230,RFL\n")
+        );
+        assert_eq!(
+            error(2, 2, "Invalid action type"),
+            err("sidwpt,typ,msg\nCINDY,fatal,RFL\n")
+        );
+        assert_eq!(error(2, 2, "Missing message"), err("sidwpt,msg\nCINDY,\n"));
+        assert_eq!(2, err("condition,msg\nrfl >,RFL\n").row);
+        assert_eq!(
+            error(2, 1, "Bad token \"Int(5)\""),
+            err("condition,msg\nrfl 5,RFL\n")
+        );
+        assert_eq!(
+            "row 2, column 2: Missing message",
+            err("sidwpt,msg\nCINDY,\n").to_string()
+        );
+    }
+}
@@ -0,0 +1,140 @@
+//! ICAO item 10 equipment and item 18 PBN/ capability model.
+
+/// Navigation and surveillance capabilities of an aircraft as filed in an ICAO flight plan.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Capabilities {
+    /// Item 10a: radio communication, navigation and approach aid equipment, e.g. `["S", "D", "E2"]`.
+    pub com_nav: Vec<String>,
+    /// Item 10b: surveillance equipment, e.g. `["L", "B1"]`.
+    pub surveillance: Vec<String>,
+    /// Item 18 PBN/ indicators, e.g. `["A1", "B1", "D1"]`.
+    pub pbn: Vec<String>,
+}
+
+impl Capabilities {
+    /// Parse the item 10 equipment string (e.g. `SDE2E3FGIJ1RWY/LB1`) and the PBN/ indicators
+    /// (e.g. `A1B1C1D1S2`).
+    ///
+    /// Unknown characters are skipped, so a malformed string results in fewer capabilities
+    /// instead of an error.
+    pub fn parse(equip: &str, pbn: &str) -> Self {
+        let (com_nav, surveillance) = equip.split_once('/').unwrap_or((equip, ""));
+        Self {
+            com_nav: split_codes(com_nav),
+            surveillance: split_codes(surveillance),
+            pbn: split_codes(pbn),
+        }
+    }
+
+    /// Item 10a codes including the ones implied by `S` (VHF RTF, VOR and ILS).
+    pub fn equipment(&self) -> Vec<String> {
+        let mut codes = self.com_nav.clone();
+        if self.com_nav.iter().any(|c| c == "S") {
+            for implied in ["L", "O", "V"] {
+                if !codes.iter().any(|c| c == implied) {
+                    codes.push(implied.into());
+                }
+            }
+        }
+        codes
+    }
+
+    pub fn has_equipment(&self, code: &str) -> bool {
+        self.equipment().iter().any(|c| c == code)
+    }
+
+    pub fn has_pbn(&self, code: &str) -> bool {
+        self.pbn.iter().any(|c| c == code)
+    }
+
+    fn has_any_pbn(&self, codes: &[&str]) -> bool {
+        codes.iter().any(|code| self.has_pbn(code))
+    }
+
+    /// RNAV 1 approved (`D1`-`D4`).
+    pub fn is_rnav1(&self) -> bool {
+        self.has_any_pbn(&["D1", "D2", "D3", "D4"])
+    }
+
+    /// RNAV 5 approved (`B1`-`B6`).
+    pub fn is_rnav5(&self) -> bool {
+        self.has_any_pbn(&["B1", "B2", "B3", "B4", "B5", "B6"])
+    }
+
+    /// RNP 1 approved (`O1`-`O4`).
+    pub fn is_rnp1(&self) -> bool {
+        self.has_any_pbn(&["O1", "O2", "O3", "O4"])
+    }
+
+    /// RNP APCH approved (`S1`, `S2`).
+    pub fn is_rnp_apch(&self) -> bool {
+        self.has_any_pbn(&["S1", "S2"])
+    }
+
+    /// CPDLC equipped (`J1`-`J7`).
+    pub fn has_datalink(&self) -> bool {
+        self.com_nav
+            .iter()
+            .any(|c| c.len() == 2 && c.starts_with('J'))
+    }
+}
+
+/// Split a sequence of single letter codes, each optionally followed by a digit.
+fn split_codes(s: &str) -> Vec<String> {
+    let mut codes = vec![];
+    let mut chars = s.trim().chars().peekable();
+    while let Some(c) = chars.next() {
+        if !c.is_ascii_uppercase() {
+            continue;
+        }
+        let mut code = c.to_string();
+        if let Some(digit) = chars.next_if(|d| d.is_ascii_digit()) {
+            code.push(digit);
+        }
+        codes.push(code);
+    }
+    codes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_equipment() {
+        let caps = Capabilities::parse("SDE2E3FGIJ1RWY/LB1", "");
+        assert_eq!(
+            vec!["S", "D", "E2", "E3", "F", "G", "I", "J1", "R", "W", "Y"],
+            caps.com_nav
+        );
+        assert_eq!(vec!["L", "B1"], caps.surveillance);
+        assert!(caps.pbn.is_empty());
+    }
+
+    #[test]
+    fn standard_equipment_implied() {
+        let caps = Capabilities::parse("SG/C", "");
+        assert!(caps.has_equipment("S"));
+        assert!(caps.has_equipment("O"));
+        assert!(caps.has_equipment("V"));
+        assert!(caps.has_equipment("L"));
+        assert!(!caps.has_equipment("W"));
+    }
+
+    #[test]
+    fn pbn_indicators() {
+        let caps = Capabilities::parse("SDFGRW/S", "A1B1C1D1S2");
+        assert!(caps.has_pbn("D1"));
+        assert!(caps.is_rnav1());
+        assert!(caps.is_rnav5());
+        assert!(caps.is_rnp_apch());
+        assert!(!caps.is_rnp1());
+        assert!(!caps.has_datalink());
+    }
+
+    #[test]
+    fn invalid_equipment() {
+        assert_eq!(Capabilities::default(), Capabilities::parse("", ""));
+        assert_eq!(vec!["S", "G"], Capabilities::parse("S-?G", "").com_nav);
+    }
+}
@@ -0,0 +1,192 @@
+//! ICAO ATS flight plan (FPL) message parser.
+//!
+//! ```text
+//! (FPL-DLH123-IS
+//! -A320/M-SDE2E3FGIJ1RWY/LB1
+//! -EDDF1200
+//! -N0450F350 ANEKI1L ANEKI Y163 NATOR N850 ABESI
+//! -EDDS0045 EDDM
+//! -PBN/A1B1C1D1S2 DOF/240101)
+//! ```
+//!
+//! EuroScope knows more about a flight plan than the message contains. The aircraft type (landplane,
+//! seaplane, ...) and the engines are unknown, the FAA equipment code is derived from item 10.
+
+use crate::equipment::Capabilities;
+use crate::ffi;
+use crate::route::{Element, Route};
+
+/// A flight plan together with the callsign it was filed for.
+#[derive(Debug, Clone)]
+pub struct Message {
+    pub callsign: String,
+    pub flight_plan: ffi::FlightPlan,
+}
+
+/// Parse a FPL message. The enclosing parentheses are optional.
+pub fn parse(message: &str) -> Result<Message, &'static str> {
+    let message = message.trim();
+    let message = message.strip_prefix('(').unwrap_or(message);
+    let message = message.strip_suffix(')').unwrap_or(message);
+    let fields: Vec<&str> = message.split('-').map(str::trim).collect();
+    if fields[0] != "FPL" {
+        return Err("Not a FPL message");
+    }
+    let [_, item7, item8, item9, item10, item13, item15, item16, ..] = fields[..] else {
+        return Err("Missing fields");
+    };
+    // Item 18 may contain hyphens, e.g. in RMK/
+    let item18 = fields[8..].join("-");
+
+    let callsign = item7.split('/').next().unwrap_or_default();
+    let rule = item8.get(..1).ok_or("Missing flight rules")?.parse()?;
+
+    let (typ, wtc) = item9
+        .split_once('/')
+        .ok_or("Missing wake turbulence category")?;
+    let icao = typ.trim_start_matches(|c: char| c.is_ascii_digit());
+    let wtc = wtc.parse()?;
+
+    let dep = airport(item13)?;
+    let arr = airport(item16.split_whitespace().next().unwrap_or_default())?;
+
+    let mut words = item15.split_whitespace();
+    let rfl = match words.next().map(Element::parse) {
+        Some(Element::SpeedLevel(speed_level)) => speed_level.level.feet().unwrap_or(0),
+        _ => return Err("Missing cruising speed and level"),
+    };
+    let route = words.collect::<Vec<_>>().join(" ");
+    let (sid, dep_rwy) = departure_procedure(&route);
+
+    let is_rvsm_capable = is_rvsm_capable(item10);
+    let faa_equip_code = faa_equip_code(item10, is_rvsm_capable);
+
+    Ok(Message {
+        callsign: callsign.into(),
+        flight_plan: ffi::FlightPlan {
+            ac: ffi::Aircraft {
+                typ: ffi::AircraftType::Unknown,
+                icao: icao.into(),
+                wtc,
+                faa_equip_code,
+                equip: item10.into(),
+                eng_typ: ffi::EngineType::Unknown,
+                eng_count: 0,
+                is_rvsm_capable,
+            },
+            rule,
+            cfl: 0,
+            rfl,
+            dep,
+            dep_rwy,
+            arr,
+            sid,
+            route,
+            remarks: item18,
+        },
+    })
+}
+
+/// Airport of item 13 or 16, followed by a time.
+fn airport(item: &str) -> Result<String, &'static str> {
+    match item.get(..4) {
+        Some(icao) if icao.chars().all(|c| c.is_ascii_uppercase()) => Ok(icao.into()),
+        _ => Err("Invalid airport"),
+    }
+}
+
+/// SID and runway of a route starting with a procedure, e.g. `ANEKI1L/25C ANEKI Y163 ...`.
+pub(crate) fn departure_procedure(route: &str) -> (String, String) {
+    match Route::parse(route).elements.first() {
+        Some(Element::Procedure { name, runway }) => {
+            (name.clone(), runway.clone().unwrap_or_default())
+        }
+        _ => (String::new(), String::new()),
+    }
+}
+
+/// Whether item 10a of the equipment contains `W`.
+pub(crate) fn is_rvsm_capable(equip: &str) -> bool {
+    equip.split('/').next().unwrap_or_default().contains('W')
+}
+
+/// The FAA equipment code closest to the item 10 equipment.
+pub(crate) fn faa_equip_code(equip: &str, is_rvsm_capable: bool) -> ffi::FaaEquipmentCode {
+    let capabilities = Capabilities::parse(equip, "");
+    let is_rnav = capabilities.has_equipment("R") || capabilities.has_equipment("G");
+    let has_dme = capabilities.has_equipment("D");
+    let has_transponder = capabilities
+        .surveillance
+        .iter()
+        .any(|c| c != "N" && !c.starts_with(['B', 'U', 'V', 'D', 'G']));
+    match (is_rvsm_capable, is_rnav, has_dme, has_transponder) {
+        (true, true, _, _) => ffi::FaaEquipmentCode::Q,
+        (true, false, _, _) => ffi::FaaEquipmentCode::W,
+        (false, true, _, _) if capabilities.has_equipment("G") => ffi::FaaEquipmentCode::G,
+        (false, true, _, _) => ffi::FaaEquipmentCode::R,
+        (false, false, true, true) => ffi::FaaEquipmentCode::A,
+        (false, false, true, false) => ffi::FaaEquipmentCode::D,
+        (false, false, false, true) => ffi::FaaEquipmentCode::U,
+        (false, false, false, false) => ffi::FaaEquipmentCode::X,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn message() {
+        let msg = parse(
+            "(FPL-DLH123/A1234-IS\n-2A320/M-SDE2E3FGIJ1RWY/LB1\n-EDDF1200\n-N0450F350 ANEKI1L/25C ANEKI Y163 NATOR\n-EDDS0045 EDDM\n-PBN/A1B1C1D1S2 RMK/TCAS-EQUIPPED)",
+        )
+        .unwrap();
+        assert_eq!("DLH123", msg.callsign);
+        let fp = msg.flight_plan;
+        assert_eq!("A320", fp.ac.icao);
+        assert_eq!("M", fp.ac.wtc.to_string());
+        assert_eq!("Q", fp.ac.faa_equip_code.to_string());
+        assert_eq!("SDE2E3FGIJ1RWY/LB1", fp.ac.equip);
+        assert!(fp.ac.is_rvsm_capable);
+        assert_eq!("I", fp.rule.to_string());
+        assert_eq!(35000, fp.rfl);
+        assert_eq!("EDDF", fp.dep);
+        assert_eq!("25C", fp.dep_rwy);
+        assert_eq!("EDDS", fp.arr);
+        assert_eq!("ANEKI1L", fp.sid);
+        assert_eq!("ANEKI1L/25C ANEKI Y163 NATOR", fp.route);
+        assert_eq!("PBN/A1B1C1D1S2 RMK/TCAS-EQUIPPED", fp.remarks);
+    }
+
+    #[test]
+    fn without_sid() {
+        let fp = parse("FPL-DEABC-VG-C172/L-SDY/C-EDFE1000-N0100A035 DCT-EDFM0030")
+            .unwrap()
+            .flight_plan;
+        assert_eq!(3500, fp.rfl);
+        assert_eq!("", fp.sid);
+        assert_eq!("DCT", fp.route);
+        assert_eq!("", fp.remarks);
+        assert_eq!("A", fp.ac.faa_equip_code.to_string());
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            Err("Missing fields"),
+            parse("(FPL-DLH123-IS)").map(|m| m.callsign)
+        );
+        assert_eq!(
+            Err("Not a FPL message"),
+            parse("(CHG-DLH123-EDDF-EDDS-0-0-0-0)").map(|m| m.callsign)
+        );
+        assert_eq!(
+            Err("Invalid flight rule"),
+            parse("FPL-DLH123-XS-A320/M-S/C-EDDF1200-N0450F350 DCT-EDDS0045").map(|m| m.callsign)
+        );
+        assert_eq!(
+            Err("Missing cruising speed and level"),
+            parse("FPL-DLH123-IS-A320/M-S/C-EDDF1200-ANEKI-EDDS0045").map(|m| m.callsign)
+        );
+    }
+}
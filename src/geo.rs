@@ -0,0 +1,179 @@
+//! Great-circle geometry on a spherical earth.
+
+use crate::navdata::Coord;
+use crate::route;
+
+/// Mean earth radius in nautical miles.
+const EARTH_RADIUS_NM: f64 = 3440.065;
+
+/// Great-circle distance in nautical miles.
+pub fn distance_nm(from: Coord, to: Coord) -> f64 {
+    let (lat1, lat2) = (from.lat.to_radians(), to.lat.to_radians());
+    let dlat = lat2 - lat1;
+    let dlon = (to.lon - from.lon).to_radians();
+    let a = (dlat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (dlon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_NM * a.sqrt().asin()
+}
+
+/// Initial true track of the great circle from `from` to `to` in degrees `[0, 360)`.
+pub fn initial_track(from: Coord, to: Coord) -> f64 {
+    let (lat1, lat2) = (from.lat.to_radians(), to.lat.to_radians());
+    let dlon = (to.lon - from.lon).to_radians();
+    let y = dlon.sin() * lat2.cos();
+    let x = lat1.cos() * lat2.sin() - lat1.sin() * lat2.cos() * dlon.cos();
+    normalize(y.atan2(x).to_degrees())
+}
+
+/// Total great-circle distance along all points in nautical miles.
+pub fn path_distance_nm(points: &[Coord]) -> f64 {
+    points.windows(2).map(|w| distance_nm(w[0], w[1])).sum()
+}
+
+/// Average true track along all points in degrees `[0, 360)`, weighted by the leg distances.
+///
+/// Returns `None` if the path has no length.
+pub fn average_track(points: &[Coord]) -> Option<f64> {
+    let (mut x, mut y) = (0.0, 0.0);
+    for leg in points.windows(2) {
+        let distance = distance_nm(leg[0], leg[1]);
+        let track = initial_track(leg[0], leg[1]).to_radians();
+        x += distance * track.cos();
+        y += distance * track.sin();
+    }
+    (x != 0.0 || y != 0.0).then(|| normalize(y.atan2(x).to_degrees()))
+}
+
+/// Convert a true track to a magnetic track. `variation` is positive for east.
+pub fn magnetic(true_track: f64, variation: f64) -> f64 {
+    normalize(true_track - variation)
+}
+
+/// Point reached from `from` after `distance_nm` on the given initial true track.
+pub fn destination(from: Coord, true_track: f64, distance_nm: f64) -> Coord {
+    let lat1 = from.lat.to_radians();
+    let lon1 = from.lon.to_radians();
+    let track = true_track.to_radians();
+    let d = distance_nm / EARTH_RADIUS_NM;
+    let lat2 = (lat1.sin() * d.cos() + lat1.cos() * d.sin() * track.cos()).asin();
+    let lon2 = lon1 + (track.sin() * d.sin() * lat1.cos()).atan2(d.cos() - lat1.sin() * lat2.sin());
+    Coord {
+        lat: lat2.to_degrees(),
+        lon: (lon2.to_degrees() + 540.0).rem_euclid(360.0) - 180.0,
+    }
+}
+
+/// Parse a coordinate as used in ICAO routes, e.g. `50N008E` or `5030N00830E`.
+pub fn parse_route_coord(name: &str) -> Option<Coord> {
+    if !route::is_coordinate(name) {
+        return None;
+    }
+    let i = name.find(['N', 'S'])?;
+    let half = |s: &str, deg: usize| -> Option<f64> {
+        let value: f64 = s[..deg].parse().ok()?;
+        let min: f64 = match &s[deg..] {
+            "" => 0.0,
+            min => min.parse().ok()?,
+        };
+        Some(value + min / 60.0)
+    };
+    let lat = half(&name[..i], 2)?;
+    let lon = half(&name[i + 1..name.len() - 1], 3)?;
+    Some(Coord {
+        lat: if name[i..].starts_with('S') {
+            -lat
+        } else {
+            lat
+        },
+        lon: if name.ends_with('W') { -lon } else { lon },
+    })
+}
+
+fn normalize(deg: f64) -> f64 {
+    deg.rem_euclid(360.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EDDF: Coord = Coord {
+        lat: 50.0331,
+        lon: 8.5706,
+    };
+    const EDDM: Coord = Coord {
+        lat: 48.3539,
+        lon: 11.7861,
+    };
+
+    #[test]
+    fn distance() {
+        assert!((distance_nm(EDDF, EDDM) - 162.0).abs() < 1.0);
+        assert_eq!(0.0, distance_nm(EDDF, EDDF));
+        let equator = Coord { lat: 0.0, lon: 0.0 };
+        let one_deg = Coord { lat: 0.0, lon: 1.0 };
+        assert!((distance_nm(equator, one_deg) - 60.04).abs() < 0.01);
+    }
+
+    #[test]
+    fn track() {
+        assert!((initial_track(EDDF, EDDM) - 127.0).abs() < 1.0);
+        assert!((initial_track(EDDM, EDDF) - 309.5).abs() < 1.0);
+        let a = Coord { lat: 0.0, lon: 0.0 };
+        let north = Coord { lat: 1.0, lon: 0.0 };
+        let west = Coord {
+            lat: 0.0,
+            lon: -1.0,
+        };
+        assert!(initial_track(a, north).abs() < 1e-9);
+        assert!((initial_track(a, west) - 270.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn path() {
+        let a = Coord { lat: 0.0, lon: 0.0 };
+        let b = Coord { lat: 0.0, lon: 1.0 };
+        let c = Coord { lat: 1.0, lon: 1.0 };
+        assert!((path_distance_nm(&[a, b, c]) - 120.07).abs() < 0.1);
+        assert!((average_track(&[a, b, c]).unwrap() - 45.0).abs() < 0.5);
+        assert_eq!(None, average_track(&[a]));
+        assert_eq!(None, average_track(&[a, a]));
+    }
+
+    #[test]
+    fn destination_point() {
+        let to = destination(EDDF, initial_track(EDDF, EDDM), distance_nm(EDDF, EDDM));
+        assert!((to.lat - EDDM.lat).abs() < 1e-6);
+        assert!((to.lon - EDDM.lon).abs() < 1e-6);
+        let a = Coord { lat: 0.0, lon: 0.0 };
+        let east = destination(a, 90.0, 60.04);
+        assert!(east.lat.abs() < 1e-9);
+        assert!((east.lon - 1.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn route_coord() {
+        assert_eq!(
+            Some(Coord {
+                lat: 50.0,
+                lon: 8.0
+            }),
+            parse_route_coord("50N008E")
+        );
+        assert_eq!(
+            Some(Coord {
+                lat: -50.5,
+                lon: -8.5
+            }),
+            parse_route_coord("5030S00830W")
+        );
+        assert_eq!(None, parse_route_coord("CINDY"));
+        assert_eq!(None, parse_route_coord("50N"));
+    }
+
+    #[test]
+    fn magnetic_variation() {
+        assert_eq!(357.0, magnetic(0.0, 3.0));
+        assert_eq!(3.0, magnetic(0.0, -3.0));
+        assert_eq!(90.0, magnetic(93.0, 3.0));
+    }
+}
@@ -0,0 +1,213 @@
+//! ICAO item 18 (other information) parser.
+
+/// Date of flight as given by the DOF/ indicator (`YYMMDD`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Date {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+}
+
+impl Date {
+    fn parse(s: &str) -> Option<Self> {
+        if s.len() != 6 || !s.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        let year = 2000 + s[0..2].parse::<u16>().ok()?;
+        let month = s[2..4].parse().ok()?;
+        let day = s[4..6].parse().ok()?;
+        if !(1..=12).contains(&month) || !(1..=days_in_month(year, month)).contains(&day) {
+            return None;
+        }
+        Some(Self { year, month, day })
+    }
+}
+
+fn days_in_month(year: u16, month: u8) -> u8 {
+    match month {
+        // Years are 2000 to 2099, where every fourth one is a leap year
+        2 if year.is_multiple_of(4) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Indicators of ICAO Doc 4444 item 18, plus `RVR` of the EUR regional supplementary procedures.
+const INDICATORS: &[&str] = &[
+    "STS", "PBN", "NAV", "COM", "DAT", "SUR", "DEP", "DEST", "DOF", "REG", "EET", "SEL", "TYP",
+    "CODE", "DLE", "OPR", "ORGN", "PER", "ALTN", "RALT", "TALT", "RIF", "RMK", "RVR",
+];
+
+/// Item 18 split into its indicators, e.g. `STS/HOSP RMK/TCAS` into `STS` and `RMK`.
+///
+/// Indicators keep the order in which they were filed. If an indicator is filed more than once,
+/// the values are joined with a space.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Item18 {
+    fields: Vec<(String, String)>,
+}
+
+impl Item18 {
+    /// Parse the item 18 remarks. Text before the first indicator is ignored.
+    pub fn parse(remarks: &str) -> Self {
+        let mut item18 = Self::default();
+        let mut current: Option<(String, Vec<&str>)> = None;
+        for word in remarks.split_whitespace() {
+            match split_indicator(word) {
+                Some((key, value)) => {
+                    if let Some((key, words)) = current.take() {
+                        item18.insert(key, words.join(" "));
+                    }
+                    let words = if value.is_empty() {
+                        vec![]
+                    } else {
+                        vec![value]
+                    };
+                    current = Some((key.into(), words));
+                }
+                None => {
+                    if let Some((_, words)) = current.as_mut() {
+                        words.push(word);
+                    }
+                }
+            }
+        }
+        if let Some((key, words)) = current {
+            item18.insert(key, words.join(" "));
+        }
+        item18
+    }
+
+    fn insert(&mut self, key: String, value: String) {
+        match self.fields.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, existing)) => {
+                existing.push(' ');
+                existing.push_str(&value);
+            }
+            None => self.fields.push((key, value)),
+        }
+    }
+
+    /// Raw value of an indicator.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.fields.iter().map(|(k, _)| k.as_str())
+    }
+
+    /// Date of flight.
+    pub fn dof(&self) -> Option<Date> {
+        self.get("DOF").and_then(Date::parse)
+    }
+
+    /// Reasons for special handling, e.g. `["HOSP", "NONRVSM"]`.
+    pub fn sts(&self) -> Vec<String> {
+        self.get("STS")
+            .map(|v| v.split_whitespace().map(String::from).collect())
+            .unwrap_or_default()
+    }
+
+    /// PBN indicators, e.g. `A1B1C1D1`.
+    pub fn pbn(&self) -> &str {
+        self.get("PBN").unwrap_or("")
+    }
+
+    /// Plain language remarks.
+    pub fn rmk(&self) -> Option<&str> {
+        self.get("RMK")
+    }
+
+    /// Aircraft registration.
+    pub fn reg(&self) -> Option<&str> {
+        self.get("REG")
+    }
+
+    /// Aircraft operator.
+    pub fn opr(&self) -> Option<&str> {
+        self.get("OPR")
+    }
+
+    /// Aircraft performance category (`A`-`H`).
+    pub fn per(&self) -> Option<char> {
+        self.get("PER").and_then(|v| v.chars().next())
+    }
+
+    /// Minimum required RVR in meters.
+    pub fn rvr(&self) -> Option<u32> {
+        self.get("RVR").and_then(|v| v.parse().ok())
+    }
+}
+
+/// Split a word like `STS/HOSP` into indicator and value. Words with other text before the slash,
+/// like `ABC/DEF` in free text, are no indicators.
+fn split_indicator(word: &str) -> Option<(&str, &str)> {
+    let (key, value) = word.split_once('/')?;
+    INDICATORS.contains(&key).then_some((key, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REMARKS: &str =
+        "PBN/A1B1C1D1S2 DOF/221201 REG/DABCD STS/HOSP NONRVSM OPR/DLH PER/C RVR/75 RMK/TCAS EQUIPPED";
+
+    #[test]
+    fn parse_indicators() {
+        let item18 = Item18::parse(REMARKS);
+        assert_eq!(
+            vec!["PBN", "DOF", "REG", "STS", "OPR", "PER", "RVR", "RMK"],
+            item18.keys().collect::<Vec<_>>()
+        );
+        assert_eq!("A1B1C1D1S2", item18.pbn());
+        assert_eq!(Some("DABCD"), item18.reg());
+        assert_eq!(Some("DLH"), item18.opr());
+        assert_eq!(Some('C'), item18.per());
+        assert_eq!(Some(75), item18.rvr());
+        assert_eq!(Some("TCAS EQUIPPED"), item18.rmk());
+        assert_eq!(vec!["HOSP", "NONRVSM"], item18.sts());
+    }
+
+    #[test]
+    fn dof() {
+        let item18 = Item18::parse(REMARKS);
+        assert_eq!(
+            Some(Date {
+                year: 2022,
+                month: 12,
+                day: 1
+            }),
+            item18.dof()
+        );
+        assert_eq!(None, Item18::parse("DOF/221301").dof());
+        assert_eq!(None, Item18::parse("DOF/TODAY").dof());
+        assert_eq!(None, Item18::parse("DOF/240231").dof());
+        assert_eq!(None, Item18::parse("DOF/230229").dof());
+        assert_eq!(None, Item18::parse("DOF/240431").dof());
+        assert_eq!(29, Item18::parse("DOF/240229").dof().unwrap().day);
+    }
+
+    #[test]
+    fn repeated_indicator() {
+        let item18 = Item18::parse("RMK/TCAS RMK/CHARTS ON BOARD");
+        assert_eq!(Some("TCAS CHARTS ON BOARD"), item18.rmk());
+    }
+
+    #[test]
+    fn free_text() {
+        let item18 = Item18::parse("/V/ RMK/SIMBRIEF");
+        assert_eq!(vec!["RMK"], item18.keys().collect::<Vec<_>>());
+        assert_eq!(Item18::default(), Item18::parse(""));
+        assert_eq!("", Item18::parse("RMK/NO PBN").pbn());
+
+        let item18 = Item18::parse("RMK/TCAS EQUIPPED ABC/DEF");
+        assert_eq!(vec!["RMK"], item18.keys().collect::<Vec<_>>());
+        assert_eq!(Some("TCAS EQUIPPED ABC/DEF"), item18.rmk());
+    }
+}
@@ -1,4 +1,14 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use crate::aircraft::{AircraftDatabase, AircraftType};
+use crate::equipment::Capabilities;
 use crate::ffi;
+use crate::geo;
+use crate::item18::Item18;
+use crate::levels::{self, Direction, Units};
+use crate::navdata::{Coord, NavDatabase};
+use crate::route::{Element, Route};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Expr {
@@ -7,7 +17,8 @@ pub enum Expr {
     Binary(BinOp, Box<Expr>, Box<Expr>),
     Unary(UnOp, Box<Expr>),
     Array(Vec<Expr>),
-    // Call(Box<Expr>, Vec<Expr>),
+    Call(Box<Expr>, Vec<Expr>),
+    Index(Box<Expr>, Box<Expr>),
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -22,6 +33,7 @@ pub enum BinOp {
     Lt,
     Mod,
     In,
+    Has,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -29,6 +41,118 @@ pub enum UnOp {
     Not,
 }
 
+impl BinOp {
+    /// Binding power as used by the parser, operators with higher power bind stronger.
+    fn precedence(&self) -> u8 {
+        match self {
+            BinOp::Or => 1,
+            BinOp::And => 3,
+            BinOp::In | BinOp::Has => 5,
+            BinOp::Eq | BinOp::Neq | BinOp::Ge | BinOp::Gt | BinOp::Le | BinOp::Lt => 7,
+            BinOp::Mod => 9,
+        }
+    }
+}
+
+impl Display for BinOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let op = match self {
+            BinOp::And => "and",
+            BinOp::Or => "or",
+            BinOp::Eq => "==",
+            BinOp::Neq => "!=",
+            BinOp::Ge => ">=",
+            BinOp::Gt => ">",
+            BinOp::Le => "<=",
+            BinOp::Lt => "<",
+            BinOp::Mod => "%",
+            BinOp::In => "in",
+            BinOp::Has => "has",
+        };
+        write!(f, "{}", op)
+    }
+}
+
+impl Display for Lit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Lit::Bool(b) => write!(f, "{}", b),
+            Lit::Int(i) => write!(f, "{}", i),
+            Lit::Text(s) => write!(f, "'{}'", s),
+        }
+    }
+}
+
+/// Writes the expression in the rule language, so that it parses to the same expression again.
+impl Display for Expr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let list = |f: &mut std::fmt::Formatter<'_>, exprs: &[Expr]| {
+            for (i, expr) in exprs.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                write!(f, "{}", expr)?;
+            }
+            Ok(())
+        };
+        match self {
+            Expr::Lit(lit) => write!(f, "{}", lit),
+            Expr::Ident(id) => write!(f, "{}", id),
+            Expr::Binary(op, lhs, rhs) => {
+                // Operators are left-associative
+                match lhs.as_ref() {
+                    Expr::Binary(lhs_op, ..) if lhs_op.precedence() < op.precedence() => {
+                        write!(f, "({})", lhs)?
+                    }
+                    _ => write!(f, "{}", lhs)?,
+                }
+                write!(f, " {} ", op)?;
+                match rhs.as_ref() {
+                    Expr::Binary(rhs_op, ..) if rhs_op.precedence() <= op.precedence() => {
+                        write!(f, "({})", rhs)
+                    }
+                    _ => write!(f, "{}", rhs),
+                }
+            }
+            Expr::Unary(UnOp::Not, expr) => match expr.as_ref() {
+                Expr::Binary(..) => write!(f, "!({})", expr),
+                _ => write!(f, "!{}", expr),
+            },
+            Expr::Array(exprs) => {
+                write!(f, "[")?;
+                list(f, exprs)?;
+                write!(f, "]")
+            }
+            Expr::Call(callee, args) => {
+                write!(f, "{}(", callee)?;
+                list(f, args)?;
+                write!(f, ")")
+            }
+            Expr::Index(base, key) => write!(f, "{}[{}]", base, key),
+        }
+    }
+}
+
+/// Lookup table of a rule file, indexed by text, e.g. `max_rfl[arr]`.
+///
+/// The entry `*` is used for keys which are not in the table. Without it, looking up a missing key
+/// is an error.
+pub type Table = HashMap<String, Expr>;
+
+/// Key of the entry used for keys which are not in a table.
+pub const TABLE_DEFAULT_KEY: &str = "*";
+
+/// Data besides the flight plan, which expressions can refer to.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Context<'a> {
+    /// Navigation database, if sector files are loaded.
+    pub navdata: Option<&'a NavDatabase>,
+    /// Aircraft performance database, if aircraft files are loaded.
+    pub aircraft: Option<&'a AircraftDatabase>,
+    /// Lookup tables of the rule file.
+    pub tables: Option<&'a HashMap<String, Table>>,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Lit {
     Bool(bool),
@@ -36,9 +160,127 @@ pub enum Lit {
     Text(String),
 }
 
-fn ident(ident: &str, fp: &ffi::FlightPlan) -> Result<Lit, &'static str> {
+fn capabilities(fp: &ffi::FlightPlan) -> Capabilities {
+    Capabilities::parse(&fp.ac.equip, Item18::parse(&fp.remarks).pbn())
+}
+
+fn text_array(items: Vec<String>) -> Expr {
+    Expr::Array(items.into_iter().map(|s| Expr::Lit(Lit::Text(s))).collect())
+}
+
+/// Route text and initial level of the portions flown under `rule`.
+///
+/// A missing portion has an empty route and level 0.
+fn portion(fp: &ffi::FlightPlan, rule: ffi::FlightRule) -> (String, u32) {
+    let portions: Vec<_> = Route::parse(&fp.route)
+        .portions(&fp.rule, fp.rfl)
+        .into_iter()
+        .filter(|p| p.rule == rule && !p.elements.is_empty())
+        .collect();
+    let text: Vec<String> = portions.iter().map(|p| p.text()).collect();
+    let level = portions.first().and_then(|p| p.level).unwrap_or(0);
+    (text.join(" "), level)
+}
+
+fn navdata<'a>(ctx: &Context<'a>) -> Result<&'a NavDatabase, &'static str> {
+    ctx.navdata.ok_or("No navigation database loaded")
+}
+
+fn aircraft<'a>(ctx: &Context<'a>) -> Result<&'a AircraftDatabase, &'static str> {
+    ctx.aircraft.ok_or("No aircraft database loaded")
+}
+
+/// Performance data of the filed aircraft type. Rules can check `ac_known` first, as an unknown
+/// type is an error.
+fn aircraft_type<'a>(
+    fp: &ffi::FlightPlan,
+    ctx: &Context<'a>,
+) -> Result<&'a AircraftType, &'static str> {
+    aircraft(ctx)?
+        .get(&fp.ac.icao)
+        .ok_or("Aircraft type not in aircraft database")
+}
+
+/// Coordinates from the departure via all known route points to the destination.
+fn route_coords(fp: &ffi::FlightPlan, ctx: &Context) -> Result<Vec<Coord>, &'static str> {
+    navdata(ctx)?
+        .route_coords(&fp.dep, &Route::parse(&fp.route), &fp.arr)
+        .ok_or("Departure or destination not in navigation database")
+}
+
+fn ident(ident: &str, fp: &ffi::FlightPlan, ctx: &Context) -> Result<Expr, &'static str> {
+    match ident {
+        "equip" => Ok(text_array(capabilities(fp).equipment())),
+        "surv" => Ok(text_array(capabilities(fp).surveillance)),
+        "pbn" => Ok(text_array(capabilities(fp).pbn)),
+        "rnav1" => Ok(Expr::Lit(Lit::Bool(capabilities(fp).is_rnav1()))),
+        "rnav5" => Ok(Expr::Lit(Lit::Bool(capabilities(fp).is_rnav5()))),
+        "rnp1" => Ok(Expr::Lit(Lit::Bool(capabilities(fp).is_rnp1()))),
+        "rnp_apch" => Ok(Expr::Lit(Lit::Bool(capabilities(fp).is_rnp_apch()))),
+        "datalink" => Ok(Expr::Lit(Lit::Bool(capabilities(fp).has_datalink()))),
+        "airways" => Ok(text_array(
+            Route::parse(&fp.route)
+                .elements
+                .into_iter()
+                .filter_map(|e| match e {
+                    Element::Airway(name) => Some(name),
+                    _ => None,
+                })
+                .collect(),
+        )),
+        "ifr_portion" => Ok(Expr::Lit(Lit::Text(portion(fp, ffi::FlightRule::Ifr).0))),
+        "vfr_portion" => Ok(Expr::Lit(Lit::Text(portion(fp, ffi::FlightRule::Vfr).0))),
+        "ifr_level" => Ok(Expr::Lit(Lit::Int(
+            portion(fp, ffi::FlightRule::Ifr).1.into(),
+        ))),
+        "vfr_level" => Ok(Expr::Lit(Lit::Int(
+            portion(fp, ffi::FlightRule::Vfr).1.into(),
+        ))),
+        "unknown_points" => Ok(text_array(
+            navdata(ctx)?.unknown_points(&Route::parse(&fp.route)),
+        )),
+        "unknown_airways" => Ok(text_array(
+            navdata(ctx)?.unknown_airways(&Route::parse(&fp.route)),
+        )),
+        "ac_known" => Ok(Expr::Lit(Lit::Bool(
+            aircraft(ctx)?.get(&fp.ac.icao).is_some(),
+        ))),
+        "ac_ceiling" => Ok(Expr::Lit(Lit::Int(aircraft_type(fp, ctx)?.ceiling.into()))),
+        "ac_cruise_speed" => Ok(Expr::Lit(Lit::Int(
+            aircraft_type(fp, ctx)?.cruise_speed.into(),
+        ))),
+        "ac_category" => Ok(Expr::Lit(Lit::Text(
+            aircraft_type(fp, ctx)?.category.clone(),
+        ))),
+        "ac_recat" => Ok(Expr::Lit(Lit::Text(aircraft_type(fp, ctx)?.recat.clone()))),
+        "broken_segment" => Ok(Expr::Lit(Lit::Text(
+            navdata(ctx)?
+                .broken_segment(&Route::parse(&fp.route))
+                .map(|s| s.to_string())
+                .unwrap_or_default(),
+        ))),
+        "route_distance_nm" => {
+            let distance = geo::path_distance_nm(&route_coords(fp, ctx)?);
+            Ok(Expr::Lit(Lit::Int(distance.round() as i64)))
+        }
+        "direct_distance_nm" => {
+            let coords = route_coords(fp, ctx)?;
+            let distance = geo::distance_nm(coords[0], coords[coords.len() - 1]);
+            Ok(Expr::Lit(Lit::Int(distance.round() as i64)))
+        }
+        "route_track" => {
+            let track = geo::average_track(&route_coords(fp, ctx)?).ok_or("Route has no track")?;
+            let track = geo::magnetic(track, navdata(ctx)?.magnetic_variation());
+            Ok(Expr::Lit(Lit::Int(track.round() as i64 % 360)))
+        }
+        ident => lit_ident(ident, fp).map(Expr::Lit),
+    }
+}
+
+fn lit_ident(ident: &str, fp: &ffi::FlightPlan) -> Result<Lit, &'static str> {
     match ident {
         "ac_type" => Ok(Lit::Text(fp.ac.typ.to_string())),
+        "ac_icao" => Ok(Lit::Text(fp.ac.icao.to_string())),
         "ac_wtc" => Ok(Lit::Text(fp.ac.wtc.to_string())),
         "ac_faa_equip_code" => Ok(Lit::Text(fp.ac.faa_equip_code.to_string())),
         "rnav" => Ok(Lit::Bool(fp.ac.faa_equip_code.is_rnav())),
@@ -48,6 +290,7 @@ fn ident(ident: &str, fp: &ffi::FlightPlan) -> Result<Lit, &'static str> {
         "rule" => Ok(Lit::Text(fp.rule.to_string())),
         "cfl" => Ok(Lit::Int(fp.cfl.into())),
         "rfl" => Ok(Lit::Int(fp.rfl.into())),
+        "in_rvsm_band" => Ok(Lit::Bool(levels::is_in_rvsm_band(fp.rfl))),
         "dep" => Ok(Lit::Text(fp.dep.clone())),
         "dep_rwy" => Ok(Lit::Text(fp.dep_rwy.clone())),
         "arr" => Ok(Lit::Text(fp.arr.clone())),
@@ -64,21 +307,154 @@ fn ident(ident: &str, fp: &ffi::FlightPlan) -> Result<Lit, &'static str> {
     }
 }
 
-fn eval_inner(expr: &Expr, fp: &ffi::FlightPlan) -> Result<Expr, &'static str> {
+/// Value of an item 18 indicator.
+///
+/// `STS` evaluates to an array of its reasons and `RVR` to an integer, all other indicators to
+/// their text. A missing indicator evaluates to `''`, or `[]` for `STS`.
+fn item18(key: &str, fp: &ffi::FlightPlan) -> Result<Expr, &'static str> {
+    let item18 = Item18::parse(&fp.remarks);
+    match key {
+        "STS" => Ok(text_array(item18.sts())),
+        "RVR" if item18.get(key).is_some() => item18
+            .rvr()
+            .map(|rvr| Expr::Lit(Lit::Int(rvr.into())))
+            .ok_or("RVR is not a number"),
+        key => Ok(Expr::Lit(Lit::Text(item18.get(key).unwrap_or("").into()))),
+    }
+}
+
+/// Whether the requested level is a valid cruising level for the direction of flight.
+///
+/// The direction is either `'E'`, `'W'` or a magnetic track in degrees. Metric levels are
+/// checked if `'M'` is given as unit.
+fn valid_cruise_level(
+    direction: &Expr,
+    units: Option<&Expr>,
+    fp: &ffi::FlightPlan,
+) -> Result<Expr, &'static str> {
+    let direction = match direction {
+        Expr::Lit(Lit::Text(s)) => {
+            Direction::from_letter(s).ok_or("Direction must be 'E' or 'W'")?
+        }
+        Expr::Lit(Lit::Int(track)) => Direction::from_track(*track),
+        _ => return Err("Direction must be 'E', 'W' or a track"),
+    };
+    let units = match units {
+        None => Units::Feet,
+        Some(Expr::Lit(Lit::Text(s))) if s == "M" => Units::Metric,
+        Some(Expr::Lit(Lit::Text(s))) if s == "F" => Units::Feet,
+        Some(_) => return Err("Units must be 'F' or 'M'"),
+    };
+    Ok(Expr::Lit(Lit::Bool(levels::is_valid_cruise_level(
+        fp.rfl, direction, &fp.rule, units,
+    ))))
+}
+
+/// Whether the text matches a wildcard pattern, where `*` matches any number of characters and
+/// `?` exactly one.
+fn matches_pattern(text: &str, pattern: &str) -> bool {
+    let text: Vec<char> = text.chars().collect();
+    let pattern: Vec<char> = pattern.chars().collect();
+    let (mut t, mut p) = (0, 0);
+    // Position after the last `*` and the text position it was matched up to
+    let mut star = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p + 1, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                t += 1;
+                p += 1;
+            }
+            _ => match star {
+                Some((star_p, star_t)) => {
+                    p = star_p;
+                    t = star_t + 1;
+                    star = Some((star_p, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+fn call(name: &str, args: &[Expr], fp: &ffi::FlightPlan) -> Result<Expr, &'static str> {
+    match (name, args) {
+        ("valid_cruise_level", [direction]) => valid_cruise_level(direction, None, fp),
+        ("valid_cruise_level", [direction, units]) => {
+            valid_cruise_level(direction, Some(units), fp)
+        }
+        ("valid_cruise_level", _) => Err("valid_cruise_level expects a direction"),
+        ("len", [Expr::Array(items)]) => Ok(Expr::Lit(Lit::Int(items.len() as i64))),
+        ("len", [Expr::Lit(Lit::Text(s))]) => Ok(Expr::Lit(Lit::Int(s.chars().count() as i64))),
+        ("len", _) => Err("len expects an array or text"),
+        ("item18", [Expr::Lit(Lit::Text(key))]) => item18(key, fp),
+        ("item18", _) => Err("item18 expects a single text argument"),
+        ("matches", [Expr::Lit(Lit::Text(text)), Expr::Lit(Lit::Text(pattern))]) => {
+            Ok(Expr::Lit(Lit::Bool(matches_pattern(text, pattern))))
+        }
+        ("matches", _) => Err("matches expects a text and a pattern"),
+        _ => Err("Function is not implemented"),
+    }
+}
+
+/// Table referred to by the expression, if it is the name of one.
+fn table<'a>(expr: &Expr, ctx: &Context<'a>) -> Option<&'a Table> {
+    match expr {
+        Expr::Ident(name) => ctx.tables?.get(name),
+        _ => None,
+    }
+}
+
+fn table_key(key: Expr) -> Result<String, &'static str> {
+    match key {
+        Expr::Lit(Lit::Text(key)) => Ok(key),
+        Expr::Lit(Lit::Int(key)) => Ok(key.to_string()),
+        _ => Err("Tables can only be indexed by text or numbers"),
+    }
+}
+
+fn index(table: &Table, key: Expr) -> Result<Expr, &'static str> {
+    let key = table_key(key)?;
+    table
+        .get(&key)
+        .or_else(|| table.get(TABLE_DEFAULT_KEY))
+        .cloned()
+        .ok_or("Key not in table")
+}
+
+fn eval_inner(expr: &Expr, fp: &ffi::FlightPlan, ctx: &Context) -> Result<Expr, &'static str> {
     match &expr {
         Expr::Lit(lit) => Ok(Expr::Lit(lit.clone())),
-        Expr::Ident(id) => Ok(Expr::Lit(ident(id, fp)?)),
+        Expr::Ident(id) => ident(id, fp, ctx),
+        Expr::Binary(BinOp::In, lhs, rhs) if table(rhs, ctx).is_some() => {
+            let key = table_key(eval_inner(lhs, fp, ctx)?)?;
+            let table = table(rhs, ctx).ok_or("Unknown table")?;
+            Ok(Expr::Lit(Lit::Bool(table.contains_key(&key))))
+        }
+        Expr::Binary(op @ (BinOp::And | BinOp::Or), lhs, rhs) => {
+            // Short-circuit, so that e.g. `arr in max_rfl and rfl > max_rfl[arr]` is not an error.
+            // An error of the right operand is only reported if the left one does not decide the
+            // value, e.g. `false and unknown_points has 'X'` is false even without navdata.
+            let lhs = eval_inner(lhs, fp, ctx)?;
+            match (op, lhs) {
+                (BinOp::And, Expr::Lit(Lit::Bool(false))) => Ok(Expr::Lit(Lit::Bool(false))),
+                (BinOp::Or, Expr::Lit(Lit::Bool(true))) => Ok(Expr::Lit(Lit::Bool(true))),
+                (_, Expr::Lit(Lit::Bool(_))) => match eval_inner(rhs, fp, ctx)? {
+                    rhs @ Expr::Lit(Lit::Bool(_)) => Ok(rhs),
+                    _ => Err("Invalid binary operation"),
+                },
+                _ => Err("Invalid binary operation"),
+            }
+        }
         Expr::Binary(op, lhs, rhs) => {
-            let lhs = eval_inner(lhs, fp)?;
-            let rhs = eval_inner(rhs, fp)?;
+            let lhs = eval_inner(lhs, fp, ctx)?;
+            let rhs = eval_inner(rhs, fp, ctx)?;
 
             match (op, lhs, rhs) {
-                (BinOp::And, Expr::Lit(Lit::Bool(lhs)), Expr::Lit(Lit::Bool(rhs))) => {
-                    Ok(Expr::Lit(Lit::Bool(lhs && rhs)))
-                }
-                (BinOp::Or, Expr::Lit(Lit::Bool(lhs)), Expr::Lit(Lit::Bool(rhs))) => {
-                    Ok(Expr::Lit(Lit::Bool(lhs || rhs)))
-                }
                 (BinOp::Eq, lhs, rhs) => Ok(Expr::Lit(Lit::Bool(lhs == rhs))),
                 (BinOp::Neq, lhs, rhs) => Ok(Expr::Lit(Lit::Bool(lhs != rhs))),
                 (BinOp::Lt, Expr::Lit(Lit::Int(lhs)), Expr::Lit(Lit::Int(rhs))) => {
@@ -93,20 +469,26 @@ fn eval_inner(expr: &Expr, fp: &ffi::FlightPlan) -> Result<Expr, &'static str> {
                 (BinOp::Ge, Expr::Lit(Lit::Int(lhs)), Expr::Lit(Lit::Int(rhs))) => {
                     Ok(Expr::Lit(Lit::Bool(lhs >= rhs)))
                 }
-                (BinOp::Mod, Expr::Lit(Lit::Int(lhs)), Expr::Lit(Lit::Int(rhs))) => {
-                    Ok(Expr::Lit(Lit::Int(lhs % rhs)))
-                }
+                (BinOp::Mod, Expr::Lit(Lit::Int(lhs)), Expr::Lit(Lit::Int(rhs))) => Ok(Expr::Lit(
+                    Lit::Int(lhs.checked_rem(rhs).ok_or("Division by zero")?),
+                )),
                 (BinOp::In, item @ (Expr::Lit(_) | Expr::Array(_)), Expr::Array(exprs)) => {
                     Ok(Expr::Lit(Lit::Bool(exprs.contains(&item))))
                 }
                 (BinOp::In, Expr::Lit(Lit::Text(s1)), Expr::Lit(Lit::Text(s2))) => {
                     Ok(Expr::Lit(Lit::Bool(s2.contains(&s1))))
                 }
+                (BinOp::Has, Expr::Array(exprs), item @ (Expr::Lit(_) | Expr::Array(_))) => {
+                    Ok(Expr::Lit(Lit::Bool(exprs.contains(&item))))
+                }
+                (BinOp::Has, Expr::Lit(Lit::Text(s1)), Expr::Lit(Lit::Text(s2))) => {
+                    Ok(Expr::Lit(Lit::Bool(s1.contains(&s2))))
+                }
                 _ => Err("Invalid binary operation"),
             }
         }
         Expr::Unary(op, expr) => {
-            let expr = eval_inner(expr, fp)?;
+            let expr = eval_inner(expr, fp, ctx)?;
             match (op, expr) {
                 (UnOp::Not, Expr::Lit(Lit::Bool(val))) => Ok(Expr::Lit(Lit::Bool(!val))),
                 _ => Err("Cannot negate this expression"),
@@ -115,20 +497,138 @@ fn eval_inner(expr: &Expr, fp: &ffi::FlightPlan) -> Result<Expr, &'static str> {
         Expr::Array(exprs) => {
             let mut evaluated_exprs = vec![];
             for expr in exprs {
-                evaluated_exprs.push(eval_inner(expr, fp)?);
+                evaluated_exprs.push(eval_inner(expr, fp, ctx)?);
             }
             Ok(Expr::Array(evaluated_exprs))
         }
+        Expr::Call(callee, args) => {
+            let name = match callee.as_ref() {
+                Expr::Ident(name) => name,
+                _ => return Err("Only functions can be called"),
+            };
+            let mut evaluated_args = vec![];
+            for arg in args {
+                evaluated_args.push(eval_inner(arg, fp, ctx)?);
+            }
+            call(name, &evaluated_args, fp)
+        }
+        Expr::Index(base, key) => {
+            let key = eval_inner(key, fp, ctx)?;
+            if let Some(table) = table(base, ctx) {
+                return index(table, key);
+            }
+            match (eval_inner(base, fp, ctx)?, key) {
+                (Expr::Array(exprs), Expr::Lit(Lit::Int(i))) => usize::try_from(i)
+                    .ok()
+                    .and_then(|i| exprs.get(i).cloned())
+                    .ok_or("Index out of range"),
+                _ => Err("Only tables and arrays can be indexed"),
+            }
+        }
     }
 }
 
-pub fn eval_cond(expr: &Expr, fp: &ffi::FlightPlan) -> Result<bool, &'static str> {
-    if let Expr::Lit(Lit::Bool(val)) = eval_inner(expr, fp)? {
+pub fn eval_cond(expr: &Expr, fp: &ffi::FlightPlan, ctx: &Context) -> Result<bool, &'static str> {
+    if let Expr::Lit(Lit::Bool(val)) = eval_inner(expr, fp, ctx)? {
         return Ok(val);
     }
     Err("Expression did not evaluate to true or false")
 }
 
+/// An evaluated expression together with its evaluated sub-expressions, see [`explain`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trace {
+    pub expr: Expr,
+    pub value: Result<Expr, &'static str>,
+    /// Literals, tables and the operands of `and` and `or` after the deciding one are left out.
+    pub children: Vec<Trace>,
+}
+
+impl Trace {
+    /// One line per expression, e.g. `rfl > 23000 -> true`, sub-expressions indented by two
+    /// spaces.
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![match &self.value {
+            Ok(value) => format!("{} -> {}", self.expr, value),
+            Err(e) => format!("{} -> error: {}", self.expr, e),
+        }];
+        for child in &self.children {
+            lines.extend(child.lines().into_iter().map(|line| format!("  {}", line)));
+        }
+        lines
+    }
+
+    /// The innermost expression which could not be evaluated, if any.
+    pub fn error(&self) -> Option<&Trace> {
+        if self.value.is_ok() {
+            return None;
+        }
+        Some(self.children.iter().find_map(Trace::error).unwrap_or(self))
+    }
+}
+
+impl Display for Trace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.lines().join("\n"))
+    }
+}
+
+/// Operands of a chain of the same operator, e.g. `a`, `b` and `c` of `a and b and c`.
+fn operands(expr: &Expr, op: BinOp) -> Vec<&Expr> {
+    match expr {
+        Expr::Binary(expr_op, lhs, rhs) if *expr_op == op => {
+            let mut terms = operands(lhs, op);
+            terms.extend(operands(rhs, op));
+            terms
+        }
+        expr => vec![expr],
+    }
+}
+
+/// Evaluates an expression like [`eval_cond`] and records the value of every sub-expression.
+pub fn explain(expr: &Expr, fp: &ffi::FlightPlan, ctx: &Context) -> Trace {
+    let sub_exprs: Vec<&Expr> = match expr {
+        Expr::Binary(op @ (BinOp::And | BinOp::Or), ..) => {
+            // Like the evaluation, stop at the operand which decides the result
+            let mut children = vec![];
+            for operand in operands(expr, *op) {
+                let trace = explain(operand, fp, ctx);
+                let decides = match (&trace.value, op) {
+                    (Ok(Expr::Lit(Lit::Bool(value))), BinOp::And) => !value,
+                    (Ok(Expr::Lit(Lit::Bool(value))), _) => *value,
+                    _ => true,
+                };
+                children.push(trace);
+                if decides {
+                    break;
+                }
+            }
+            return Trace {
+                expr: expr.clone(),
+                value: eval_inner(expr, fp, ctx),
+                children,
+            };
+        }
+        Expr::Binary(BinOp::In, lhs, rhs) if table(rhs, ctx).is_some() => vec![lhs],
+        Expr::Binary(_, lhs, rhs) => vec![lhs, rhs],
+        Expr::Unary(_, expr) => vec![expr],
+        Expr::Array(exprs) => exprs.iter().collect(),
+        Expr::Call(_, args) => args.iter().collect(),
+        Expr::Index(base, key) if table(base, ctx).is_some() => vec![key],
+        Expr::Index(base, key) => vec![base, key],
+        Expr::Lit(_) | Expr::Ident(_) => vec![],
+    };
+    Trace {
+        expr: expr.clone(),
+        value: eval_inner(expr, fp, ctx),
+        children: sub_exprs
+            .into_iter()
+            .filter(|expr| !matches!(expr, Expr::Lit(_)))
+            .map(|expr| explain(expr, fp, ctx))
+            .collect(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -137,8 +637,10 @@ mod tests {
         fn default() -> Self {
             Self {
                 typ: ffi::AircraftType::Landplane,
+                icao: "A320".to_string(),
                 wtc: ffi::WakeTurbulenceCategory::Medium,
                 faa_equip_code: ffi::FaaEquipmentCode::Q,
+                equip: "SDE2E3FGIJ1RWY/LB1".to_string(),
                 eng_typ: ffi::EngineType::Jet,
                 eng_count: 2,
                 is_rvsm_capable: true,
@@ -158,6 +660,7 @@ mod tests {
                 arr: "EDDM".to_string(),
                 sid: "CINDY4S".to_string(),
                 route: "CINDY Z74 HAREM T104 ROKIL".to_string(),
+                remarks: "PBN/A1B1C1D1S2 DOF/221201 STS/HOSP RVR/75 RMK/TCAS".to_string(),
             }
         }
     }
@@ -172,13 +675,64 @@ mod tests {
                 Box::new(Expr::Lit(Lit::Bool(true))),
             )),
         );
-        assert_eq!(Ok(true), eval_cond(&expr, &ffi::FlightPlan::default()))
+        assert_eq!(
+            Ok(true),
+            eval_cond(&expr, &ffi::FlightPlan::default(), &Context::default())
+        )
+    }
+
+    #[test]
+    fn ident_types() {
+        use crate::lang::types::{ident_type, Type};
+
+        let fp = ffi::FlightPlan::default();
+        let names = "equip surv pbn airways rnav1 rnav5 rnp1 rnp_apch datalink rnav \
+            ac_is_rvsm_capable in_rvsm_band ifr_level vfr_level ac_eng_count cfl rfl ifr_portion \
+            vfr_portion ac_type ac_icao ac_wtc ac_faa_equip_code ac_eng_type rule dep dep_rwy arr \
+            sid sidwpt route";
+        for name in names.split_whitespace() {
+            let typ = match eval_inner(&Expr::Ident(name.into()), &fp, &Context::default()) {
+                Ok(Expr::Lit(Lit::Bool(_))) => Type::Bool,
+                Ok(Expr::Lit(Lit::Int(_))) => Type::Int,
+                Ok(Expr::Lit(Lit::Text(_))) => Type::Text,
+                Ok(Expr::Array(items))
+                    if items.iter().all(|i| matches!(i, Expr::Lit(Lit::Text(_)))) =>
+                {
+                    Type::Array(Box::new(Type::Text))
+                }
+                result => panic!("{name} evaluated to {result:?}"),
+            };
+            assert_eq!(Some(typ), ident_type(name), "{name}");
+        }
     }
 
     #[test]
     fn invalid_not_int() {
         let expr = Expr::Unary(UnOp::Not, Box::new(Expr::Lit(Lit::Int(42))));
-        assert!(eval_inner(&expr, &ffi::FlightPlan::default()).is_err())
+        assert!(eval_inner(&expr, &ffi::FlightPlan::default(), &Context::default()).is_err())
+    }
+
+    #[test]
+    fn modulo() {
+        use crate::lang::parser::parse;
+
+        let fp = ffi::FlightPlan {
+            rfl: 35000,
+            ..Default::default()
+        };
+        let eval = |input: &str| eval_inner(&parse(input).unwrap(), &fp, &Context::default());
+        assert_eq!(Ok(Expr::Lit(Lit::Int(1000))), eval("rfl % 2000"));
+        assert_eq!(Err("Division by zero"), eval("rfl % 0"));
+
+        let overflow = Expr::Binary(
+            BinOp::Mod,
+            Box::new(Expr::Lit(Lit::Int(i64::MIN))),
+            Box::new(Expr::Lit(Lit::Int(-1))),
+        );
+        assert_eq!(
+            Err("Division by zero"),
+            eval_inner(&overflow, &fp, &Context::default())
+        );
     }
 
     #[test]
@@ -191,7 +745,10 @@ mod tests {
                 Expr::Lit(Lit::Text("TOBAK".into())),
             ])),
         );
-        assert_eq!(Ok(true), eval_cond(&expr, &ffi::FlightPlan::default()));
+        assert_eq!(
+            Ok(true),
+            eval_cond(&expr, &ffi::FlightPlan::default(), &Context::default())
+        );
         let expr = Expr::Binary(
             BinOp::In,
             Box::new(Expr::Lit(Lit::Text("OBOKA".into()))),
@@ -200,7 +757,10 @@ mod tests {
                 Expr::Lit(Lit::Text("TOBAK".into())),
             ])),
         );
-        assert_eq!(Ok(false), eval_cond(&expr, &ffi::FlightPlan::default()));
+        assert_eq!(
+            Ok(false),
+            eval_cond(&expr, &ffi::FlightPlan::default(), &Context::default())
+        );
     }
 
     #[test]
@@ -212,13 +772,19 @@ mod tests {
                 "TOBAK7M/25C TOBAK Z10 NOSEX DCT KLF".into(),
             ))),
         );
-        assert_eq!(Ok(true), eval_cond(&expr, &ffi::FlightPlan::default()));
+        assert_eq!(
+            Ok(true),
+            eval_cond(&expr, &ffi::FlightPlan::default(), &Context::default())
+        );
         let expr = Expr::Binary(
             BinOp::In,
             Box::new(Expr::Lit(Lit::Text("TOBAK Z10".into()))),
             Box::new(Expr::Lit(Lit::Text("Text".into()))),
         );
-        assert_eq!(Ok(false), eval_cond(&expr, &ffi::FlightPlan::default()));
+        assert_eq!(
+            Ok(false),
+            eval_cond(&expr, &ffi::FlightPlan::default(), &Context::default())
+        );
     }
 
     #[test]
@@ -228,7 +794,10 @@ mod tests {
             Box::new(Expr::Lit(Lit::Text("Hello World!".into()))),
             Box::new(Expr::Lit(Lit::Text("Hello World!".into()))),
         );
-        assert_eq!(Ok(true), eval_cond(&expr, &ffi::FlightPlan::default()))
+        assert_eq!(
+            Ok(true),
+            eval_cond(&expr, &ffi::FlightPlan::default(), &Context::default())
+        )
     }
 
     #[test]
@@ -244,7 +813,7 @@ mod tests {
             Box::new(Expr::Lit(Lit::Int(35000))),
         );
 
-        assert_eq!(Ok(true), eval_cond(&expr, &fp))
+        assert_eq!(Ok(true), eval_cond(&expr, &fp, &Context::default()))
     }
 
     #[test]
@@ -259,21 +828,21 @@ mod tests {
             Box::new(Expr::Ident("rfl".into())),
             Box::new(Expr::Lit(Lit::Int(35000))),
         );
-        assert_eq!(Ok(true), eval_cond(&expr, &fp));
+        assert_eq!(Ok(true), eval_cond(&expr, &fp, &Context::default()));
 
         let expr = Expr::Binary(
             BinOp::Le,
             Box::new(Expr::Ident("rfl".into())),
             Box::new(Expr::Lit(Lit::Int(34999))),
         );
-        assert_eq!(Ok(false), eval_cond(&expr, &fp));
+        assert_eq!(Ok(false), eval_cond(&expr, &fp, &Context::default()));
 
         let expr = Expr::Binary(
             BinOp::Le,
             Box::new(Expr::Ident("rfl".into())),
             Box::new(Expr::Lit(Lit::Int(35001))),
         );
-        assert_eq!(Ok(true), eval_cond(&expr, &fp));
+        assert_eq!(Ok(true), eval_cond(&expr, &fp, &Context::default()));
     }
 
     #[test]
@@ -281,8 +850,10 @@ mod tests {
         let fp = ffi::FlightPlan {
             ac: ffi::Aircraft {
                 typ: ffi::AircraftType::Helicopter,
+                icao: "EC35".to_string(),
                 wtc: ffi::WakeTurbulenceCategory::Light,
                 faa_equip_code: ffi::FaaEquipmentCode::G,
+                equip: "SG/C".to_string(),
                 eng_typ: ffi::EngineType::Electric,
                 eng_count: 1,
                 is_rvsm_capable: false,
@@ -291,22 +862,40 @@ mod tests {
         };
 
         let expr = Expr::Ident("ac_type".into());
-        assert_eq!(Ok(Expr::Lit(Lit::Text("H".into()))), eval_inner(&expr, &fp));
+        assert_eq!(
+            Ok(Expr::Lit(Lit::Text("H".into()))),
+            eval_inner(&expr, &fp, &Context::default())
+        );
 
         let expr = Expr::Ident("ac_wtc".into());
-        assert_eq!(Ok(Expr::Lit(Lit::Text("L".into()))), eval_inner(&expr, &fp));
+        assert_eq!(
+            Ok(Expr::Lit(Lit::Text("L".into()))),
+            eval_inner(&expr, &fp, &Context::default())
+        );
 
         let expr = Expr::Ident("ac_faa_equip_code".into());
-        assert_eq!(Ok(Expr::Lit(Lit::Text("G".into()))), eval_inner(&expr, &fp));
+        assert_eq!(
+            Ok(Expr::Lit(Lit::Text("G".into()))),
+            eval_inner(&expr, &fp, &Context::default())
+        );
 
         let expr = Expr::Ident("ac_eng_type".into());
-        assert_eq!(Ok(Expr::Lit(Lit::Text("E".into()))), eval_inner(&expr, &fp));
+        assert_eq!(
+            Ok(Expr::Lit(Lit::Text("E".into()))),
+            eval_inner(&expr, &fp, &Context::default())
+        );
 
         let expr = Expr::Ident("ac_eng_count".into());
-        assert_eq!(Ok(Expr::Lit(Lit::Int(1))), eval_inner(&expr, &fp));
+        assert_eq!(
+            Ok(Expr::Lit(Lit::Int(1))),
+            eval_inner(&expr, &fp, &Context::default())
+        );
 
         let expr = Expr::Ident("ac_is_rvsm_capable".into());
-        assert_eq!(Ok(Expr::Lit(Lit::Bool(false))), eval_inner(&expr, &fp));
+        assert_eq!(
+            Ok(Expr::Lit(Lit::Bool(false))),
+            eval_inner(&expr, &fp, &Context::default())
+        );
     }
 
     #[test]
@@ -316,7 +905,10 @@ mod tests {
             ..Default::default()
         };
         let expr = Expr::Ident("rule".into());
-        assert_eq!(Ok(Expr::Lit(Lit::Text("Z".into()))), eval_inner(&expr, &fp))
+        assert_eq!(
+            Ok(Expr::Lit(Lit::Text("Z".into()))),
+            eval_inner(&expr, &fp, &Context::default())
+        )
     }
 
     #[test]
@@ -326,7 +918,10 @@ mod tests {
             ..Default::default()
         };
         let expr = Expr::Ident("cfl".into());
-        assert_eq!(Ok(Expr::Lit(Lit::Int(4000))), eval_inner(&expr, &fp))
+        assert_eq!(
+            Ok(Expr::Lit(Lit::Int(4000))),
+            eval_inner(&expr, &fp, &Context::default())
+        )
     }
 
     #[test]
@@ -336,7 +931,32 @@ mod tests {
             ..Default::default()
         };
         let expr = Expr::Ident("rfl".into());
-        assert_eq!(Ok(Expr::Lit(Lit::Int(35000))), eval_inner(&expr, &fp))
+        assert_eq!(
+            Ok(Expr::Lit(Lit::Int(35000))),
+            eval_inner(&expr, &fp, &Context::default())
+        )
+    }
+
+    #[test]
+    fn var_in_rvsm_band_eval() {
+        let fp = ffi::FlightPlan {
+            rfl: 41000,
+            ..Default::default()
+        };
+        let expr = Expr::Ident("in_rvsm_band".into());
+        assert_eq!(
+            Ok(Expr::Lit(Lit::Bool(true))),
+            eval_inner(&expr, &fp, &Context::default())
+        );
+
+        let fp = ffi::FlightPlan {
+            rfl: 24000,
+            ..Default::default()
+        };
+        assert_eq!(
+            Ok(Expr::Lit(Lit::Bool(false))),
+            eval_inner(&expr, &fp, &Context::default())
+        );
     }
 
     #[test]
@@ -348,7 +968,7 @@ mod tests {
         let expr = Expr::Ident("dep".into());
         assert_eq!(
             Ok(Expr::Lit(Lit::Text("EDDF".into()))),
-            eval_inner(&expr, &fp)
+            eval_inner(&expr, &fp, &Context::default())
         )
     }
 
@@ -361,7 +981,7 @@ mod tests {
         let expr = Expr::Ident("dep_rwy".into());
         assert_eq!(
             Ok(Expr::Lit(Lit::Text("07C".into()))),
-            eval_inner(&expr, &fp)
+            eval_inner(&expr, &fp, &Context::default())
         )
     }
 
@@ -374,7 +994,7 @@ mod tests {
         let expr = Expr::Ident("arr".into());
         assert_eq!(
             Ok(Expr::Lit(Lit::Text("EDDS".into()))),
-            eval_inner(&expr, &fp)
+            eval_inner(&expr, &fp, &Context::default())
         )
     }
 
@@ -387,7 +1007,7 @@ mod tests {
         let expr = Expr::Ident("sid".into());
         assert_eq!(
             Ok(Expr::Lit(Lit::Text("CINDY4S".into()))),
-            eval_inner(&expr, &fp)
+            eval_inner(&expr, &fp, &Context::default())
         )
     }
 
@@ -400,7 +1020,462 @@ mod tests {
         let expr = Expr::Ident("route".into());
         assert_eq!(
             Ok(Expr::Lit(Lit::Text("CINDY Z74 HAREM T104 ROKIL".into()))),
-            eval_inner(&expr, &fp)
+            eval_inner(&expr, &fp, &Context::default())
         )
     }
+
+    #[test]
+    fn var_equipment_eval() {
+        let fp = ffi::FlightPlan::default();
+
+        let expr = Expr::Binary(
+            BinOp::Has,
+            Box::new(Expr::Ident("equip".into())),
+            Box::new(Expr::Lit(Lit::Text("W".into()))),
+        );
+        assert_eq!(Ok(true), eval_cond(&expr, &fp, &Context::default()));
+
+        let expr = Expr::Binary(
+            BinOp::Has,
+            Box::new(Expr::Ident("pbn".into())),
+            Box::new(Expr::Lit(Lit::Text("O1".into()))),
+        );
+        assert_eq!(Ok(false), eval_cond(&expr, &fp, &Context::default()));
+
+        let expr = Expr::Ident("rnav1".into());
+        assert_eq!(Ok(true), eval_cond(&expr, &fp, &Context::default()));
+
+        let expr = Expr::Ident("rnp_apch".into());
+        assert_eq!(Ok(true), eval_cond(&expr, &fp, &Context::default()));
+
+        let expr = Expr::Ident("datalink".into());
+        assert_eq!(Ok(true), eval_cond(&expr, &fp, &Context::default()));
+
+        let fp = ffi::FlightPlan {
+            remarks: String::new(),
+            ..Default::default()
+        };
+        let expr = Expr::Ident("rnav1".into());
+        assert_eq!(Ok(false), eval_cond(&expr, &fp, &Context::default()));
+    }
+
+    #[test]
+    fn item18_call() {
+        let fp = ffi::FlightPlan::default();
+        let call = |key: &str| {
+            Expr::Call(
+                Box::new(Expr::Ident("item18".into())),
+                vec![Expr::Lit(Lit::Text(key.into()))],
+            )
+        };
+
+        let expr = Expr::Binary(
+            BinOp::Has,
+            Box::new(call("STS")),
+            Box::new(Expr::Lit(Lit::Text("HOSP".into()))),
+        );
+        assert_eq!(Ok(true), eval_cond(&expr, &fp, &Context::default()));
+
+        assert_eq!(
+            Ok(Expr::Lit(Lit::Text("221201".into()))),
+            eval_inner(&call("DOF"), &fp, &Context::default())
+        );
+        assert_eq!(
+            Ok(Expr::Lit(Lit::Int(75))),
+            eval_inner(&call("RVR"), &fp, &Context::default())
+        );
+        assert_eq!(
+            Ok(Expr::Lit(Lit::Text("".into()))),
+            eval_inner(&call("OPR"), &fp, &Context::default())
+        );
+
+        let fp = ffi::FlightPlan {
+            remarks: String::new(),
+            ..Default::default()
+        };
+        assert_eq!(
+            Ok(Expr::Array(vec![])),
+            eval_inner(&call("STS"), &fp, &Context::default())
+        );
+        assert!(eval_inner(
+            &Expr::Call(Box::new(Expr::Ident("item18".into())), vec![]),
+            &fp,
+            &Context::default()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn valid_cruise_level_call() {
+        let call =
+            |args: Vec<Expr>| Expr::Call(Box::new(Expr::Ident("valid_cruise_level".into())), args);
+        let fp = ffi::FlightPlan {
+            rfl: 35000,
+            ..Default::default()
+        };
+
+        let expr = call(vec![Expr::Lit(Lit::Text("E".into()))]);
+        assert_eq!(Ok(true), eval_cond(&expr, &fp, &Context::default()));
+
+        let expr = call(vec![Expr::Lit(Lit::Text("W".into()))]);
+        assert_eq!(Ok(false), eval_cond(&expr, &fp, &Context::default()));
+
+        let expr = call(vec![Expr::Lit(Lit::Int(270))]);
+        assert_eq!(Ok(false), eval_cond(&expr, &fp, &Context::default()));
+
+        let expr = call(vec![
+            Expr::Lit(Lit::Text("E".into())),
+            Expr::Lit(Lit::Text("M".into())),
+        ]);
+        assert_eq!(Ok(false), eval_cond(&expr, &fp, &Context::default()));
+
+        let expr = call(vec![Expr::Lit(Lit::Text("N".into()))]);
+        assert!(eval_cond(&expr, &fp, &Context::default()).is_err());
+    }
+
+    #[test]
+    fn var_portion_eval() {
+        let fp = ffi::FlightPlan {
+            rule: ffi::FlightRule::Yankee,
+            rfl: 23000,
+            route: "CINDY Z74 HAREM/N0120A045 VFR DCT ROKIL".into(),
+            ..Default::default()
+        };
+        let eval = |id: &str| eval_inner(&Expr::Ident(id.into()), &fp, &Context::default());
+        assert_eq!(
+            Ok(Expr::Lit(Lit::Text("CINDY Z74 HAREM/N0120A045".into()))),
+            eval("ifr_portion")
+        );
+        assert_eq!(Ok(Expr::Lit(Lit::Int(23000))), eval("ifr_level"));
+        assert_eq!(
+            Ok(Expr::Lit(Lit::Text("DCT ROKIL".into()))),
+            eval("vfr_portion")
+        );
+        assert_eq!(Ok(Expr::Lit(Lit::Int(4500))), eval("vfr_level"));
+
+        let fp = ffi::FlightPlan::default();
+        let eval = |id: &str| eval_inner(&Expr::Ident(id.into()), &fp, &Context::default());
+        assert_eq!(
+            Ok(Expr::Lit(Lit::Text("CINDY Z74 HAREM T104 ROKIL".into()))),
+            eval("ifr_portion")
+        );
+        assert_eq!(Ok(Expr::Lit(Lit::Text("".into()))), eval("vfr_portion"));
+        assert_eq!(Ok(Expr::Lit(Lit::Int(0))), eval("vfr_level"));
+    }
+
+    #[test]
+    fn unknown_points_eval() {
+        let mut navdata = NavDatabase::default();
+        crate::navdata::sector::parse_sct(
+            "[FIXES]\nCINDY N049.48.00.000 E008.58.00.000\nHAREM N049.40.00.000 E009.30.00.000\n[LOW AIRWAY]\nZ74 CINDY CINDY HAREM HAREM\n",
+            &mut navdata,
+        );
+        let ctx = Context {
+            navdata: Some(&navdata),
+            ..Default::default()
+        };
+        let fp = ffi::FlightPlan {
+            route: "CINDI Z74 HAREM T104 ROKIL".into(),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            Ok(Expr::Array(vec![
+                Expr::Lit(Lit::Text("CINDI".into())),
+                Expr::Lit(Lit::Text("ROKIL".into())),
+            ])),
+            eval_inner(&Expr::Ident("unknown_points".into()), &fp, &ctx)
+        );
+        assert_eq!(
+            Ok(Expr::Array(vec![Expr::Lit(Lit::Text("T104".into()))])),
+            eval_inner(&Expr::Ident("unknown_airways".into()), &fp, &ctx)
+        );
+        assert_eq!(
+            Ok(Expr::Lit(Lit::Text("CINDI Z74 HAREM".into()))),
+            eval_inner(&Expr::Ident("broken_segment".into()), &fp, &ctx)
+        );
+
+        let expr = Expr::Binary(
+            BinOp::Gt,
+            Box::new(Expr::Call(
+                Box::new(Expr::Ident("len".into())),
+                vec![Expr::Ident("unknown_points".into())],
+            )),
+            Box::new(Expr::Lit(Lit::Int(0))),
+        );
+        assert_eq!(Ok(true), eval_cond(&expr, &fp, &ctx));
+
+        // Without navigation database
+        assert!(eval_cond(&expr, &fp, &Context::default()).is_err());
+    }
+
+    #[test]
+    fn route_geometry_eval() {
+        let mut navdata = NavDatabase::default();
+        crate::navdata::sector::parse_sct(
+            "[AIRPORT]\nAAAA 118.000 N000.00.00.000 E000.00.00.000 D\nBBBB 118.000 N001.00.00.000 E001.00.00.000 D\n[FIXES]\nFIXA N000.00.00.000 E001.00.00.000\n",
+            &mut navdata,
+        );
+        navdata.set_magnetic_variation(5.0);
+        let ctx = Context {
+            navdata: Some(&navdata),
+            ..Default::default()
+        };
+        let fp = ffi::FlightPlan {
+            dep: "AAAA".into(),
+            arr: "BBBB".into(),
+            route: "FIXA".into(),
+            ..Default::default()
+        };
+        let eval = |id: &str| eval_inner(&Expr::Ident(id.into()), &fp, &ctx);
+
+        assert_eq!(Ok(Expr::Lit(Lit::Int(120))), eval("route_distance_nm"));
+        assert_eq!(Ok(Expr::Lit(Lit::Int(85))), eval("direct_distance_nm"));
+        assert_eq!(Ok(Expr::Lit(Lit::Int(40))), eval("route_track"));
+
+        let fp = ffi::FlightPlan {
+            arr: "CCCC".into(),
+            ..fp
+        };
+        assert!(eval_inner(&Expr::Ident("route_distance_nm".into()), &fp, &ctx).is_err());
+    }
+
+    #[test]
+    fn aircraft_eval() {
+        let mut aircraft = AircraftDatabase::default();
+        aircraft.parse_csv("A320,39000,450,C,D\n");
+        let ctx = Context {
+            aircraft: Some(&aircraft),
+            ..Default::default()
+        };
+        let fp = ffi::FlightPlan::default();
+        let eval = |id: &str, fp: &ffi::FlightPlan| eval_inner(&Expr::Ident(id.into()), fp, &ctx);
+
+        assert_eq!(
+            Ok(Expr::Lit(Lit::Text("A320".into()))),
+            eval("ac_icao", &fp)
+        );
+        assert_eq!(Ok(Expr::Lit(Lit::Int(39000))), eval("ac_ceiling", &fp));
+        assert_eq!(Ok(Expr::Lit(Lit::Int(450))), eval("ac_cruise_speed", &fp));
+        assert_eq!(
+            Ok(Expr::Lit(Lit::Text("C".into()))),
+            eval("ac_category", &fp)
+        );
+        assert_eq!(Ok(Expr::Lit(Lit::Text("D".into()))), eval("ac_recat", &fp));
+        assert_eq!(Ok(Expr::Lit(Lit::Bool(true))), eval("ac_known", &fp));
+
+        // Unknown type
+        let fp = ffi::FlightPlan {
+            ac: ffi::Aircraft {
+                icao: "ZZZZ".into(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(Ok(Expr::Lit(Lit::Bool(false))), eval("ac_known", &fp));
+        assert!(eval("ac_ceiling", &fp).is_err());
+        assert!(eval("ac_recat", &fp).is_err());
+        let guarded = Expr::Binary(
+            BinOp::And,
+            Box::new(Expr::Ident("ac_known".into())),
+            Box::new(Expr::Binary(
+                BinOp::Gt,
+                Box::new(Expr::Ident("rfl".into())),
+                Box::new(Expr::Ident("ac_ceiling".into())),
+            )),
+        );
+        assert_eq!(
+            Ok(Expr::Lit(Lit::Bool(false))),
+            eval_inner(&guarded, &fp, &ctx)
+        );
+
+        // Without aircraft database
+        let expr = Expr::Ident("ac_ceiling".into());
+        assert!(eval_inner(&expr, &fp, &Context::default()).is_err());
+    }
+
+    #[test]
+    fn table_eval() {
+        let tables = HashMap::from([(
+            "max_rfl".to_string(),
+            Table::from([
+                ("EDDS".to_string(), Expr::Lit(Lit::Int(24000))),
+                ("EDDN".to_string(), Expr::Lit(Lit::Int(23000))),
+            ]),
+        )]);
+        let ctx = Context {
+            tables: Some(&tables),
+            ..Default::default()
+        };
+        let index = |key: &str| {
+            Expr::Index(
+                Box::new(Expr::Ident("max_rfl".into())),
+                Box::new(Expr::Lit(Lit::Text(key.into()))),
+            )
+        };
+        let fp = ffi::FlightPlan::default();
+
+        assert_eq!(
+            Ok(Expr::Lit(Lit::Int(24000))),
+            eval_inner(&index("EDDS"), &fp, &ctx)
+        );
+        assert_eq!(
+            Err("Key not in table"),
+            eval_inner(&index("EDDM"), &fp, &ctx)
+        );
+
+        let contains = |key: &str| {
+            Expr::Binary(
+                BinOp::In,
+                Box::new(Expr::Lit(Lit::Text(key.into()))),
+                Box::new(Expr::Ident("max_rfl".into())),
+            )
+        };
+        assert_eq!(Ok(true), eval_cond(&contains("EDDN"), &fp, &ctx));
+        assert_eq!(Ok(false), eval_cond(&contains("EDDM"), &fp, &ctx));
+
+        // The lookup is not evaluated for missing keys
+        let guarded = Expr::Binary(
+            BinOp::And,
+            Box::new(contains("EDDM")),
+            Box::new(Expr::Binary(
+                BinOp::Gt,
+                Box::new(Expr::Ident("rfl".into())),
+                Box::new(index("EDDM")),
+            )),
+        );
+        assert_eq!(Ok(false), eval_cond(&guarded, &fp, &ctx));
+
+        // Default entry
+        let mut tables = tables.clone();
+        tables
+            .get_mut("max_rfl")
+            .unwrap()
+            .insert(TABLE_DEFAULT_KEY.into(), Expr::Lit(Lit::Int(66000)));
+        let ctx = Context {
+            tables: Some(&tables),
+            ..Default::default()
+        };
+        assert_eq!(
+            Ok(Expr::Lit(Lit::Int(66000))),
+            eval_inner(&index("EDDM"), &fp, &ctx)
+        );
+        assert_eq!(Ok(false), eval_cond(&contains("EDDM"), &fp, &ctx));
+
+        // Unknown table
+        assert!(eval_inner(&index("EDDS"), &fp, &Context::default()).is_err());
+
+        // Arrays
+        let array = |i: i64| {
+            Expr::Index(
+                Box::new(Expr::Array(vec![Expr::Lit(Lit::Int(1))])),
+                Box::new(Expr::Lit(Lit::Int(i))),
+            )
+        };
+        assert_eq!(Ok(Expr::Lit(Lit::Int(1))), eval_inner(&array(0), &fp, &ctx));
+        assert_eq!(Err("Index out of range"), eval_inner(&array(1), &fp, &ctx));
+        assert_eq!(Err("Index out of range"), eval_inner(&array(-1), &fp, &ctx));
+        let expr = Expr::Index(
+            Box::new(Expr::Ident("dep".into())),
+            Box::new(Expr::Lit(Lit::Int(0))),
+        );
+        assert_eq!(
+            Err("Only tables and arrays can be indexed"),
+            eval_inner(&expr, &fp, &ctx)
+        );
+    }
+
+    #[test]
+    fn short_circuit_eval() {
+        use crate::lang::parser::parse;
+
+        let fp = ffi::FlightPlan::default();
+        let ctx = Context::default();
+        let eval = |input: &str| eval_cond(&parse(input).unwrap(), &fp, &ctx);
+
+        // The right operand would fail without a navigation database
+        assert_eq!(Ok(false), eval("false and unknown_points has 'X'"));
+        assert_eq!(Ok(true), eval("true or unknown_points has 'X'"));
+        assert_eq!(
+            Err("No navigation database loaded"),
+            eval("true and unknown_points has 'X'")
+        );
+        assert_eq!(
+            Err("No navigation database loaded"),
+            eval("false or unknown_points has 'X'")
+        );
+        // The left operand is always evaluated
+        assert_eq!(
+            Err("No navigation database loaded"),
+            eval("unknown_points has 'X' and false")
+        );
+    }
+
+    #[test]
+    fn matches_eval() {
+        assert!(matches_pattern("EDDS", "ED*"));
+        assert!(matches_pattern("EDDS", "ED?S"));
+        assert!(matches_pattern("EDDS", "*"));
+        assert!(matches_pattern("EDDS", "*D*S"));
+        assert!(matches_pattern("", "*"));
+        assert!(!matches_pattern("LSZH", "ED*"));
+        assert!(!matches_pattern("EDDS", "ED?"));
+        assert!(!matches_pattern("EDDS", "*F*"));
+
+        let expr = Expr::Call(
+            Box::new(Expr::Ident("matches".into())),
+            vec![
+                Expr::Ident("arr".into()),
+                Expr::Lit(Lit::Text("ED*".into())),
+            ],
+        );
+        let fp = ffi::FlightPlan::default();
+        assert_eq!(Ok(true), eval_cond(&expr, &fp, &Context::default()));
+    }
+
+    #[test]
+    fn explain_trace() {
+        use crate::lang::parser::parse;
+
+        let fp = ffi::FlightPlan::default();
+        let expr =
+            parse("dep == 'EDDF' and sidwpt == 'CINDY' and rfl > 23000 and arr == 'EDDN'").unwrap();
+        let trace = explain(&expr, &fp, &Context::default());
+        assert_eq!(Ok(Expr::Lit(Lit::Bool(false))), trace.value);
+        assert_eq!(
+            vec![
+                "dep == 'EDDF' and sidwpt == 'CINDY' and rfl > 23000 and arr == 'EDDN' -> false",
+                "  dep == 'EDDF' -> true",
+                "    dep -> 'EDDF'",
+                "  sidwpt == 'CINDY' -> true",
+                "    sidwpt -> 'CINDY'",
+                "  rfl > 23000 -> true",
+                "    rfl -> 35000",
+                "  arr == 'EDDN' -> false",
+                "    arr -> 'EDDM'",
+            ],
+            trace.lines()
+        );
+
+        // Operands after the deciding one are not evaluated
+        let expr = parse("arr in ['EDDM', 'EDDN'] or unknown_points has 'CINDY'").unwrap();
+        let trace = explain(&expr, &fp, &Context::default());
+        assert_eq!(Ok(Expr::Lit(Lit::Bool(true))), trace.value);
+        assert_eq!(1, trace.children.len());
+
+        let expr = parse("len(unknown_points) > 0").unwrap();
+        assert_eq!(
+            vec![
+                "len(unknown_points) > 0 -> error: No navigation database loaded",
+                "  len(unknown_points) -> error: No navigation database loaded",
+                "    unknown_points -> error: No navigation database loaded",
+            ],
+            explain(&expr, &fp, &Context::default()).lines()
+        );
+        let trace = explain(&expr, &fp, &Context::default());
+        assert_eq!(
+            Some(&Expr::Ident("unknown_points".into())),
+            trace.error().map(|t| &t.expr)
+        );
+    }
 }
@@ -0,0 +1,394 @@
+//! Constraints of conditions made of simple comparisons, e.g. `rfl > 24000 and arr == 'EDDM'`,
+//! which tell without a flight plan whether a condition can be true and whether it implies another
+//! one.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::ast::{BinOp, Expr, Lit, UnOp};
+use super::types;
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum Value {
+    Bool(bool),
+    Int(i64),
+    Text(String),
+}
+
+impl From<&Lit> for Value {
+    fn from(lit: &Lit) -> Self {
+        match lit {
+            Lit::Bool(b) => Value::Bool(*b),
+            Lit::Int(i) => Value::Int(*i),
+            Lit::Text(s) => Value::Text(s.clone()),
+        }
+    }
+}
+
+/// Largest range of integers which is enumerated to compare domains.
+const MAX_RANGE: i128 = 1000;
+
+/// Values an identifier can have.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Domain {
+    /// Values of `==` and `in`, all other values are excluded.
+    allowed: Option<BTreeSet<Value>>,
+    /// Values of `!=` and `!(.. in ..)`.
+    excluded: BTreeSet<Value>,
+    /// Inclusive bounds of integers.
+    min: Option<i64>,
+    max: Option<i64>,
+}
+
+impl Domain {
+    fn contains(&self, value: &Value) -> bool {
+        let in_range = match value {
+            Value::Int(i) => {
+                self.min.is_none_or(|min| *i >= min) && self.max.is_none_or(|max| *i <= max)
+            }
+            _ => true,
+        };
+        in_range
+            && !self.excluded.contains(value)
+            && self.allowed.as_ref().is_none_or(|a| a.contains(value))
+    }
+
+    /// Integers of the range, if it is small enough to be enumerated.
+    fn range(&self) -> Option<Vec<Value>> {
+        let (min, max) = (self.min?, self.max?);
+        if max as i128 - min as i128 >= MAX_RANGE {
+            return None;
+        }
+        Some((min..=max).map(Value::Int).collect())
+    }
+
+    fn is_empty(&self) -> bool {
+        if let Some(allowed) = &self.allowed {
+            return !allowed.iter().any(|v| self.contains(v));
+        }
+        match (self.min, self.max) {
+            (Some(min), Some(max)) if min > max => true,
+            _ => self
+                .range()
+                .is_some_and(|range| !range.iter().any(|v| self.contains(v))),
+        }
+    }
+
+    fn intersect(&mut self, other: &Domain) {
+        self.allowed = match (self.allowed.take(), &other.allowed) {
+            (Some(a), Some(b)) => Some(a.intersection(b).cloned().collect()),
+            (a, b) => a.or_else(|| b.clone()),
+        };
+        self.excluded.extend(other.excluded.iter().cloned());
+        self.min = self.min.max(other.min);
+        self.max = match (self.max, other.max) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+    }
+
+    /// Whether every value of this domain is a value of `other`.
+    fn is_subset(&self, other: &Domain) -> bool {
+        if let Some(values) = self
+            .allowed
+            .clone()
+            .map(Vec::from_iter)
+            .or_else(|| self.range())
+        {
+            return values
+                .iter()
+                .filter(|v| self.contains(v))
+                .all(|v| other.contains(v));
+        }
+        let above = |min: Option<i64>, bound: Option<i64>| match (min, bound) {
+            (_, None) => true,
+            (Some(min), Some(bound)) => min >= bound,
+            (None, Some(_)) => false,
+        };
+        let below = |max: Option<i64>, bound: Option<i64>| match (max, bound) {
+            (_, None) => true,
+            (Some(max), Some(bound)) => max <= bound,
+            (None, Some(_)) => false,
+        };
+        other.allowed.is_none()
+            && above(self.min, other.min)
+            && below(self.max, other.max)
+            && other.excluded.iter().all(|v| !self.contains(v))
+    }
+}
+
+/// Terms of a conjunction, e.g. `a`, `b` and `c` of `a and (b and c)`. `true` has no terms.
+fn conjuncts(expr: &Expr) -> Vec<&Expr> {
+    match expr {
+        Expr::Binary(BinOp::And, lhs, rhs) => {
+            let mut terms = conjuncts(lhs);
+            terms.extend(conjuncts(rhs));
+            terms
+        }
+        Expr::Lit(Lit::Bool(true)) => vec![],
+        expr => vec![expr],
+    }
+}
+
+/// Whether a term looks up a table, e.g. `max_rfl[arr]` or `sidwpt in sid_direction`, whose values
+/// depend on the rule file.
+fn uses_table(term: &Expr) -> bool {
+    match term {
+        Expr::Index(..) => true,
+        Expr::Binary(BinOp::In, _, rhs) if matches!(rhs.as_ref(), Expr::Ident(name) if types::ident_type(name).is_none()) => {
+            true
+        }
+        Expr::Binary(_, lhs, rhs) => uses_table(lhs) || uses_table(rhs),
+        Expr::Unary(_, expr) => uses_table(expr),
+        Expr::Array(items) => items.iter().any(uses_table),
+        Expr::Call(function, args) => uses_table(function) || args.iter().any(uses_table),
+        Expr::Lit(_) | Expr::Ident(_) => false,
+    }
+}
+
+/// Operator with swapped operands, e.g. `<` for `5 > rfl`.
+fn swap(op: BinOp) -> BinOp {
+    match op {
+        BinOp::Lt => BinOp::Gt,
+        BinOp::Le => BinOp::Ge,
+        BinOp::Gt => BinOp::Lt,
+        BinOp::Ge => BinOp::Le,
+        op => op,
+    }
+}
+
+/// Identifier and its domain of a simple comparison.
+fn comparison(term: &Expr) -> Option<(&str, Domain)> {
+    let mut domain = Domain::default();
+    let (ident, op, value) = match term {
+        Expr::Ident(ident) => (ident, BinOp::Eq, Value::Bool(true)),
+        Expr::Unary(UnOp::Not, expr) => {
+            let (ident, domain) = comparison(expr)?;
+            return match (
+                domain.allowed,
+                domain.excluded.is_empty(),
+                domain.min,
+                domain.max,
+            ) {
+                // `!(a in [..])` and `!a`
+                (Some(allowed), true, None, None) => Some((
+                    ident,
+                    Domain {
+                        excluded: allowed,
+                        ..Default::default()
+                    },
+                )),
+                _ => None,
+            };
+        }
+        Expr::Binary(BinOp::In, lhs, rhs) => {
+            let (Expr::Ident(ident), Expr::Array(items)) = (lhs.as_ref(), rhs.as_ref()) else {
+                return None;
+            };
+            let values = items.iter().map(|item| match item {
+                Expr::Lit(lit) => Some(Value::from(lit)),
+                _ => None,
+            });
+            domain.allowed = Some(values.collect::<Option<_>>()?);
+            return Some((ident, domain));
+        }
+        Expr::Binary(op, lhs, rhs) => match (lhs.as_ref(), rhs.as_ref()) {
+            (Expr::Ident(ident), Expr::Lit(lit)) => (ident, *op, Value::from(lit)),
+            (Expr::Lit(lit), Expr::Ident(ident)) => (ident, swap(*op), Value::from(lit)),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    match (op, value) {
+        (BinOp::Eq, value) => domain.allowed = Some(BTreeSet::from([value])),
+        (BinOp::Neq, value) => domain.excluded = BTreeSet::from([value]),
+        (BinOp::Lt, Value::Int(i)) => domain.max = Some(i.checked_sub(1)?),
+        (BinOp::Le, Value::Int(i)) => domain.max = Some(i),
+        (BinOp::Gt, Value::Int(i)) => domain.min = Some(i.checked_add(1)?),
+        (BinOp::Ge, Value::Int(i)) => domain.min = Some(i),
+        _ => return None,
+    }
+    Some((ident, domain))
+}
+
+/// A condition as the domains of the identifiers of its simple comparisons and its other terms.
+#[derive(Debug, Clone, Default)]
+pub struct Constraints<'a> {
+    domains: BTreeMap<&'a str, Domain>,
+    /// Terms which are not simple comparisons.
+    others: Vec<&'a Expr>,
+}
+
+impl<'a> Constraints<'a> {
+    pub fn new(condition: &'a Expr) -> Self {
+        let mut constraints = Self::default();
+        for term in conjuncts(condition) {
+            match comparison(term) {
+                Some((ident, domain)) => match constraints.domains.get_mut(ident) {
+                    Some(existing) => existing.intersect(&domain),
+                    None => {
+                        constraints.domains.insert(ident, domain);
+                    }
+                },
+                None => constraints.others.push(term),
+            }
+        }
+        constraints
+    }
+
+    /// Whether the condition consists only of simple comparisons.
+    pub fn is_simple(&self) -> bool {
+        self.others.is_empty()
+    }
+
+    /// Whether the condition can be true. Only a contradiction of simple comparisons, `false` or a
+    /// term together with its negation make it always false.
+    pub fn can_be_true(&self) -> bool {
+        let negated = |term: &Expr| {
+            self.others
+                .iter()
+                .any(|other| matches!(other, Expr::Unary(UnOp::Not, expr) if **expr == *term))
+        };
+        !self.domains.values().any(Domain::is_empty)
+            && !self
+                .others
+                .iter()
+                .any(|term| **term == Expr::Lit(Lit::Bool(false)) || negated(term))
+    }
+
+    /// Whether every flight plan matching this condition matches `other`, as all other terms of
+    /// `other` are also terms of this one and its comparisons allow no other values. Terms looking
+    /// up a table are only the same if both conditions are of the same rule file, `same_file`.
+    pub fn implies(&self, other: &Constraints, same_file: bool) -> bool {
+        if !self.can_be_true() {
+            return true;
+        }
+        other
+            .others
+            .iter()
+            .all(|term| (same_file || !uses_table(term)) && self.others.contains(term))
+            && other.domains.iter().all(|(ident, domain)| {
+                self.domains
+                    .get(ident)
+                    .is_some_and(|own| own.is_subset(domain))
+            })
+    }
+
+    /// Whether some flight plan matches both conditions, if both are simple.
+    pub fn overlaps(&self, other: &Constraints) -> Option<bool> {
+        if !self.is_simple() || !other.is_simple() {
+            return None;
+        }
+        let mut both = self.clone();
+        for (ident, domain) in &other.domains {
+            match both.domains.get_mut(ident) {
+                Some(existing) => existing.intersect(domain),
+                None => {
+                    both.domains.insert(ident, domain.clone());
+                }
+            }
+        }
+        Some(both.can_be_true())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lang::parser::parse;
+
+    fn check<T>(condition: &str, f: impl FnOnce(&Constraints) -> T) -> T {
+        let expr = parse(condition).unwrap();
+        f(&Constraints::new(&expr))
+    }
+
+    fn implies(condition: &str, other: &str) -> bool {
+        let other = parse(other).unwrap();
+        check(condition, |c| c.implies(&Constraints::new(&other), true))
+    }
+
+    fn overlaps(condition: &str, other: &str) -> Option<bool> {
+        let other = parse(other).unwrap();
+        check(condition, |c| c.overlaps(&Constraints::new(&other)))
+    }
+
+    #[test]
+    fn can_be_true() {
+        for condition in [
+            "true",
+            "rfl > 24000 and rfl < 24002",
+            "arr in ['EDDM', 'EDDN'] and arr != 'EDDM'",
+            "sidwpt == 'CINDY' and valid_cruise_level('E')",
+            "rfl >= 1 and rfl <= 3 and rfl != 1 and rfl != 3",
+        ] {
+            assert!(check(condition, |c| c.can_be_true()), "{condition}");
+        }
+        for condition in [
+            "false",
+            "dep == 'EDDF' and false",
+            "rfl > 24000 and rfl < 24001",
+            "5000 > rfl and rfl > 6000",
+            "arr == 'EDDM' and arr == 'EDDN'",
+            "arr in ['EDDM', 'EDDN'] and !(arr in ['EDDN', 'EDDM'])",
+            "rfl >= 1 and rfl <= 2 and rfl != 1 and rfl != 2",
+            "is_rvsm_capable and !is_rvsm_capable",
+            "item18('STS') has 'HOSP' and !(item18('STS') has 'HOSP')",
+        ] {
+            assert!(!check(condition, |c| c.can_be_true()), "{condition}");
+        }
+    }
+
+    #[test]
+    fn implication() {
+        assert!(implies("rfl > 30000", "rfl > 20000"));
+        assert!(implies("rfl == 25000", "rfl >= 25000 and rfl <= 25000"));
+        assert!(implies(
+            "arr == 'EDDM' and rfl > 5",
+            "arr in ['EDDM', 'EDDN']"
+        ));
+        assert!(implies("arr in ['EDDM'] and rfl < 5", "arr != 'EDDN'"));
+        assert!(implies("rfl > 1 and rfl < 5 and rfl != 3", "rfl in [2, 4]"));
+        assert!(implies(
+            "sidwpt == 'CINDY' and equip has 'W'",
+            "equip has 'W'"
+        ));
+        assert!(implies("dep == 'EDDF'", "true"));
+        assert!(implies("false", "rfl > 20000"));
+
+        assert!(!implies("rfl > 20000", "rfl > 30000"));
+        assert!(!implies("arr in ['EDDM', 'EDDN']", "arr == 'EDDM'"));
+        assert!(!implies("arr != 'EDDN'", "arr == 'EDDM'"));
+        assert!(!implies("rfl > 20000", "rfl != 25000"));
+        assert!(!implies("dep == 'EDDF'", "dep == 'EDDF' and equip has 'W'"));
+        assert!(!implies("true", "dep == 'EDDF'"));
+    }
+
+    #[test]
+    fn implication_across_files() {
+        let implies = |condition: &str, other: &str| {
+            let other = parse(other).unwrap();
+            check(condition, |c| c.implies(&Constraints::new(&other), false))
+        };
+        assert!(implies("rfl > 5 and equip has 'W'", "equip has 'W'"));
+        assert!(implies("'T104' in airways", "'T104' in airways"));
+
+        // The tables of the files may differ
+        assert!(!implies("rfl > max_rfl[arr]", "rfl > max_rfl[arr]"));
+        assert!(!implies(
+            "sidwpt in sid_direction",
+            "sidwpt in sid_direction"
+        ));
+        assert!(!implies(
+            "!valid_cruise_level(sid_direction[sidwpt])",
+            "!valid_cruise_level(sid_direction[sidwpt])"
+        ));
+    }
+
+    #[test]
+    fn overlap() {
+        assert_eq!(Some(true), overlaps("rfl > 20000", "rfl < 30000"));
+        assert_eq!(Some(true), overlaps("dep == 'EDDF'", "arr == 'EDDM'"));
+        assert_eq!(Some(false), overlaps("rfl > 30000", "rfl < 20000"));
+        assert_eq!(Some(false), overlaps("arr in ['EDDM']", "arr != 'EDDM'"));
+        assert_eq!(None, overlaps("rfl > 20000", "equip has 'W'"));
+    }
+}
@@ -1,9 +1,9 @@
-use std::{error::Error, fmt::Display, num::ParseIntError, str::Chars};
+use std::{error::Error, fmt::Display, num::ParseIntError, ops::Range, str::Chars};
 
 use self::Token::*;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-enum LexError {
+pub enum LexError {
     /// End of file
     Eof,
     Expected {
@@ -84,6 +84,8 @@ pub enum Token {
     Percent,
     /// "in"
     In,
+    /// "has"
+    Has,
 }
 
 struct Cursor<'a> {
@@ -173,6 +175,17 @@ impl<'a> Lexer<'a> {
         Ok(&self.input[start..stop])
     }
 
+    /// The next token with its byte range in the input, or `None` at the end of the input.
+    pub fn next_spanned(&mut self) -> Option<(Result<Token, LexError>, Range<usize>)> {
+        self.cursor.eat_while(|c| c.is_whitespace());
+        let start = self.cursor.len_consumed();
+        if start == self.input.len() {
+            return None;
+        }
+        let token = self.advance_token();
+        Some((token, start..self.cursor.len_consumed()))
+    }
+
     fn advance_token(&mut self) -> Result<Token, LexError> {
         self.cursor.eat_while(|c| c.is_whitespace());
         match self.cursor.bump().ok_or(LexError::Eof)? {
@@ -218,6 +231,7 @@ impl<'a> Lexer<'a> {
                 "true" => Ok(Bool(true)),
                 "false" => Ok(Bool(false)),
                 "in" => Ok(In),
+                "has" => Ok(Has),
                 ident => Ok(Ident(ident.into())),
             },
             c => Err(LexError::UnrecognizedChar(c)),
@@ -270,6 +284,20 @@ mod tests {
         assert_eq!(None, lex.next());
     }
 
+    #[test]
+    fn spans() {
+        let mut lex = Lexer::new(" rfl >= 'A'  ");
+        assert_eq!(Some((Ok(Ident("rfl".into())), 1..4)), lex.next_spanned());
+        assert_eq!(Some((Ok(Ge), 5..7)), lex.next_spanned());
+        assert_eq!(Some((Ok(Text("A".into())), 8..11)), lex.next_spanned());
+        assert_eq!(None, lex.next_spanned());
+
+        let mut lex = Lexer::new("dep == 'EDDF");
+        lex.next_spanned();
+        lex.next_spanned();
+        assert_eq!(Some((Err(LexError::Eof), 7..12)), lex.next_spanned());
+    }
+
     #[test]
     fn parens() {
         let mut lex = Lexer::new("(((-42)))");
@@ -325,6 +353,14 @@ mod tests {
         assert_eq!(Some(Ge), lex.next());
     }
 
+    #[test]
+    fn lex_has() {
+        let mut lex = Lexer::new("equip has 'W'");
+        assert_eq!(Some(Ident("equip".into())), lex.next());
+        assert_eq!(Some(Has), lex.next());
+        assert_eq!(Some(Text("W".into())), lex.next());
+    }
+
     #[test]
     fn lex_peek() {
         let input = "rfl % 2000 == 0";
@@ -1,3 +1,5 @@
 pub mod ast;
+pub mod constraints;
 pub mod lexer;
 pub mod parser;
+pub mod types;
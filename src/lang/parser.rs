@@ -1,8 +1,8 @@
-use std::{error::Error, fmt::Display, iter::Peekable};
+use std::{error::Error, fmt::Display, ops::Range};
 
 use super::{
     ast::{BinOp, Expr, Lit, UnOp},
-    lexer::{Lexer, Token},
+    lexer::{LexError, Lexer, Token},
 };
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -11,6 +11,7 @@ pub enum ParseError {
     UnmatchedParen,
     UnmatchedBracked,
     BadToken(Token),
+    Lex(LexError),
 }
 
 impl Display for ParseError {
@@ -20,20 +21,125 @@ impl Display for ParseError {
             ParseError::UnmatchedParen => write!(f, "Parenthesis unmatched, expected ')'"),
             ParseError::UnmatchedBracked => write!(f, "Bracket unmatched, expected ']'"),
             ParseError::BadToken(t) => write!(f, "Bad token \"{:?}\"", t),
+            ParseError::Lex(e) => write!(f, "{}", e),
         }
     }
 }
 
 impl Error for ParseError {}
 
+/// A [`ParseError`] with the byte range of the input it refers to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxError {
+    pub error: ParseError,
+    pub span: Range<usize>,
+}
+
+impl Display for SyntaxError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} at {}..{}",
+            self.error, self.span.start, self.span.end
+        )
+    }
+}
+
+impl Error for SyntaxError {}
+
+/// Tokens of the lexer, which can be peeked, together with the span of the last one.
+struct Tokens<'a> {
+    lexer: Lexer<'a>,
+    peeked: Option<Option<(Token, Range<usize>)>>,
+    /// Span of the last token returned, or the end of the input after the last one.
+    span: Range<usize>,
+    /// A token which could not be lexed ends the tokens.
+    error: Option<(LexError, Range<usize>)>,
+    len: usize,
+}
+
+impl<'a> Tokens<'a> {
+    fn new(input: &'a str) -> Self {
+        Self {
+            lexer: Lexer::new(input),
+            peeked: None,
+            span: 0..0,
+            error: None,
+            len: input.len(),
+        }
+    }
+
+    fn advance(&mut self) -> Option<(Token, Range<usize>)> {
+        if self.error.is_some() {
+            return None;
+        }
+        match self.lexer.next_spanned()? {
+            (Ok(token), span) => Some((token, span)),
+            (Err(e), span) => {
+                self.error = Some((e, span));
+                None
+            }
+        }
+    }
+
+    fn peek(&mut self) -> Option<&Token> {
+        if self.peeked.is_none() {
+            self.peeked = Some(self.advance());
+        }
+        self.peeked
+            .as_ref()
+            .and_then(|t| t.as_ref())
+            .map(|(t, _)| t)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let next = match self.peeked.take() {
+            Some(peeked) => peeked,
+            None => self.advance(),
+        };
+        match next {
+            Some((token, span)) => {
+                self.span = span;
+                Some(token)
+            }
+            None => {
+                self.span = self.len..self.len;
+                None
+            }
+        }
+    }
+}
+
+/// Parses an expression, which must be the whole input. See [`parse_spanned`] for where parsing
+/// failed.
 pub fn parse<S: AsRef<str>>(input: S) -> Result<Expr, ParseError> {
-    let input = input.as_ref();
-    let lexer = Lexer::new(input);
-    let mut token_stream = lexer.peekable();
-    expr_bp(&mut token_stream, 0)
+    parse_spanned(input).map_err(|e| e.error)
 }
 
-fn parse_lhs(lexer: &mut Peekable<Lexer<'_>>) -> Result<Expr, ParseError> {
+/// Parses an expression like [`parse`]. Errors refer to the token at which parsing failed.
+pub fn parse_spanned<S: AsRef<str>>(input: S) -> Result<Expr, SyntaxError> {
+    let mut tokens = Tokens::new(input.as_ref());
+    let result = expr_bp(&mut tokens, 0);
+    if let Some((error, span)) = tokens.error.take() {
+        return Err(SyntaxError {
+            error: ParseError::Lex(error),
+            span,
+        });
+    }
+    let expr = result.map_err(|error| SyntaxError {
+        error,
+        span: tokens.span.clone(),
+    })?;
+    match tokens.next() {
+        Some(token) => Err(SyntaxError {
+            error: ParseError::BadToken(token),
+            span: tokens.span,
+        }),
+        None => Ok(expr),
+    }
+}
+
+fn parse_lhs(lexer: &mut Tokens<'_>) -> Result<Expr, ParseError> {
     match lexer.next().ok_or(ParseError::PrematureEof)? {
         Token::Bool(b) => Ok(Expr::Lit(Lit::Bool(b))),
         Token::Int(i) => Ok(Expr::Lit(Lit::Int(i.into()))),
@@ -47,6 +153,10 @@ fn parse_lhs(lexer: &mut Peekable<Lexer<'_>>) -> Result<Expr, ParseError> {
             Ok(lhs)
         }
         Token::OpenBracket => {
+            if let Some(Token::CloseBracket) = lexer.peek() {
+                lexer.next();
+                return Ok(Expr::Array(vec![]));
+            }
             let mut exprs = vec![expr_bp(lexer, 0)?];
             while let Some(Token::Comma) = lexer.peek() {
                 lexer.next();
@@ -66,7 +176,7 @@ fn parse_lhs(lexer: &mut Peekable<Lexer<'_>>) -> Result<Expr, ParseError> {
     }
 }
 
-fn expr_bp(lexer: &mut Peekable<Lexer<'_>>, min_bp: u8) -> Result<Expr, ParseError> {
+fn expr_bp(lexer: &mut Tokens<'_>, min_bp: u8) -> Result<Expr, ParseError> {
     let mut lhs = parse_lhs(lexer)?;
 
     loop {
@@ -75,7 +185,31 @@ fn expr_bp(lexer: &mut Peekable<Lexer<'_>>, min_bp: u8) -> Result<Expr, ParseErr
             Some(t) => t,
         };
 
-        // Postfix could come here
+        if let Token::OpenParen = token {
+            let (l_bp, ()) = postfix_binding_power();
+            if l_bp < min_bp {
+                break;
+            }
+            lexer.next();
+
+            lhs = Expr::Call(Box::new(lhs), parse_args(lexer)?);
+            continue;
+        }
+
+        if let Token::OpenBracket = token {
+            let (l_bp, ()) = postfix_binding_power();
+            if l_bp < min_bp {
+                break;
+            }
+            lexer.next();
+
+            let key = expr_bp(lexer, 0)?;
+            if lexer.next() != Some(Token::CloseBracket) {
+                return Err(ParseError::UnmatchedBracked);
+            }
+            lhs = Expr::Index(Box::new(lhs), Box::new(key));
+            continue;
+        }
 
         let maybe_binop = match token {
             Token::And => Some(BinOp::And),
@@ -88,6 +222,7 @@ fn expr_bp(lexer: &mut Peekable<Lexer<'_>>, min_bp: u8) -> Result<Expr, ParseErr
             Token::Lt => Some(BinOp::Lt),
             Token::Percent => Some(BinOp::Mod),
             Token::In => Some(BinOp::In),
+            Token::Has => Some(BinOp::Has),
             _ => None,
         };
         if let Some(op) = maybe_binop {
@@ -108,17 +243,38 @@ fn expr_bp(lexer: &mut Peekable<Lexer<'_>>, min_bp: u8) -> Result<Expr, ParseErr
     Ok(lhs)
 }
 
+fn parse_args(lexer: &mut Tokens<'_>) -> Result<Vec<Expr>, ParseError> {
+    let mut args = vec![];
+    if let Some(Token::CloseParen) = lexer.peek() {
+        lexer.next();
+        return Ok(args);
+    }
+    args.push(expr_bp(lexer, 0)?);
+    while let Some(Token::Comma) = lexer.peek() {
+        lexer.next();
+        args.push(expr_bp(lexer, 0)?);
+    }
+    if lexer.next() != Some(Token::CloseParen) {
+        return Err(ParseError::UnmatchedParen);
+    }
+    Ok(args)
+}
+
 fn prefix_binding_power(op: &UnOp) -> ((), u8) {
     match op {
         UnOp::Not => ((), 9),
     }
 }
 
+fn postfix_binding_power() -> (u8, ()) {
+    (11, ())
+}
+
 fn infix_binding_power(op: &BinOp) -> (u8, u8) {
     match op {
         BinOp::Or => (1, 2),
         BinOp::And => (3, 4),
-        BinOp::In => (5, 6),
+        BinOp::In | BinOp::Has => (5, 6),
         BinOp::Eq | BinOp::Neq | BinOp::Ge | BinOp::Gt | BinOp::Le | BinOp::Lt => (7, 8),
         BinOp::Mod => (9, 10),
     }
@@ -228,4 +384,108 @@ mod tests {
 
         assert_eq!(expected_expr, parsed);
     }
+
+    #[test]
+    fn has() {
+        let expected = Expr::Binary(
+            BinOp::And,
+            Box::new(Expr::Binary(
+                BinOp::Has,
+                Box::new(Expr::Ident("equip".into())),
+                Box::new(Expr::Lit(Lit::Text("W".into()))),
+            )),
+            Box::new(Expr::Binary(
+                BinOp::Has,
+                Box::new(Expr::Ident("pbn".into())),
+                Box::new(Expr::Lit(Lit::Text("D1".into()))),
+            )),
+        );
+        assert_eq!(Ok(expected), parse("equip has 'W' and pbn has 'D1'"));
+    }
+
+    #[test]
+    fn call() {
+        let expected = Expr::Binary(
+            BinOp::Has,
+            Box::new(Expr::Call(
+                Box::new(Expr::Ident("item18".into())),
+                vec![Expr::Lit(Lit::Text("STS".into()))],
+            )),
+            Box::new(Expr::Lit(Lit::Text("HOSP".into()))),
+        );
+        assert_eq!(Ok(expected), parse("item18('STS') has 'HOSP'"));
+        assert_eq!(
+            Ok(Expr::Call(Box::new(Expr::Ident("now".into())), vec![])),
+            parse("now()")
+        );
+        assert_eq!(Err(ParseError::UnmatchedParen), parse("item18('STS'"));
+    }
+
+    #[test]
+    fn index() {
+        let expected = Expr::Binary(
+            BinOp::Gt,
+            Box::new(Expr::Ident("rfl".into())),
+            Box::new(Expr::Index(
+                Box::new(Expr::Ident("max_rfl".into())),
+                Box::new(Expr::Ident("arr".into())),
+            )),
+        );
+        assert_eq!(Ok(expected), parse("rfl > max_rfl[arr]"));
+        let expected = Expr::Binary(
+            BinOp::In,
+            Box::new(Expr::Ident("arr".into())),
+            Box::new(Expr::Array(vec![Expr::Lit(Lit::Text("EDDS".into()))])),
+        );
+        assert_eq!(Ok(expected), parse("arr in ['EDDS']"));
+        assert_eq!(Err(ParseError::UnmatchedBracked), parse("max_rfl[arr"));
+    }
+
+    #[test]
+    fn display_roundtrip() {
+        for input in [
+            "dep == 'EDDF' and sidwpt in ['TOBAK', 'ANEKI'] and !valid_cruise_level('E')",
+            "(a or b) and c",
+            "a or b and c",
+            "!(rfl % 2000 == 1000) or -42 > rfl",
+            "arr in max_rfl and rfl > max_rfl[arr]",
+            "equip has 'W' and item18('STS') has 'HOSP'",
+            "len([]) == 0 and now()",
+        ] {
+            let expr = parse(input).unwrap();
+            assert_eq!(Ok(expr.clone()), parse(expr.to_string()), "{input}");
+        }
+        assert_eq!(
+            "(a or b) and !c",
+            parse("(a or b) and !c").unwrap().to_string()
+        );
+    }
+
+    #[test]
+    fn spans() {
+        let err = |error, span| Err(SyntaxError { error, span });
+        assert!(parse_spanned("dep == 'EDDF'").is_ok());
+        assert_eq!(
+            err(ParseError::PrematureEof, 17..17),
+            parse_spanned("dep == 'EDDF' and")
+        );
+        assert_eq!(
+            err(ParseError::BadToken(Token::Int(5)), 4..5),
+            parse_spanned("rfl 5")
+        );
+        assert_eq!(
+            err(ParseError::UnmatchedParen, 9..10),
+            parse_spanned("(rfl > 5 ]")
+        );
+        assert_eq!(
+            err(ParseError::Lex(LexError::UnrecognizedChar('.')), 7..8),
+            parse_spanned("sidwpt .")
+        );
+        // `parse` rejects the same input
+        assert_eq!(Err(ParseError::BadToken(Token::Int(5))), parse("rfl 5"));
+        assert_eq!(
+            Err(ParseError::Lex(LexError::UnrecognizedChar('.'))),
+            parse("sidwpt .")
+        );
+    }
 }
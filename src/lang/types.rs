@@ -0,0 +1,334 @@
+//! Static type checking of expressions, without a flight plan.
+
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use super::ast::{BinOp, Expr, Lit, Table, UnOp};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Type {
+    Bool,
+    Int,
+    Text,
+    Array(Box<Type>),
+    /// Type which is only known during evaluation, e.g. of `[]` or `item18(key)`.
+    Any,
+}
+
+impl Type {
+    /// Whether values of both types can be equal.
+    fn unifies(&self, other: &Type) -> bool {
+        match (self, other) {
+            (Type::Any, _) | (_, Type::Any) => true,
+            (Type::Array(lhs), Type::Array(rhs)) => lhs.unifies(rhs),
+            (lhs, rhs) => lhs == rhs,
+        }
+    }
+
+    /// The more specific of both types, or `Any` if they do not unify.
+    fn unify(self, other: Type) -> Type {
+        match (self, other) {
+            (Type::Any, other) | (other, Type::Any) => other,
+            (Type::Array(lhs), Type::Array(rhs)) => Type::Array(Box::new(lhs.unify(*rhs))),
+            (lhs, rhs) if lhs == rhs => lhs,
+            _ => Type::Any,
+        }
+    }
+}
+
+impl Display for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Type::Bool => write!(f, "bool"),
+            Type::Int => write!(f, "int"),
+            Type::Text => write!(f, "text"),
+            Type::Array(item) => write!(f, "[{}]", item),
+            Type::Any => write!(f, "any"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TypeError {
+    UnknownIdent(String),
+    UnknownFunction(String),
+    /// The message and the expression it applies to.
+    Mismatch(String, String),
+}
+
+impl Display for TypeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TypeError::UnknownIdent(name) => write!(f, "Unknown identifier {}", name),
+            TypeError::UnknownFunction(name) => write!(f, "Unknown function {}", name),
+            TypeError::Mismatch(msg, expr) => write!(f, "{} in `{}`", msg, expr),
+        }
+    }
+}
+
+/// Type of an identifier, as evaluated by `ast::ident`.
+pub fn ident_type(name: &str) -> Option<Type> {
+    let text_array = || Type::Array(Box::new(Type::Text));
+    match name {
+        "equip" | "surv" | "pbn" | "airways" | "unknown_points" | "unknown_airways" => {
+            Some(text_array())
+        }
+        "rnav1" | "rnav5" | "rnp1" | "rnp_apch" | "datalink" | "rnav" | "ac_is_rvsm_capable"
+        | "in_rvsm_band" | "ac_known" => Some(Type::Bool),
+        "ifr_level" | "vfr_level" | "ac_ceiling" | "ac_cruise_speed" | "route_distance_nm"
+        | "direct_distance_nm" | "route_track" | "ac_eng_count" | "cfl" | "rfl" => Some(Type::Int),
+        "ifr_portion" | "vfr_portion" | "ac_category" | "ac_recat" | "broken_segment"
+        | "ac_type" | "ac_icao" | "ac_wtc" | "ac_faa_equip_code" | "ac_eng_type" | "rule"
+        | "dep" | "dep_rwy" | "arr" | "sid" | "sidwpt" | "route" => Some(Type::Text),
+        _ => None,
+    }
+}
+
+/// Type of the values of a table, `Any` if they differ.
+fn table_type(table: &Table) -> Type {
+    table
+        .values()
+        .map(literal_type)
+        .reduce(Type::unify)
+        .unwrap_or(Type::Any)
+}
+
+/// Type of a literal expression, as in tables.
+fn literal_type(expr: &Expr) -> Type {
+    match expr {
+        Expr::Lit(Lit::Bool(_)) => Type::Bool,
+        Expr::Lit(Lit::Int(_)) => Type::Int,
+        Expr::Lit(Lit::Text(_)) => Type::Text,
+        Expr::Array(items) => Type::Array(Box::new(
+            items
+                .iter()
+                .map(literal_type)
+                .reduce(Type::unify)
+                .unwrap_or(Type::Any),
+        )),
+        _ => Type::Any,
+    }
+}
+
+/// Checks the types of an expression and returns its type.
+///
+/// Identifiers refer to the tables first, like during evaluation.
+pub fn check(expr: &Expr, tables: &HashMap<String, Table>) -> Result<Type, TypeError> {
+    let mismatch = |msg: &str| Err(TypeError::Mismatch(msg.into(), expr.to_string()));
+    let table = |expr: &Expr| match expr {
+        Expr::Ident(name) => tables.get(name),
+        _ => None,
+    };
+    match expr {
+        Expr::Lit(_) => Ok(literal_type(expr)),
+        Expr::Ident(name) if tables.contains_key(name) => {
+            mismatch("Tables can only be indexed or used with in")
+        }
+        Expr::Ident(name) => ident_type(name).ok_or_else(|| TypeError::UnknownIdent(name.clone())),
+        Expr::Binary(BinOp::In, lhs, rhs) if table(rhs).is_some() => match check(lhs, tables)? {
+            Type::Text | Type::Int | Type::Any => Ok(Type::Bool),
+            _ => mismatch("Tables can only be indexed by text or numbers"),
+        },
+        Expr::Binary(op, lhs, rhs) => {
+            let lhs = check(lhs, tables)?;
+            let rhs = check(rhs, tables)?;
+            let (lhs, rhs) = (&lhs, &rhs);
+            match op {
+                BinOp::And | BinOp::Or if lhs.unifies(&Type::Bool) && rhs.unifies(&Type::Bool) => {
+                    Ok(Type::Bool)
+                }
+                BinOp::And | BinOp::Or => mismatch("Expected bool operands"),
+                BinOp::Eq | BinOp::Neq if lhs.unifies(rhs) => Ok(Type::Bool),
+                BinOp::Eq | BinOp::Neq => mismatch("Compared values have different types"),
+                BinOp::Ge | BinOp::Gt | BinOp::Le | BinOp::Lt
+                    if lhs.unifies(&Type::Int) && rhs.unifies(&Type::Int) =>
+                {
+                    Ok(Type::Bool)
+                }
+                BinOp::Mod if lhs.unifies(&Type::Int) && rhs.unifies(&Type::Int) => Ok(Type::Int),
+                BinOp::Ge | BinOp::Gt | BinOp::Le | BinOp::Lt | BinOp::Mod => {
+                    mismatch("Expected int operands")
+                }
+                BinOp::In => {
+                    contains(rhs, lhs).map_or_else(|| mismatch("Invalid operands of in"), Ok)
+                }
+                BinOp::Has => {
+                    contains(lhs, rhs).map_or_else(|| mismatch("Invalid operands of has"), Ok)
+                }
+            }
+        }
+        Expr::Unary(UnOp::Not, operand) => match check(operand, tables)? {
+            Type::Bool | Type::Any => Ok(Type::Bool),
+            _ => mismatch("Cannot negate this expression"),
+        },
+        Expr::Array(items) => {
+            let mut item_type = Type::Any;
+            for item in items {
+                let typ = check(item, tables)?;
+                if !typ.unifies(&item_type) {
+                    return mismatch("Array items have different types");
+                }
+                item_type = item_type.unify(typ);
+            }
+            Ok(Type::Array(Box::new(item_type)))
+        }
+        Expr::Call(callee, args) => {
+            let Expr::Ident(name) = callee.as_ref() else {
+                return mismatch("Only functions can be called");
+            };
+            let types = args
+                .iter()
+                .map(|arg| check(arg, tables))
+                .collect::<Result<Vec<_>, _>>()?;
+            call_type(name, args, &types)
+                .ok_or_else(|| TypeError::UnknownFunction(name.clone()))?
+                .map_or_else(mismatch, Ok)
+        }
+        Expr::Index(base, key) => {
+            let key = check(key, tables)?;
+            if let Some(table) = table(base) {
+                return match key {
+                    Type::Text | Type::Int | Type::Any => Ok(table_type(table)),
+                    _ => mismatch("Tables can only be indexed by text or numbers"),
+                };
+            }
+            match (check(base, tables)?, key) {
+                (Type::Array(item), Type::Int | Type::Any) => Ok(*item),
+                (Type::Any, Type::Int | Type::Any) => Ok(Type::Any),
+                _ => mismatch("Only tables and arrays can be indexed"),
+            }
+        }
+    }
+}
+
+/// Type of `item in collection`, if valid.
+fn contains(collection: &Type, item: &Type) -> Option<Type> {
+    match (collection, item) {
+        (Type::Array(items), item) if items.unifies(item) => Some(Type::Bool),
+        (Type::Text | Type::Any, Type::Text | Type::Any) => Some(Type::Bool),
+        (Type::Any, _) => Some(Type::Bool),
+        _ => None,
+    }
+}
+
+/// Return type of a function, as evaluated by `ast::call`, or `None` for unknown functions.
+fn call_type(name: &str, args: &[Expr], types: &[Type]) -> Option<Result<Type, &'static str>> {
+    let is = |typ: &Type, expected: &[Type]| expected.iter().any(|e| typ.unifies(e));
+    let result = match (name, types) {
+        ("valid_cruise_level", [direction])
+        | ("valid_cruise_level", [direction, Type::Text | Type::Any])
+            if is(direction, &[Type::Text, Type::Int]) =>
+        {
+            Ok(Type::Bool)
+        }
+        ("valid_cruise_level", _) => Err("valid_cruise_level expects a direction"),
+        ("len", [Type::Array(_) | Type::Text | Type::Any]) => Ok(Type::Int),
+        ("len", _) => Err("len expects an array or text"),
+        ("item18", [Type::Text | Type::Any]) => Ok(match args {
+            [Expr::Lit(Lit::Text(key))] if key == "STS" => Type::Array(Box::new(Type::Text)),
+            [Expr::Lit(Lit::Text(key))] if key == "RVR" => Type::Int,
+            [Expr::Lit(Lit::Text(_))] => Type::Text,
+            _ => Type::Any,
+        }),
+        ("item18", _) => Err("item18 expects a single text argument"),
+        ("matches", [Type::Text | Type::Any, Type::Text | Type::Any]) => Ok(Type::Bool),
+        ("matches", _) => Err("matches expects a text and a pattern"),
+        _ => return None,
+    };
+    Some(result)
+}
+
+/// Identifiers an expression refers to, including tables and functions.
+pub fn idents(expr: &Expr) -> Vec<&str> {
+    let mut idents = vec![];
+    collect_idents(expr, &mut idents);
+    idents
+}
+
+fn collect_idents<'a>(expr: &'a Expr, idents: &mut Vec<&'a str>) {
+    match expr {
+        Expr::Lit(_) => {}
+        Expr::Ident(name) => idents.push(name),
+        Expr::Binary(_, lhs, rhs) | Expr::Index(lhs, rhs) => {
+            collect_idents(lhs, idents);
+            collect_idents(rhs, idents);
+        }
+        Expr::Unary(_, expr) => collect_idents(expr, idents),
+        Expr::Array(exprs) => exprs.iter().for_each(|e| collect_idents(e, idents)),
+        Expr::Call(callee, args) => {
+            collect_idents(callee, idents);
+            args.iter().for_each(|e| collect_idents(e, idents));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lang::parser::parse;
+
+    fn check_str(input: &str) -> Result<Type, TypeError> {
+        let tables = HashMap::from([(
+            "max_rfl".to_string(),
+            Table::from([("EDDM".to_string(), Expr::Lit(Lit::Int(24000)))]),
+        )]);
+        check(&parse(input).unwrap(), &tables)
+    }
+
+    #[test]
+    fn valid() {
+        for input in [
+            "dep == 'EDDF' and rfl >= 5000",
+            "ac_eng_type in ['P', 'T'] or !rnav",
+            "equip has 'W' and 'A1' in pbn",
+            "valid_cruise_level('E') and valid_cruise_level(route_track, 'M')",
+            "arr in max_rfl and rfl > max_rfl[arr]",
+            "len(item18('STS')) > 0 and item18('STS') has 'HOSP'",
+            "matches(arr, 'ED*') and rfl % 2000 == 1000",
+            "airways[0] == 'T104' and [] == airways",
+        ] {
+            assert_eq!(Ok(Type::Bool), check_str(input), "{input}");
+        }
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            Err(TypeError::UnknownIdent("destination".into())),
+            check_str("destination == 'EDDM'")
+        );
+        assert_eq!(
+            Err(TypeError::UnknownFunction("valid_rfl".into())),
+            check_str("valid_rfl('E')")
+        );
+        assert_eq!(
+            Err(TypeError::Mismatch(
+                "Compared values have different types".into(),
+                "rfl == 'F350'".into()
+            )),
+            check_str("rfl == 'F350'")
+        );
+        assert!(check_str("dep > 5").is_err());
+        assert!(check_str("rfl and rnav").is_err());
+        assert!(check_str("['P', 1]").is_err());
+        assert!(check_str("max_rfl == 1").is_err());
+        assert!(check_str("matches(arr)").is_err());
+        assert!(check_str("rfl in ['F350']").is_err());
+        assert_eq!(Ok(Type::Int), check_str("max_rfl[arr]"));
+    }
+
+    #[test]
+    fn referenced_idents() {
+        let expr = parse("arr in max_rfl and valid_cruise_level(sid_direction[sidwpt])").unwrap();
+        assert_eq!(
+            vec![
+                "arr",
+                "max_rfl",
+                "valid_cruise_level",
+                "sid_direction",
+                "sidwpt"
+            ],
+            idents(&expr)
+        );
+    }
+}
@@ -0,0 +1,220 @@
+//! Cruising levels according to ICAO Annex 2, Appendix 3.
+
+use crate::ffi;
+
+/// Direction of flight as used by the table of cruising levels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// Magnetic track 000° to 179°.
+    East,
+    /// Magnetic track 180° to 359°.
+    West,
+}
+
+impl Direction {
+    /// Direction for a magnetic track in degrees.
+    pub fn from_track(track: i64) -> Self {
+        if track.rem_euclid(360) < 180 {
+            Self::East
+        } else {
+            Self::West
+        }
+    }
+
+    /// Parse `E` or `W`.
+    pub fn from_letter(s: &str) -> Option<Self> {
+        match s {
+            "E" => Some(Self::East),
+            "W" => Some(Self::West),
+            _ => None,
+        }
+    }
+}
+
+/// Unit of the table of cruising levels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Units {
+    /// Flight levels in feet (Appendix 3, table a).
+    Feet,
+    /// Metric levels in meters (Appendix 3, table b).
+    Metric,
+}
+
+const FEET_PER_METER: f64 = 3.28084;
+
+/// Lowest flight level of the RVSM band in feet.
+pub const RVSM_MIN: u32 = 29000;
+/// Highest flight level of the RVSM band in feet.
+pub const RVSM_MAX: u32 = 41000;
+
+/// Whether `level` (in feet) is between FL290 and FL410 inclusive.
+pub fn is_in_rvsm_band(level: u32) -> bool {
+    (RVSM_MIN..=RVSM_MAX).contains(&level)
+}
+
+/// Whether `level` (in feet) is a valid cruising level for the direction of flight.
+///
+/// The requested level applies to the first portion of the route, so Yankee flight plans are
+/// checked like IFR and Zulu flight plans like VFR.
+pub fn is_valid_cruise_level(
+    level: u32,
+    direction: Direction,
+    rule: &ffi::FlightRule,
+    units: Units,
+) -> bool {
+    let vfr = matches!(*rule, ffi::FlightRule::Vfr | ffi::FlightRule::Zulu);
+    match units {
+        Units::Feet => is_valid_feet(level, direction, vfr),
+        Units::Metric => {
+            // Metric levels are transmitted in feet, round back to the nearest 10 m.
+            let meters = (f64::from(level) / FEET_PER_METER / 10.0).round() as u32 * 10;
+            is_valid_meters(meters, direction, vfr)
+        }
+    }
+}
+
+fn is_valid_feet(level: u32, direction: Direction, vfr: bool) -> bool {
+    match (direction, vfr) {
+        // FL010 - FL410 odd, then FL450, FL490, ...
+        (Direction::East, false) => {
+            (1000..=41000).contains(&level) && level % 2000 == 1000
+                || level >= 45000 && (level - 45000).is_multiple_of(4000)
+        }
+        // FL020 - FL400 even, then FL430, FL470, ...
+        (Direction::West, false) => {
+            (2000..=40000).contains(&level) && level.is_multiple_of(2000)
+                || level >= 43000 && (level - 43000).is_multiple_of(4000)
+        }
+        // FL035 - FL275
+        (Direction::East, true) => (3500..=27500).contains(&level) && level % 2000 == 1500,
+        // FL045 - FL285
+        (Direction::West, true) => (4500..=28500).contains(&level) && level % 2000 == 500,
+    }
+}
+
+fn is_valid_meters(level: u32, direction: Direction, vfr: bool) -> bool {
+    match (direction, vfr) {
+        // 300 m - 8100 m every 600 m, 8900 m - 12500 m every 600 m, then every 1200 m
+        (Direction::East, false) => {
+            (300..=8100).contains(&level) && level % 600 == 300
+                || (8900..=12500).contains(&level) && (level - 8900).is_multiple_of(600)
+                || level >= 13700 && (level - 13700).is_multiple_of(1200)
+        }
+        // 600 m - 8400 m every 600 m, 9200 m - 12200 m every 600 m, then every 1200 m
+        (Direction::West, false) => {
+            (600..=8400).contains(&level) && level.is_multiple_of(600)
+                || (9200..=12200).contains(&level) && (level - 9200).is_multiple_of(600)
+                || level >= 13100 && (level - 13100).is_multiple_of(1200)
+        }
+        // 1050 m - 8250 m
+        (Direction::East, true) => (1050..=8250).contains(&level) && level % 600 == 450,
+        // 1350 m - 8550 m
+        (Direction::West, true) => (1350..=8550).contains(&level) && level % 600 == 150,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ifr(level: u32, direction: Direction) -> bool {
+        is_valid_cruise_level(level, direction, &ffi::FlightRule::Ifr, Units::Feet)
+    }
+
+    fn vfr(level: u32, direction: Direction) -> bool {
+        is_valid_cruise_level(level, direction, &ffi::FlightRule::Vfr, Units::Feet)
+    }
+
+    #[test]
+    fn rvsm_band() {
+        assert!(!is_in_rvsm_band(28000));
+        assert!(is_in_rvsm_band(29000));
+        assert!(is_in_rvsm_band(41000));
+        assert!(!is_in_rvsm_band(43000));
+    }
+
+    #[test]
+    fn direction() {
+        assert_eq!(Direction::East, Direction::from_track(0));
+        assert_eq!(Direction::East, Direction::from_track(179));
+        assert_eq!(Direction::West, Direction::from_track(180));
+        assert_eq!(Direction::West, Direction::from_track(359));
+        assert_eq!(Direction::East, Direction::from_track(360));
+        assert_eq!(Direction::West, Direction::from_track(-90));
+    }
+
+    #[test]
+    fn ifr_east() {
+        for level in [1000, 23000, 29000, 35000, 41000, 45000, 49000] {
+            assert!(ifr(level, Direction::East), "{}", level);
+        }
+        for level in [0, 2000, 24000, 35500, 43000, 47000] {
+            assert!(!ifr(level, Direction::East), "{}", level);
+        }
+    }
+
+    #[test]
+    fn ifr_west() {
+        for level in [2000, 24000, 30000, 40000, 43000, 47000] {
+            assert!(ifr(level, Direction::West), "{}", level);
+        }
+        for level in [1000, 35000, 42000, 45000] {
+            assert!(!ifr(level, Direction::West), "{}", level);
+        }
+    }
+
+    #[test]
+    fn vfr_levels() {
+        assert!(vfr(3500, Direction::East));
+        assert!(vfr(27500, Direction::East));
+        assert!(!vfr(29500, Direction::East));
+        assert!(!vfr(5000, Direction::East));
+        assert!(vfr(4500, Direction::West));
+        assert!(vfr(28500, Direction::West));
+        assert!(!vfr(3500, Direction::West));
+    }
+
+    #[test]
+    fn yankee_is_ifr() {
+        let rule = ffi::FlightRule::Yankee;
+        assert!(is_valid_cruise_level(
+            35000,
+            Direction::East,
+            &rule,
+            Units::Feet
+        ));
+    }
+
+    #[test]
+    fn zulu_is_vfr() {
+        let rule = ffi::FlightRule::Zulu;
+        assert!(is_valid_cruise_level(
+            5500,
+            Direction::East,
+            &rule,
+            Units::Feet
+        ));
+        assert!(!is_valid_cruise_level(
+            5000,
+            Direction::East,
+            &rule,
+            Units::Feet
+        ));
+    }
+
+    #[test]
+    fn metric() {
+        let check = |meters: f64, direction| {
+            let feet = (meters * FEET_PER_METER).round() as u32;
+            is_valid_cruise_level(feet, direction, &ffi::FlightRule::Ifr, Units::Metric)
+        };
+        assert!(check(8900.0, Direction::East));
+        assert!(check(10100.0, Direction::East));
+        assert!(check(13700.0, Direction::East));
+        assert!(!check(8400.0, Direction::East));
+        assert!(check(8400.0, Direction::West));
+        assert!(check(9200.0, Direction::West));
+        assert!(check(13100.0, Direction::West));
+        assert!(!check(8900.0, Direction::West));
+    }
+}
@@ -1,10 +1,31 @@
+pub mod aircraft;
+pub mod checks;
 pub mod config;
+pub mod coverage;
+pub mod datafeed;
+pub mod decision_table;
+pub mod equipment;
+pub mod fpl;
+pub mod geo;
+pub mod item18;
 mod lang;
-
+pub mod levels;
+pub mod lint;
+pub mod navdata;
+#[cfg(feature = "plugin")]
+pub mod plugin;
+pub mod route;
+pub mod rule_tests;
+mod serde_impls;
+pub mod vfpc;
+
+use std::collections::HashMap;
 use std::fmt::Display;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
 use lang::ast;
+use lang::parser;
 
 #[cxx::bridge(namespace = "ffi")]
 pub mod ffi {
@@ -99,8 +120,12 @@ pub mod ffi {
     #[derive(Debug, Clone)]
     struct Aircraft {
         typ: AircraftType,
+        /// ICAO type designator, e.g. `A320`.
+        icao: String,
         wtc: WakeTurbulenceCategory,
         faa_equip_code: FaaEquipmentCode,
+        /// ICAO item 10 equipment and capabilities, e.g. `SDE2E3FGIJ1RWY/LB1`.
+        equip: String,
         eng_typ: EngineType,
         eng_count: u8,
         is_rvsm_capable: bool,
@@ -125,6 +150,8 @@ pub mod ffi {
         arr: String,
         sid: String,
         route: String,
+        /// ICAO item 18 other information.
+        remarks: String,
     }
 
     #[derive(Debug)]
@@ -140,26 +167,6 @@ pub mod ffi {
         Warning,
         Error,
     }
-
-    extern "Rust" {
-        fn check_flightplan(fp: FlightPlan) -> Result<Action>;
-        // fn on_function_call(plugin: Pin<&mut EsPlugin>);
-        // fn on_get_tag_item(plugin: Pin<&mut EsPlugin>);
-    }
-
-    #[namespace = ""]
-    unsafe extern "C++" {
-        include!("esfpc/cxx/main.hpp");
-
-        #[namespace = "EuroScopePlugIn"]
-        type CPlugIn;
-
-        type EsPlugin;
-
-        fn create_plugin() -> UniquePtr<EsPlugin>;
-        fn display_user_message(self: Pin<&mut EsPlugin>, message: &str);
-        fn get_dll_path() -> Result<String>;
-    }
 }
 
 impl Display for ffi::AircraftType {
@@ -251,6 +258,96 @@ impl Display for ffi::FlightRule {
     }
 }
 
+impl FromStr for ffi::AircraftType {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "?" => Ok(Self::Unknown),
+            "L" => Ok(Self::Landplane),
+            "S" => Ok(Self::Seaplane),
+            "A" => Ok(Self::Amphibian),
+            "H" => Ok(Self::Helicopter),
+            "G" => Ok(Self::Gyrocopter),
+            "T" => Ok(Self::TiltWing),
+            _ => Err("Invalid aircraft type"),
+        }
+    }
+}
+
+impl FromStr for ffi::WakeTurbulenceCategory {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "?" => Ok(Self::Unknown),
+            "L" => Ok(Self::Light),
+            "M" => Ok(Self::Medium),
+            "H" => Ok(Self::Heavy),
+            "S" | "J" => Ok(Self::Super),
+            _ => Err("Invalid wake turbulence category"),
+        }
+    }
+}
+
+impl FromStr for ffi::FaaEquipmentCode {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "?" => Ok(Self::Unknown),
+            "T" => Ok(Self::T),
+            "X" => Ok(Self::X),
+            "U" => Ok(Self::U),
+            "D" => Ok(Self::D),
+            "B" => Ok(Self::B),
+            "A" => Ok(Self::A),
+            "M" => Ok(Self::M),
+            "N" => Ok(Self::N),
+            "P" => Ok(Self::P),
+            "Y" => Ok(Self::Y),
+            "C" => Ok(Self::C),
+            "I" => Ok(Self::I),
+            "E" => Ok(Self::E),
+            "F" => Ok(Self::F),
+            "G" => Ok(Self::G),
+            "R" => Ok(Self::R),
+            "W" => Ok(Self::W),
+            "Q" => Ok(Self::Q),
+            _ => Err("Invalid FAA equipment code"),
+        }
+    }
+}
+
+impl FromStr for ffi::EngineType {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "?" => Ok(Self::Unknown),
+            "P" => Ok(Self::Piston),
+            "T" => Ok(Self::Turboprop),
+            "J" => Ok(Self::Jet),
+            "E" => Ok(Self::Electric),
+            _ => Err("Invalid engine type"),
+        }
+    }
+}
+
+impl FromStr for ffi::FlightRule {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "V" => Ok(Self::Vfr),
+            "I" => Ok(Self::Ifr),
+            "Y" => Ok(Self::Yankee),
+            "Z" => Ok(Self::Zulu),
+            _ => Err("Invalid flight rule"),
+        }
+    }
+}
+
 impl From<config::Action> for ffi::Action {
     fn from(config_action: config::Action) -> Self {
         Self {
@@ -265,91 +362,215 @@ impl From<config::Action> for ffi::Action {
     }
 }
 
-static mut PLUGIN: Option<Plugin> = None;
-
-pub struct Plugin {
-    /// Our plugin holds a unique pointer to automatically deallocate the C++ plugin when this Rust counterpart is dropped.
-    ///
-    /// ## Warning
-    /// This is not really a unique pointer. EuroScope holds a reference to the C++ plugin as well!
-    cpp_plugin: cxx::UniquePtr<ffi::EsPlugin>,
+/// The rule files of a rules directory, together with the databases configured in the settings
+/// file next to it.
+///
+/// The files are checked in the order of their priority, then of their paths, and the rules of a
+/// file in the order of their priority, then of the file, see [`config::sort`] and
+/// [`config::Config::ordered_rules`]. So the action shown does not depend on the file system.
+pub struct Checker {
     configs: Vec<config::Config>,
+    files: Vec<PathBuf>,
+    navdata: navdata::NavDatabase,
+    aircraft: aircraft::AircraftDatabase,
 }
 
-impl Drop for Plugin {
-    fn drop(&mut self) {
-        self.cpp_plugin
-            .pin_mut()
-            .display_user_message("ESFPC unloaded.");
-    }
-}
-
-impl Plugin {
-    pub fn start(mut cpp_plugin: cxx::UniquePtr<ffi::EsPlugin>) -> Result<Self, std::io::Error> {
-        let rules_dir = find_rules_dir()?;
-        println!("Rules directory: {}", rules_dir.display());
-
-        let mut configs = vec![];
-        for entry in rules_dir.read_dir()? {
-            let path = entry?.path();
-            let file = std::fs::File::open(path)?;
-            let reader = std::io::BufReader::new(file);
-            let config = serde_json::from_reader(reader)?;
-            configs.push(config);
+impl Checker {
+    pub fn load(rules_dir: &Path) -> Result<Self, std::io::Error> {
+        let mut loaded = vec![];
+        for path in config::rule_files(rules_dir)? {
+            let config = config::Config::load(&path)?;
+            loaded.push((path, config));
         }
+        config::sort(&mut loaded);
+        let (files, configs) = loaded.into_iter().unzip();
 
-        cpp_plugin.pin_mut().display_user_message("ESFPC loaded.");
+        let (settings, settings_dir) = load_settings(rules_dir)?;
+        let navdata = load_navdata(&settings, &settings_dir)?;
+        let aircraft = load_aircraft(&settings, &settings_dir)?;
 
         Ok(Self {
-            cpp_plugin,
             configs,
+            files,
+            navdata,
+            aircraft,
         })
     }
-}
 
-fn find_rules_dir() -> Result<PathBuf, std::io::Error> {
-    let dir = std::env::current_dir()?.join("rules");
-    if dir.exists() {
-        return Ok(dir);
+    pub fn configs(&self) -> &[config::Config] {
+        &self.configs
     }
 
-    let dll_path =
-        ffi::get_dll_path().map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
-    let dir = PathBuf::from(dll_path)
+    /// Paths of the rule files, in the same order as [`Checker::configs`].
+    pub fn files(&self) -> &[PathBuf] {
+        &self.files
+    }
+
+    pub fn navdata(&self) -> &navdata::NavDatabase {
+        &self.navdata
+    }
+
+    /// Navigation database, if any sector file was loaded.
+    fn loaded_navdata(&self) -> Option<&navdata::NavDatabase> {
+        (!self.navdata.is_empty()).then_some(&self.navdata)
+    }
+
+    pub fn aircraft(&self) -> &aircraft::AircraftDatabase {
+        &self.aircraft
+    }
+
+    /// Aircraft performance database, if any aircraft file was loaded.
+    fn loaded_aircraft(&self) -> Option<&aircraft::AircraftDatabase> {
+        (!self.aircraft.is_empty()).then_some(&self.aircraft)
+    }
+
+    /// The action shown for a flight plan, see [`check_configs`].
+    pub fn check(&self, fp: &ffi::FlightPlan) -> Result<config::Action, &'static str> {
+        check_configs(
+            &self.configs,
+            fp,
+            self.loaded_navdata(),
+            self.loaded_aircraft(),
+        )
+    }
+
+    /// Traces of the rules evaluated for a flight plan, see [`explain`].
+    pub fn explain(&self, fp: &ffi::FlightPlan) -> Vec<RuleTrace> {
+        explain(
+            &self.configs,
+            fp,
+            self.loaded_navdata(),
+            self.loaded_aircraft(),
+        )
+    }
+
+    /// Evaluates an expression like a rule condition, but of any type, e.g. `sidwpt`, with the
+    /// tables of all rule files. A table defined by several files is the one of the first file.
+    pub fn eval(
+        &self,
+        input: &str,
+        fp: &ffi::FlightPlan,
+    ) -> Result<ast::Trace, parser::SyntaxError> {
+        let expr = parser::parse_spanned(input)?;
+        let mut tables = HashMap::new();
+        for config in self.configs.iter().rev() {
+            tables.extend(config.tables.clone());
+        }
+        let ctx = ast::Context {
+            navdata: self.loaded_navdata(),
+            aircraft: self.loaded_aircraft(),
+            tables: Some(&tables),
+        };
+        Ok(ast::explain(&expr, fp, &ctx))
+    }
+
+    /// All findings for a flight plan, see [`findings`].
+    pub fn findings(&self, fp: &ffi::FlightPlan) -> Vec<Finding> {
+        findings(
+            &self.configs,
+            fp,
+            self.loaded_navdata(),
+            self.loaded_aircraft(),
+        )
+    }
+
+    /// Lines explaining the action shown for a flight plan, as printed by the "Check FP" function
+    /// of the plugin: the action with the name and detail of its finding and, if a rule decided
+    /// it, its evaluated condition.
+    pub fn explain_lines(&self, fp: &ffi::FlightPlan) -> Vec<String> {
+        let mut lines = vec![match self.findings(fp).first() {
+            Some(Finding {
+                name,
+                action: Ok(action),
+                detail,
+            }) => match (name, detail) {
+                (Some(name), Some(detail)) => {
+                    format!("{} {} ({}: {})", action.typ, action.msg, name, detail)
+                }
+                (Some(name), None) => format!("{} {} ({})", action.typ, action.msg, name),
+                (None, _) => format!("{} {}", action.typ, action.msg),
+            },
+            Some(Finding { action: Err(e), .. }) => format!("Check failed: {}", e),
+            None => "warning UNK (no rule matched)".into(),
+        }];
+        if let Some(rule) = self.explain(fp).pop().filter(RuleTrace::fired) {
+            lines.extend(rule.trace.lines());
+        }
+        lines
+    }
+}
+
+/// Name of the settings file, which is placed next to the rules directory.
+const SETTINGS_FILE: &str = "esfpc.json";
+
+/// Reads the settings file, if there is one, and returns it together with its directory.
+fn load_settings(rules_dir: &Path) -> Result<(config::Settings, PathBuf), std::io::Error> {
+    let settings_path = rules_dir.with_file_name(SETTINGS_FILE);
+    let settings_dir = settings_path
         .parent()
-        .ok_or_else(|| {
-            std::io::Error::new(
-                std::io::ErrorKind::InvalidData,
-                "DLL path has no parent directory",
-            )
-        })?
-        .join("rules");
-    if dir.exists() {
-        return Ok(dir);
-    }
-
-    Err(std::io::Error::new(
-        std::io::ErrorKind::InvalidData,
-        "No rules directory found",
-    ))
+        .unwrap_or_else(|| Path::new("."))
+        .to_path_buf();
+    if !settings_path.exists() {
+        return Ok((config::Settings::default(), settings_dir));
+    }
+
+    let file = std::fs::File::open(&settings_path)?;
+    let reader = std::io::BufReader::new(file);
+    let settings = serde_json::from_reader(reader)?;
+    Ok((settings, settings_dir))
 }
 
-pub fn check_flightplan(fp: ffi::FlightPlan) -> Result<ffi::Action, &'static str> {
-    unsafe {
-        match &PLUGIN {
-            Some(plugin) => check_flightplan_impl(plugin, fp).map(ffi::Action::from),
-            None => unreachable!(),
-        }
+/// Loads the sector files listed in the settings.
+fn load_navdata(
+    settings: &config::Settings,
+    settings_dir: &Path,
+) -> Result<navdata::NavDatabase, std::io::Error> {
+    let mut navdata = navdata::NavDatabase::default();
+    for path in &settings.sector_files {
+        navdata.load(&settings_dir.join(path))?;
+    }
+    if let Some(variation) = settings.magnetic_variation {
+        navdata.set_magnetic_variation(variation);
     }
+
+    Ok(navdata)
 }
 
-pub fn check_flightplan_impl(
-    plugin: &Plugin,
-    fp: ffi::FlightPlan,
+/// Loads the aircraft performance files listed in the settings.
+fn load_aircraft(
+    settings: &config::Settings,
+    settings_dir: &Path,
+) -> Result<aircraft::AircraftDatabase, std::io::Error> {
+    let mut aircraft = aircraft::AircraftDatabase::default();
+    for path in &settings.aircraft_files {
+        aircraft.load(&settings_dir.join(path))?;
+    }
+    Ok(aircraft)
+}
+
+/// Check a flight plan against the built-in checks of all configs, then against their rules.
+///
+/// Returns the action of the first failing check or matching rule, or a warning if no rule
+/// matches.
+pub fn check_configs(
+    configs: &[config::Config],
+    fp: &ffi::FlightPlan,
+    navdata: Option<&navdata::NavDatabase>,
+    aircraft: Option<&aircraft::AircraftDatabase>,
 ) -> Result<config::Action, &'static str> {
-    for config in &plugin.configs {
-        for rule in &config.rules {
-            return match ast::eval_cond(&rule.condition, &fp) {
+    for config in configs {
+        if let Some(action) = checks::check(&config.checks, fp, navdata, aircraft) {
+            return Ok(action);
+        }
+    }
+    for config in configs {
+        let ctx = ast::Context {
+            navdata,
+            aircraft,
+            tables: Some(&config.tables),
+        };
+        for (_, rule) in config.ordered_rules() {
+            return match ast::eval_cond(&rule.condition, fp, &ctx) {
                 Ok(true) => Ok(rule.action.clone()),
                 Ok(false) => continue,
                 Err(e) => Err(e),
@@ -362,38 +583,107 @@ pub fn check_flightplan_impl(
     })
 }
 
-#[export_name = "?EuroScopePlugInInit@@YAXPAPAVCPlugIn@EuroScopePlugIn@@@Z"]
-fn plugin_init(plugin_instance: *mut *mut ffi::CPlugIn) {
-    // Safeguard, that the EuroScope pointer can be dereferenced later.
-    assert!(!plugin_instance.is_null());
-
-    // Create a new C++ plugin instance on the heap as unique pointer.
-    let cpp_plugin = ffi::create_plugin();
-
-    // Turn the unique pointer into a raw pointer.
-    // This is required, because we have to set the EuroScope plugin pointer to the same instance.
-    // We are now responsible again to free the memory of cpp_plugin.
-    let cpp_plugin_ptr = cpp_plugin.into_raw();
-
-    // Recreate a unique pointer to handle the deallocation on drop.
-    // When drop is called, EsPlugin is freed and we are no longer responsible.
-    let cpp_plugin = unsafe { cxx::UniquePtr::from_raw(cpp_plugin_ptr) };
-
-    match Plugin::start(cpp_plugin) {
-        Ok(plugin) => unsafe {
-            // Initialize static variable with out plugin.
-            PLUGIN = Some(plugin);
-            // Set the EuroScope plugin pointer.
-            *plugin_instance = cpp_plugin_ptr as *mut ffi::CPlugIn;
-        },
-        Err(_) => unsafe {
-            // Set the EuroScope plugin pointer to null, as the plugin creation failed.
-            *plugin_instance = std::ptr::null_mut();
-        },
+/// A failing built-in check or a matching rule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    /// Name of the check, e.g. `rvsm`, or of the rule, if it has one.
+    pub name: Option<String>,
+    /// The action, or the error if the rule could not be evaluated.
+    pub action: Result<config::Action, &'static str>,
+    /// What a check found, e.g. the unknown points.
+    pub detail: Option<String>,
+}
+
+/// Check a flight plan against all built-in checks and rules of all configs.
+///
+/// Unlike [`check_configs`], this does not stop at the first finding. The findings are in the same
+/// order, so the first one is the action shown in EuroScope.
+pub fn findings(
+    configs: &[config::Config],
+    fp: &ffi::FlightPlan,
+    navdata: Option<&navdata::NavDatabase>,
+    aircraft: Option<&aircraft::AircraftDatabase>,
+) -> Vec<Finding> {
+    let mut findings = vec![];
+    for config in configs {
+        for failure in checks::check_all(&config.checks, fp, navdata, aircraft) {
+            findings.push(Finding {
+                name: Some(failure.name.into()),
+                action: Ok(failure.action),
+                detail: failure.detail,
+            });
+        }
+    }
+    for config in configs {
+        let ctx = ast::Context {
+            navdata,
+            aircraft,
+            tables: Some(&config.tables),
+        };
+        for (_, rule) in config.ordered_rules() {
+            let action = match ast::eval_cond(&rule.condition, fp, &ctx) {
+                Ok(true) => Ok(rule.action.clone()),
+                Ok(false) => continue,
+                Err(e) => Err(e),
+            };
+            findings.push(Finding {
+                name: rule.name.clone(),
+                action,
+                detail: None,
+            });
+        }
+    }
+    findings
+}
+
+/// A rule with its evaluated condition, see [`explain`].
+#[derive(Debug, Clone)]
+pub struct RuleTrace {
+    pub name: Option<String>,
+    pub action: config::Action,
+    pub trace: ast::Trace,
+}
+
+impl RuleTrace {
+    /// Whether the condition is true or could not be evaluated, so the rule decided the action.
+    pub fn fired(&self) -> bool {
+        !matches!(self.trace.value, Ok(ast::Expr::Lit(ast::Lit::Bool(false))))
     }
 }
 
-#[export_name = "?EuroScopePlugInExit@@YAXXZ"]
-fn plugin_exit() {
-    unsafe { PLUGIN = None };
+/// Explains the action shown for a flight plan by the traces of the rules evaluated, which are
+/// all rules up to the first one that fired, like in [`check_configs`]. If a built-in check
+/// fails, no rule is evaluated.
+pub fn explain(
+    configs: &[config::Config],
+    fp: &ffi::FlightPlan,
+    navdata: Option<&navdata::NavDatabase>,
+    aircraft: Option<&aircraft::AircraftDatabase>,
+) -> Vec<RuleTrace> {
+    for config in configs {
+        if checks::check(&config.checks, fp, navdata, aircraft).is_some() {
+            return vec![];
+        }
+    }
+    let mut traces = vec![];
+    for config in configs {
+        let ctx = ast::Context {
+            navdata,
+            aircraft,
+            tables: Some(&config.tables),
+        };
+        for (_, rule) in config.ordered_rules() {
+            let trace = RuleTrace {
+                name: rule.name.clone(),
+                action: rule.action.clone(),
+                trace: ast::explain(&rule.condition, fp, &ctx),
+            };
+            let fired = trace.fired();
+            traces.push(trace);
+            if fired {
+                return traces;
+            }
+        }
+    }
+    traces
 }
@@ -0,0 +1,373 @@
+//! Static checks of rule files, which find mistakes before the rules are loaded into EuroScope.
+
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::config::{self, Action, Config};
+use crate::lang::constraints::Constraints;
+use crate::lang::types::{self, Type, TypeError};
+use crate::vfpc;
+
+/// Longest message which fits into the tag item, whose buffer of `ITEM_STRING_SIZE` (16) in
+/// `cxx/main.cpp` includes the terminating null. Longer messages are truncated.
+pub const MAX_MSG_LEN: usize = 15;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+    pub file: PathBuf,
+    /// 1-based number of the rule in its file, if the diagnostic is about a rule.
+    pub rule: Option<usize>,
+    /// Name of the rule, or of the check or table the diagnostic is about.
+    pub name: Option<String>,
+    pub severity: Severity,
+    /// Kind of the diagnostic, e.g. `unknown-identifier`.
+    pub code: &'static str,
+    pub msg: String,
+}
+
+/// `rules/eddf.json: error: rule 3 (CINDY destination): Unknown identifier dest [unknown-identifier]`
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(f, "{}: {}: ", self.file.display(), severity)?;
+        match (self.rule, &self.name) {
+            (Some(rule), Some(name)) => write!(f, "rule {} ({}): ", rule, name)?,
+            (Some(rule), None) => write!(f, "rule {}: ", rule)?,
+            (None, Some(name)) => write!(f, "{}: ", name)?,
+            (None, None) => {}
+        }
+        write!(f, "{} [{}]", self.msg, self.code)
+    }
+}
+
+/// Lints all rule files of a directory.
+pub fn lint_dir(rules_dir: &Path) -> Result<Vec<Diagnostic>, std::io::Error> {
+    let mut diagnostics = vec![];
+    let mut configs = vec![];
+    for path in config::rule_files(rules_dir)? {
+        match Config::load(&path) {
+            Ok(config) => {
+                diagnostics.extend(unsupported_vfpc_fields(&path));
+                configs.push((path, config))
+            }
+            Err(e) => diagnostics.push(Diagnostic {
+                file: path,
+                rule: None,
+                name: None,
+                severity: Severity::Error,
+                code: "parse",
+                msg: e.to_string(),
+            }),
+        }
+    }
+    config::sort(&mut configs);
+    diagnostics.extend(lint_configs(&configs));
+    Ok(diagnostics)
+}
+
+/// Warns about fields of a `Sid.json` file which are ignored, as they are not supported.
+fn unsupported_vfpc_fields(path: &Path) -> Vec<Diagnostic> {
+    if path
+        .extension()
+        .is_none_or(|e| !e.eq_ignore_ascii_case("json"))
+    {
+        return vec![];
+    }
+    let fields = match std::fs::read_to_string(path) {
+        Ok(input) if vfpc::is_sid_json(&input) => vfpc::unsupported_fields(&input),
+        _ => return vec![],
+    };
+    fields
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(sid, field)| Diagnostic {
+            file: path.to_path_buf(),
+            rule: None,
+            name: Some(sid),
+            severity: Severity::Warning,
+            code: "unsupported-field",
+            msg: format!("Field {field} is not supported and ignored"),
+        })
+        .collect()
+}
+
+/// Lints rule files in the order they are checked, as rules can shadow rules of later files.
+pub fn lint_configs(configs: &[(PathBuf, Config)]) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+    // Earlier rules with their file and number
+    let mut earlier: Vec<(&Path, usize, &config::Rule)> = vec![];
+    for (path, config) in configs {
+        let diagnostic =
+            |rule: Option<usize>, name: Option<&str>, severity, code, msg| Diagnostic {
+                file: path.clone(),
+                rule,
+                name: name.map(String::from),
+                severity,
+                code,
+                msg,
+            };
+
+        for (check, action) in config.checks.actions() {
+            if let Some(msg) = long_message(action) {
+                diagnostics.push(diagnostic(
+                    None,
+                    Some(check),
+                    Severity::Warning,
+                    "long-message",
+                    msg,
+                ));
+            }
+        }
+
+        let mut names: Vec<&str> = config.tables.keys().map(String::as_str).collect();
+        names.sort();
+        for name in names {
+            let used = config
+                .rules
+                .iter()
+                .any(|rule| types::idents(&rule.condition).contains(&name));
+            if !used {
+                diagnostics.push(diagnostic(
+                    None,
+                    Some(name),
+                    Severity::Warning,
+                    "unused-table",
+                    "Table is not used by any rule".into(),
+                ));
+            }
+        }
+
+        for (i, rule) in config.ordered_rules() {
+            let number = i + 1;
+            let rule_diagnostic = |severity, code, msg| {
+                diagnostic(Some(number), rule.name.as_deref(), severity, code, msg)
+            };
+
+            match types::check(&rule.condition, &config.tables) {
+                Ok(Type::Bool | Type::Any) => {}
+                Ok(typ) => diagnostics.push(rule_diagnostic(
+                    Severity::Error,
+                    "type",
+                    format!("Condition is {}, not bool", typ),
+                )),
+                Err(e @ TypeError::UnknownIdent(_)) => diagnostics.push(rule_diagnostic(
+                    Severity::Error,
+                    "unknown-identifier",
+                    e.to_string(),
+                )),
+                Err(e @ TypeError::UnknownFunction(_)) => diagnostics.push(rule_diagnostic(
+                    Severity::Error,
+                    "unknown-function",
+                    e.to_string(),
+                )),
+                Err(e @ TypeError::Mismatch(..)) => {
+                    diagnostics.push(rule_diagnostic(Severity::Error, "type", e.to_string()))
+                }
+            }
+
+            if let Some(msg) = long_message(&rule.action) {
+                diagnostics.push(rule_diagnostic(Severity::Warning, "long-message", msg));
+            }
+
+            if let Some(name) = &rule.name {
+                let duplicate = earlier
+                    .iter()
+                    .find(|(_, _, other)| other.name.as_ref() == Some(name));
+                if let Some((file, number, _)) = duplicate {
+                    diagnostics.push(rule_diagnostic(
+                        Severity::Warning,
+                        "duplicate-name",
+                        format!("Name is also used by rule {} in {}", number, file.display()),
+                    ));
+                }
+            }
+
+            let constraints = Constraints::new(&rule.condition);
+            if !constraints.can_be_true() {
+                diagnostics.push(rule_diagnostic(
+                    Severity::Warning,
+                    "unreachable",
+                    "Condition is always false".into(),
+                ));
+            } else if let Some((file, number, _)) = earlier.iter().find(|(file, _, other)| {
+                constraints.implies(&Constraints::new(&other.condition), *file == path)
+            }) {
+                diagnostics.push(rule_diagnostic(
+                    Severity::Warning,
+                    "unreachable",
+                    format!(
+                        "Every flight plan matching it matches rule {} in {} first",
+                        number,
+                        file.display()
+                    ),
+                ));
+            }
+            earlier.push((path, number, rule));
+        }
+    }
+    diagnostics
+}
+
+/// Message, if the action message does not fit into the tag item.
+fn long_message(action: &Action) -> Option<String> {
+    let len = action.msg.chars().count();
+    (len > MAX_MSG_LEN).then(|| {
+        format!(
+            "Message '{}' is longer than {} characters",
+            action.msg, MAX_MSG_LEN
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lint(files: &[(&str, &str)]) -> Vec<(Option<usize>, &'static str)> {
+        let configs: Vec<(PathBuf, Config)> = files
+            .iter()
+            .map(|(path, json)| (PathBuf::from(path), serde_json::from_str(json).unwrap()))
+            .collect();
+        lint_configs(&configs)
+            .into_iter()
+            .map(|d| (d.rule, d.code))
+            .collect()
+    }
+
+    #[test]
+    fn valid() {
+        let rules = r#"{ "tables": { "max_rfl": { "EDDM": 24000 } }, "rules": [
+            { "name": "RFL", "condition": "arr in max_rfl and rfl > max_rfl[arr]", "action": { "typ": "error", "msg": "RFL" } },
+            { "name": "OK", "condition": "true", "action": { "typ": "success", "msg": "OK" } }
+        ] }"#;
+        assert_eq!(
+            Vec::<(Option<usize>, &str)>::new(),
+            lint(&[("a.json", rules)])
+        );
+    }
+
+    #[test]
+    fn rule_diagnostics() {
+        let rules = r#"{ "rules": [
+            { "condition": "destination == 'EDDM'", "action": { "typ": "error", "msg": "DST" } },
+            { "condition": "valid_rfl('E')", "action": { "typ": "error", "msg": "RFL" } },
+            { "condition": "rfl == 'F350'", "action": { "typ": "error", "msg": "RFL" } },
+            { "condition": "rfl % 2000", "action": { "typ": "error", "msg": "RFL" } },
+            { "condition": "dep == 'EDDF'", "action": { "typ": "error", "msg": "DEPARTURE RESTRICTED" } }
+        ] }"#;
+        assert_eq!(
+            vec![
+                (Some(1), "unknown-identifier"),
+                (Some(2), "unknown-function"),
+                (Some(3), "type"),
+                (Some(4), "type"),
+                (Some(5), "long-message"),
+            ],
+            lint(&[("a.json", rules)])
+        );
+    }
+
+    #[test]
+    fn unreachable_and_duplicates() {
+        let first = r#"{ "rules": [
+            { "name": "CINDY", "condition": "dep == 'EDDF' and sidwpt == 'CINDY'", "action": { "typ": "error", "msg": "RFL" } },
+            { "name": "Never", "condition": "false", "action": { "typ": "error", "msg": "X" } }
+        ] }"#;
+        let second = r#"{ "rules": [
+            { "name": "CINDY", "condition": "sidwpt == 'CINDY' and rfl > 5000 and dep == 'EDDF'", "action": { "typ": "error", "msg": "RFL" } },
+            { "condition": "sidwpt == 'ANEKI'", "action": { "typ": "error", "msg": "RFL" } }
+        ] }"#;
+        let diagnostics = lint(&[("a.json", first), ("b.json", second)]);
+        assert_eq!(
+            vec![
+                (Some(2), "unreachable"),
+                (Some(1), "duplicate-name"),
+                (Some(1), "unreachable"),
+            ],
+            diagnostics
+        );
+    }
+
+    #[test]
+    fn unreachable_by_priority() {
+        let rules = r#"{ "rules": [
+            { "condition": "dep == 'EDDF' and rfl > 30000", "action": { "typ": "error", "msg": "RFL" } },
+            { "condition": "dep == 'EDDF'", "action": { "typ": "success", "msg": "OK" }, "priority": 1 }
+        ] }"#;
+        assert_eq!(vec![(Some(1), "unreachable")], lint(&[("a.json", rules)]));
+    }
+
+    #[test]
+    fn unused_tables_and_checks() {
+        let rules = r#"{
+            "checks": { "rvsm": { "action": { "typ": "error", "msg": "NON RVSM AIRCRAFT" } } },
+            "tables": { "unused": { "EDDM": 1 } },
+            "rules": []
+        }"#;
+        let configs = vec![(
+            PathBuf::from("a.json"),
+            serde_json::from_str(rules).unwrap(),
+        )];
+        let diagnostics = lint_configs(&configs);
+        assert_eq!(2, diagnostics.len());
+        assert_eq!(
+            "a.json: warning: rvsm: Message 'NON RVSM AIRCRAFT' is longer than 15 characters [long-message]",
+            diagnostics[0].to_string()
+        );
+        assert_eq!(
+            "a.json: warning: unused: Table is not used by any rule [unused-table]",
+            diagnostics[1].to_string()
+        );
+    }
+
+    #[test]
+    fn unsupported_vfpc_field() {
+        let dir = Path::new("tests/fixtures/cli/lint-vfpc");
+        let diagnostics = lint_dir(dir).unwrap();
+        assert_eq!(
+            vec![format!(
+                "{}: warning: EDDF CINDY: Field navigation is not supported and ignored [unsupported-field]",
+                dir.join("Sid.json").display()
+            )],
+            diagnostics
+                .iter()
+                .filter(|d| d.code == "unsupported-field")
+                .map(|d| d.to_string())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn trailing_garbage() {
+        let dir = Path::new("tests/fixtures/cli/lint-syntax");
+        let diagnostics = lint_dir(dir).unwrap();
+        let codes: Vec<_> = diagnostics.iter().map(|d| (d.rule, d.code)).collect();
+        assert_eq!(vec![(None, "parse"), (None, "parse")], codes);
+        assert!(
+            diagnostics[0].msg.contains(
+                "Bad token \"Ident(\"sidwpt\")\" at 14..20 in condition `dep == 'EDDF' sidwpt == 'CINDY'`"
+            ),
+            "{}",
+            diagnostics[0]
+        );
+        assert!(
+            diagnostics[1]
+                .msg
+                .contains("Char '.' unrecognized at 26..27 in condition"),
+            "{}",
+            diagnostics[1]
+        );
+    }
+}
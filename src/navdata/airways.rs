@@ -0,0 +1,61 @@
+//! Parser for airway CSV exports.
+//!
+//! Every line is one segment, `AIRWAY,FROM,TO[,DIRECTION]`. The direction is either `B` for both
+//! directions, which is the default, or `F` if the segment may only be flown from `FROM` to `TO`.
+//! Empty lines and lines starting with `#` are ignored, as are malformed lines.
+
+use super::{NavDatabase, Segment};
+
+pub fn parse_csv(input: &str, db: &mut NavDatabase) {
+    for line in input.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        let (airway, from, to, direction) = match fields[..] {
+            [airway, from, to] => (airway, from, to, "B"),
+            [airway, from, to, direction] => (airway, from, to, direction),
+            _ => continue,
+        };
+        let one_way = match direction {
+            "B" | "" => false,
+            "F" => true,
+            _ => continue,
+        };
+        if airway.is_empty() || from.is_empty() || to.is_empty() {
+            continue;
+        }
+        db.add_segment(
+            airway,
+            Segment {
+                from: from.into(),
+                to: to.into(),
+                one_way,
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn segments() {
+        let input = "# airway,from,to,direction\nT104,HAREM,ROKIL\n\nY163, ROKIL , NOSEX ,F\nINVALID\nT104,X,Y,Q\n";
+        let mut db = NavDatabase::default();
+        parse_csv(input, &mut db);
+        assert_eq!(
+            &[Segment {
+                from: "HAREM".into(),
+                to: "ROKIL".into(),
+                one_way: false,
+            }],
+            db.segments("T104")
+        );
+        assert!(db.segments("Y163")[0].one_way);
+        assert_eq!("NOSEX", db.segments("Y163")[0].to);
+        assert!(!db.contains_airway("INVALID"));
+    }
+}
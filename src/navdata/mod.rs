@@ -0,0 +1,400 @@
+//! Navigation database built from EuroScope sector files.
+
+pub mod airways;
+pub mod sector;
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::path::Path;
+
+use crate::geo;
+use crate::route::{self, Element, Route};
+
+/// WGS84 coordinate in decimal degrees.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Coord {
+    pub lat: f64,
+    pub lon: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PointKind {
+    Vor,
+    Ndb,
+    Fix,
+    Airport,
+}
+
+/// Named significant point.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NavPoint {
+    pub name: String,
+    pub kind: PointKind,
+    pub coord: Coord,
+}
+
+/// One direction of a runway.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Runway {
+    pub airport: String,
+    /// Runway designator, e.g. `25C`.
+    pub ident: String,
+    /// Magnetic heading in degrees.
+    pub heading: u16,
+    pub threshold: Coord,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProcedureKind {
+    Sid,
+    Star,
+}
+
+/// SID or STAR as defined in the `[SIDSSTARS]` section of an `.ese` file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Procedure {
+    pub kind: ProcedureKind,
+    pub airport: String,
+    pub runway: String,
+    pub name: String,
+    pub points: Vec<String>,
+}
+
+/// Segment of an airway between two named points.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Segment {
+    pub from: String,
+    pub to: String,
+    /// The segment may only be flown from `from` to `to`.
+    pub one_way: bool,
+}
+
+/// Why an airway segment of a route is invalid.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SegmentError {
+    UnknownAirway,
+    /// The airway is not preceded or followed by a point.
+    MissingPoint,
+    /// The point is not on the airway.
+    NotOnAirway(String),
+    /// Both points are on the airway, but not connected by it.
+    Disconnected,
+    /// The airway may only be flown in the opposite direction.
+    WrongDirection,
+}
+
+/// First invalid airway segment of a route.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BrokenSegment {
+    pub entry: Option<String>,
+    pub airway: String,
+    pub exit: Option<String>,
+    pub error: SegmentError,
+}
+
+impl fmt::Display for BrokenSegment {
+    /// The segment as written in the route, e.g. `HAREM T104 ROKIL`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parts = [
+            self.entry.as_deref(),
+            Some(&self.airway),
+            self.exit.as_deref(),
+        ];
+        let parts: Vec<&str> = parts.into_iter().flatten().collect();
+        write!(f, "{}", parts.join(" "))
+    }
+}
+
+/// In-memory navigation database.
+///
+/// Point names are not unique world-wide, so every name maps to all points with that name.
+#[derive(Debug, Default)]
+pub struct NavDatabase {
+    points: HashMap<String, Vec<NavPoint>>,
+    runways: Vec<Runway>,
+    procedures: Vec<Procedure>,
+    airways: HashMap<String, Vec<Segment>>,
+    /// Magnetic variation in degrees, positive for east.
+    magnetic_variation: Option<f64>,
+}
+
+impl NavDatabase {
+    /// Load a `.sct`, `.ese` or airway `.csv` file, depending on its extension.
+    ///
+    /// Sector files are usually not UTF-8 encoded, invalid characters are replaced.
+    pub fn load(&mut self, path: &Path) -> Result<(), std::io::Error> {
+        let bytes = std::fs::read(path)?;
+        let input = String::from_utf8_lossy(&bytes);
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase());
+        match extension.as_deref() {
+            Some("sct") => sector::parse_sct(&input, self),
+            Some("ese") => sector::parse_ese(&input, self),
+            Some("csv") => airways::parse_csv(&input, self),
+            _ => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("Unknown sector file type: {}", path.display()),
+                ))
+            }
+        }
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+            && self.runways.is_empty()
+            && self.procedures.is_empty()
+            && self.airways.is_empty()
+    }
+
+    /// Magnetic variation in degrees, positive for east. Defaults to 0.
+    pub fn magnetic_variation(&self) -> f64 {
+        self.magnetic_variation.unwrap_or(0.0)
+    }
+
+    pub fn set_magnetic_variation(&mut self, variation: f64) {
+        self.magnetic_variation = Some(variation);
+    }
+
+    pub fn add_point(&mut self, point: NavPoint) {
+        self.points
+            .entry(point.name.clone())
+            .or_default()
+            .push(point);
+    }
+
+    pub fn add_airway(&mut self, name: &str) {
+        self.airways.entry(name.into()).or_default();
+    }
+
+    pub fn add_segment(&mut self, airway: &str, segment: Segment) {
+        self.airways.entry(airway.into()).or_default().push(segment);
+    }
+
+    pub fn add_runway(&mut self, runway: Runway) {
+        self.runways.push(runway);
+    }
+
+    pub fn add_procedure(&mut self, procedure: Procedure) {
+        self.procedures.push(procedure);
+    }
+
+    /// All points with the given name.
+    pub fn points(&self, name: &str) -> &[NavPoint] {
+        self.points.get(name).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    pub fn contains_point(&self, name: &str) -> bool {
+        self.points.contains_key(name)
+    }
+
+    pub fn contains_airway(&self, name: &str) -> bool {
+        self.airways.contains_key(name)
+    }
+
+    /// All segments of the given airway.
+    pub fn segments(&self, airway: &str) -> &[Segment] {
+        self.airways.get(airway).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// Points of the route which are not in the database, in order of appearance.
+    ///
+    /// Coordinates are always known. Points given as bearing and distance are known if their
+    /// navaid is. Elements which could not be classified are reported as unknown as well, airways
+    /// filed in the position of a point are not.
+    pub fn unknown_points(&self, route: &Route) -> Vec<String> {
+        let mut unknown: Vec<String> = vec![];
+        for element in &route.elements {
+            let name = match element {
+                _ if self.misplaced_airway(element).is_some() => continue,
+                Element::Point { name, .. } if route::is_coordinate(name) => continue,
+                Element::Point { name, .. } => {
+                    let navaid = route::bearing_distance_navaid(name).unwrap_or(name);
+                    if self.contains_point(navaid) {
+                        continue;
+                    }
+                    name
+                }
+                Element::Unknown(word) => word,
+                _ => continue,
+            };
+            if !unknown.contains(name) {
+                unknown.push(name.clone());
+            }
+        }
+        unknown
+    }
+
+    /// Airways of the route which are not in the database, in order of appearance.
+    ///
+    /// If no airways are loaded, no airway is reported.
+    pub fn unknown_airways(&self, route: &Route) -> Vec<String> {
+        if self.airways.is_empty() {
+            return vec![];
+        }
+        let mut unknown: Vec<String> = vec![];
+        for element in &route.elements {
+            if let Element::Airway(name) = element {
+                if !self.contains_airway(name) && !unknown.contains(name) {
+                    unknown.push(name.clone());
+                }
+            }
+        }
+        unknown
+    }
+
+    /// Coordinates of the route from the departure to the destination airport.
+    ///
+    /// Points with ambiguous names resolve to the one closest to the previous point. Unknown
+    /// points are skipped. Returns `None` if either airport is unknown.
+    pub fn route_coords(&self, dep: &str, route: &Route, arr: &str) -> Option<Vec<Coord>> {
+        let mut coords = vec![self.airport(dep)?.coord];
+        for element in &route.elements {
+            let Element::Point { name, .. } = element else {
+                continue;
+            };
+            let prev = coords[coords.len() - 1];
+            if let Some(coord) = self.point_coord(name, prev) {
+                coords.push(coord);
+            }
+        }
+        coords.push(self.airport(arr)?.coord);
+        Some(coords)
+    }
+
+    /// Coordinate of a route point, resolving ambiguous names relative to `near`.
+    fn point_coord(&self, name: &str, near: Coord) -> Option<Coord> {
+        if let Some(coord) = geo::parse_route_coord(name) {
+            return Some(coord);
+        }
+        let closest = |name: &str| {
+            self.points(name)
+                .iter()
+                .map(|p| p.coord)
+                .min_by(|a, b| geo::distance_nm(near, *a).total_cmp(&geo::distance_nm(near, *b)))
+        };
+        match route::bearing_distance_navaid(name) {
+            Some(navaid) => {
+                let bearing: f64 = name[navaid.len()..navaid.len() + 3].parse().ok()?;
+                let distance: f64 = name[navaid.len() + 3..].parse().ok()?;
+                let track = bearing + self.magnetic_variation();
+                Some(geo::destination(closest(navaid)?, track, distance))
+            }
+            None => closest(name),
+        }
+    }
+
+    /// First airway segment of the route which is not valid.
+    ///
+    /// Each airway must be entered and left at points on that airway, which are connected in the
+    /// direction of flight. If no airways are loaded, no segment is reported.
+    pub fn broken_segment(&self, route: &Route) -> Option<BrokenSegment> {
+        if self.airways.is_empty() {
+            return None;
+        }
+        let point = |element: Option<&Element>| match element {
+            Some(Element::Point { name, .. }) => Some(name.clone()),
+            _ => None,
+        };
+        for (i, element) in route.elements.iter().enumerate() {
+            let airway = match element {
+                Element::Airway(airway) => airway,
+                _ => match self.misplaced_airway(element) {
+                    Some(airway) => airway,
+                    None => continue,
+                },
+            };
+            let entry = i.checked_sub(1).and_then(|i| point(route.elements.get(i)));
+            let exit = point(route.elements.get(i + 1));
+            let error = match (&entry, &exit) {
+                (Some(entry), Some(exit)) => self.segment_error(airway, entry, exit),
+                _ => Some(SegmentError::MissingPoint),
+            };
+            if let Some(error) = error {
+                return Some(BrokenSegment {
+                    entry,
+                    airway: airway.clone(),
+                    exit,
+                    error,
+                });
+            }
+        }
+        None
+    }
+
+    /// Name of a known airway which [`Route::parse`] took for a point, as it is not between two
+    /// points, e.g. `T104` in `T104 ROKIL`. Points of the same name take precedence.
+    fn misplaced_airway<'a>(&self, element: &'a Element) -> Option<&'a String> {
+        match element {
+            Element::Point { name, change: None }
+                if self.contains_airway(name) && !self.contains_point(name) =>
+            {
+                Some(name)
+            }
+            _ => None,
+        }
+    }
+
+    fn segment_error(&self, airway: &str, entry: &str, exit: &str) -> Option<SegmentError> {
+        if !self.contains_airway(airway) {
+            return Some(SegmentError::UnknownAirway);
+        }
+        let segments = self.segments(airway);
+        for point in [entry, exit] {
+            if !segments.iter().any(|s| s.from == point || s.to == point) {
+                return Some(SegmentError::NotOnAirway(point.into()));
+            }
+        }
+        if connects(segments, entry, exit, true) {
+            None
+        } else if connects(segments, entry, exit, false) {
+            Some(SegmentError::WrongDirection)
+        } else {
+            Some(SegmentError::Disconnected)
+        }
+    }
+
+    pub fn airport(&self, icao: &str) -> Option<&NavPoint> {
+        self.points(icao)
+            .iter()
+            .find(|p| p.kind == PointKind::Airport)
+    }
+
+    pub fn runways<'a>(&'a self, airport: &'a str) -> impl Iterator<Item = &'a Runway> {
+        self.runways.iter().filter(move |r| r.airport == airport)
+    }
+
+    pub fn procedure(&self, airport: &str, name: &str) -> Option<&Procedure> {
+        self.procedures
+            .iter()
+            .find(|p| p.airport == airport && p.name == name)
+    }
+}
+
+/// Whether `to` can be reached from `from` along the segments, optionally ignoring one-way
+/// restrictions.
+fn connects(segments: &[Segment], from: &str, to: &str, directed: bool) -> bool {
+    let mut visited = HashSet::from([from]);
+    let mut queue = VecDeque::from([from]);
+    while let Some(point) = queue.pop_front() {
+        if point == to {
+            return true;
+        }
+        for segment in segments {
+            let next = if segment.from == point {
+                &segment.to
+            } else if segment.to == point && !(directed && segment.one_way) {
+                &segment.from
+            } else {
+                continue;
+            };
+            if visited.insert(next) {
+                queue.push_back(next);
+            }
+        }
+    }
+    false
+}
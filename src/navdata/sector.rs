@@ -0,0 +1,262 @@
+//! Parser for EuroScope sector files (`.sct`) and extended sector files (`.ese`).
+//!
+//! Lines which cannot be parsed are skipped, as sector files in the wild contain all sorts of
+//! oddities which should not prevent the plugin from loading.
+
+use std::collections::HashMap;
+
+use super::{Coord, NavDatabase, NavPoint, PointKind, Procedure, ProcedureKind, Runway, Segment};
+
+/// Parse one half of a coordinate, e.g. `N050.01.59.000` or `E008.34.14.000`.
+pub fn parse_coord_part(s: &str) -> Option<f64> {
+    let sign = match s.get(..1)? {
+        "N" | "E" => 1.0,
+        "S" | "W" => -1.0,
+        _ => return None,
+    };
+    let mut parts = s[1..].splitn(3, '.');
+    let deg: f64 = parts.next()?.parse().ok()?;
+    let min: f64 = parts.next()?.parse().ok()?;
+    let sec: f64 = parts.next()?.parse().ok()?;
+    Some(sign * (deg + min / 60.0 + sec / 3600.0))
+}
+
+pub fn parse_coord(lat: &str, lon: &str) -> Option<Coord> {
+    Some(Coord {
+        lat: parse_coord_part(lat)?,
+        lon: parse_coord_part(lon)?,
+    })
+}
+
+/// Iterate over all non-empty lines together with the name of their section.
+pub(crate) fn section_lines(input: &str) -> impl Iterator<Item = (&str, &str)> {
+    let mut section = "";
+    input.lines().filter_map(move |line| {
+        let line = line.split(';').next().unwrap_or("").trim_end();
+        if line.trim().is_empty() {
+            return None;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            section = name;
+            return None;
+        }
+        Some((section, line))
+    })
+}
+
+fn parse_point(fields: &[&str], kind: PointKind) -> Option<NavPoint> {
+    let (name, lat, lon) = match kind {
+        PointKind::Fix => (fields.first()?, fields.get(1)?, fields.get(2)?),
+        _ => (fields.first()?, fields.get(2)?, fields.get(3)?),
+    };
+    Some(NavPoint {
+        name: name.to_string(),
+        kind,
+        coord: parse_coord(lat, lon)?,
+    })
+}
+
+/// Both directions of a `[RUNWAY]` line.
+fn parse_runways(fields: &[&str]) -> Option<[Runway; 2]> {
+    let airport = fields.get(8)?.to_string();
+    Some([
+        Runway {
+            airport: airport.clone(),
+            ident: fields.first()?.to_string(),
+            heading: fields.get(2)?.parse().ok()?,
+            threshold: parse_coord(fields.get(4)?, fields.get(5)?)?,
+        },
+        Runway {
+            airport,
+            ident: fields.get(1)?.to_string(),
+            heading: fields.get(3)?.parse().ok()?,
+            threshold: parse_coord(fields.get(6)?, fields.get(7)?)?,
+        },
+    ])
+}
+
+/// Key to look up points by coordinate, precise to about a meter.
+fn coord_key(coord: Coord) -> (i64, i64) {
+    (
+        (coord.lat * 1e5).round() as i64,
+        (coord.lon * 1e5).round() as i64,
+    )
+}
+
+/// Name of an airway segment end, given either as coordinate or as point name twice.
+fn segment_point(lat: &str, lon: &str, names: &HashMap<(i64, i64), String>) -> Option<String> {
+    match parse_coord(lat, lon) {
+        Some(coord) => names.get(&coord_key(coord)).cloned(),
+        None => (lat == lon).then(|| lat.to_string()),
+    }
+}
+
+/// Airway segment of a `[HIGH AIRWAY]` or `[LOW AIRWAY]` line.
+///
+/// Segment ends given as coordinates are resolved to the point at that position.
+fn parse_segment(fields: &[&str], names: &HashMap<(i64, i64), String>) -> Option<Segment> {
+    Some(Segment {
+        from: segment_point(fields.get(1)?, fields.get(2)?, names)?,
+        to: segment_point(fields.get(3)?, fields.get(4)?, names)?,
+        one_way: false,
+    })
+}
+
+/// Parse the `[INFO]`, `[VOR]`, `[NDB]`, `[FIXES]`, `[AIRPORT]`, `[RUNWAY]`, `[HIGH AIRWAY]`
+/// and `[LOW AIRWAY]` sections.
+///
+/// The eighth `[INFO]` line is the magnetic variation, which sector files give positive for west.
+///
+/// The `[SID]` and `[STAR]` sections only contain diagrams, the procedures themselves are read
+/// from the `.ese` file.
+pub fn parse_sct(input: &str, db: &mut NavDatabase) {
+    let mut info_line = 0;
+    let mut names = None;
+    for (section, line) in section_lines(input) {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let kind = match section {
+            "INFO" => {
+                if info_line == 7 {
+                    if let Ok(variation) = line.trim().parse::<f64>() {
+                        db.set_magnetic_variation(-variation);
+                    }
+                }
+                info_line += 1;
+                continue;
+            }
+            "VOR" => PointKind::Vor,
+            "NDB" => PointKind::Ndb,
+            "FIXES" => PointKind::Fix,
+            "AIRPORT" => PointKind::Airport,
+            "RUNWAY" => {
+                for runway in parse_runways(&fields).into_iter().flatten() {
+                    db.add_runway(runway);
+                }
+                continue;
+            }
+            "HIGH AIRWAY" | "LOW AIRWAY" => {
+                let Some(name) = fields.first() else {
+                    continue;
+                };
+                db.add_airway(name);
+                // Airway sections follow the point sections
+                let names = names.get_or_insert_with(|| {
+                    db.points
+                        .values()
+                        .flatten()
+                        .filter(|p| p.kind != PointKind::Airport)
+                        .map(|p| (coord_key(p.coord), p.name.clone()))
+                        .collect()
+                });
+                if let Some(segment) = parse_segment(&fields, names) {
+                    db.add_segment(name, segment);
+                }
+                continue;
+            }
+            _ => continue,
+        };
+        if let Some(point) = parse_point(&fields, kind) {
+            db.add_point(point);
+        }
+    }
+}
+
+/// Parse the `[SIDSSTARS]` section, e.g. `SID:EDDF:25C:TOBAK7M:TOBAK`.
+pub fn parse_ese(input: &str, db: &mut NavDatabase) {
+    for (section, line) in section_lines(input) {
+        if section != "SIDSSTARS" {
+            continue;
+        }
+        let fields: Vec<&str> = line.split(':').map(str::trim).collect();
+        let kind = match fields.first() {
+            Some(&"SID") => ProcedureKind::Sid,
+            Some(&"STAR") => ProcedureKind::Star,
+            _ => continue,
+        };
+        if let [_, airport, runway, name, route, ..] = fields[..] {
+            db.add_procedure(Procedure {
+                kind,
+                airport: airport.into(),
+                runway: runway.into(),
+                name: name.into(),
+                points: route.split_whitespace().map(String::from).collect(),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn coord() {
+        let lat = parse_coord_part("N050.01.59.000").unwrap();
+        assert!((lat - 50.033_055).abs() < 1e-5);
+        let lon = parse_coord_part("W008.30.00.000").unwrap();
+        assert!((lon + 8.5).abs() < 1e-9);
+        assert_eq!(None, parse_coord_part("X050.01.59.000"));
+        assert_eq!(None, parse_coord_part("N050.01"));
+        assert_eq!(None, parse_coord_part("FFM"));
+    }
+
+    #[test]
+    fn sections() {
+        let input = "#define X 1\n[VOR]\n; comment\nFFM 114.200 N050.03.13.000 E008.38.14.000 ; Frankfurt\n\n[FIXES]\nCINDY N049.48.00.000 E008.58.00.000\n";
+        assert_eq!(
+            vec![
+                ("", "#define X 1"),
+                ("VOR", "FFM 114.200 N050.03.13.000 E008.38.14.000"),
+                ("FIXES", "CINDY N049.48.00.000 E008.58.00.000"),
+            ],
+            section_lines(input).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn malformed_lines_skipped() {
+        let input = "[FIXES]\nCINDY N049.48.00.000\nHAREM N049.40.00.000 E009.30.00.000\n[RUNWAY]\n07C 25C 069\n";
+        let mut db = NavDatabase::default();
+        parse_sct(input, &mut db);
+        assert!(!db.contains_point("CINDY"));
+        assert!(db.contains_point("HAREM"));
+        assert_eq!(0, db.runways("EDDF").count());
+    }
+
+    #[test]
+    fn airway_segments() {
+        let input = "[FIXES]\nCINDY N049.48.00.000 E008.58.00.000\nHAREM N049.40.00.000 E009.30.00.000\n[LOW AIRWAY]\nZ74 N049.48.00.000 E008.58.00.000 N049.40.00.000 E009.30.00.000\nZ74 HAREM HAREM ROKIL ROKIL\nZ74 N049.40.00.000 E009.30.00.000 N049.00.00.000 E010.00.00.000\n";
+        let mut db = NavDatabase::default();
+        parse_sct(input, &mut db);
+        let segments: Vec<_> = db
+            .segments("Z74")
+            .iter()
+            .map(|s| (s.from.as_str(), s.to.as_str()))
+            .collect();
+        assert_eq!(vec![("CINDY", "HAREM"), ("HAREM", "ROKIL")], segments);
+    }
+
+    #[test]
+    fn magnetic_variation() {
+        let input =
+            "[INFO]\nEDGG\nEDGG_CTR\nEDDF\nN050.01.59.000\nE008.34.14.000\n60\n39\n-3.0\n1\n";
+        let mut db = NavDatabase::default();
+        parse_sct(input, &mut db);
+        assert_eq!(3.0, db.magnetic_variation());
+    }
+
+    #[test]
+    fn procedures() {
+        let input = "[SIDSSTARS]\nSID:EDDF:25C:TOBAK7M:DF155 TOBAK\nSTAR:EDDM:26R:ROKIL1A:ROKIL\nINVALID:X\n";
+        let mut db = NavDatabase::default();
+        parse_ese(input, &mut db);
+        let sid = db.procedure("EDDF", "TOBAK7M").unwrap();
+        assert_eq!(ProcedureKind::Sid, sid.kind);
+        assert_eq!("25C", sid.runway);
+        assert_eq!(vec!["DF155", "TOBAK"], sid.points);
+        assert_eq!(
+            ProcedureKind::Star,
+            db.procedure("EDDM", "ROKIL1A").unwrap().kind
+        );
+    }
+}
@@ -0,0 +1,176 @@
+//! EuroScope plugin, which is created by the C++ plugin in `cxx/` and checks its flight plans.
+
+use std::path::PathBuf;
+
+use crate::{aircraft, config, navdata, Checker};
+
+#[cxx::bridge(namespace = "ffi")]
+pub mod ffi {
+    extern "Rust" {
+        fn check_flightplan(fp: FlightPlan) -> Result<Action>;
+        fn explain_flightplan(fp: FlightPlan) -> Vec<String>;
+        // fn on_function_call(plugin: Pin<&mut EsPlugin>);
+        // fn on_get_tag_item(plugin: Pin<&mut EsPlugin>);
+    }
+
+    unsafe extern "C++" {
+        include!("esfpc/src/lib.rs.h");
+
+        type FlightPlan = crate::ffi::FlightPlan;
+        type Action = crate::ffi::Action;
+    }
+
+    #[namespace = ""]
+    unsafe extern "C++" {
+        include!("esfpc/cxx/main.hpp");
+
+        #[namespace = "EuroScopePlugIn"]
+        type CPlugIn;
+
+        type EsPlugin;
+
+        fn create_plugin() -> UniquePtr<EsPlugin>;
+        fn display_user_message(self: Pin<&mut EsPlugin>, message: &str);
+        fn get_dll_path() -> Result<String>;
+    }
+}
+
+static mut PLUGIN: Option<Plugin> = None;
+
+pub struct Plugin {
+    /// Our plugin holds a unique pointer to automatically deallocate the C++ plugin when this Rust counterpart is dropped.
+    ///
+    /// ## Warning
+    /// This is not really a unique pointer. EuroScope holds a reference to the C++ plugin as well!
+    cpp_plugin: cxx::UniquePtr<ffi::EsPlugin>,
+    checker: Checker,
+}
+
+impl Drop for Plugin {
+    fn drop(&mut self) {
+        self.cpp_plugin
+            .pin_mut()
+            .display_user_message("ESFPC unloaded.");
+    }
+}
+
+impl Plugin {
+    pub fn start(mut cpp_plugin: cxx::UniquePtr<ffi::EsPlugin>) -> Result<Self, std::io::Error> {
+        let rules_dir = find_rules_dir()?;
+        println!("Rules directory: {}", rules_dir.display());
+
+        let checker = Checker::load(&rules_dir)?;
+
+        cpp_plugin.pin_mut().display_user_message("ESFPC loaded.");
+
+        Ok(Self {
+            cpp_plugin,
+            checker,
+        })
+    }
+
+    pub fn checker(&self) -> &Checker {
+        &self.checker
+    }
+
+    pub fn navdata(&self) -> &navdata::NavDatabase {
+        self.checker.navdata()
+    }
+
+    pub fn aircraft(&self) -> &aircraft::AircraftDatabase {
+        self.checker.aircraft()
+    }
+}
+
+fn find_rules_dir() -> Result<PathBuf, std::io::Error> {
+    let dir = std::env::current_dir()?.join("rules");
+    if dir.exists() {
+        return Ok(dir);
+    }
+
+    let dll_path =
+        ffi::get_dll_path().map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    let dir = PathBuf::from(dll_path)
+        .parent()
+        .ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "DLL path has no parent directory",
+            )
+        })?
+        .join("rules");
+    if dir.exists() {
+        return Ok(dir);
+    }
+
+    Err(std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        "No rules directory found",
+    ))
+}
+
+/// The plugin started by EuroScope, which calls into Rust only after `plugin_init`.
+fn plugin() -> &'static Plugin {
+    unsafe {
+        match &PLUGIN {
+            Some(plugin) => plugin,
+            None => unreachable!(),
+        }
+    }
+}
+
+pub fn check_flightplan(fp: crate::ffi::FlightPlan) -> Result<crate::ffi::Action, &'static str> {
+    check_flightplan_impl(plugin(), fp).map(crate::ffi::Action::from)
+}
+
+pub fn check_flightplan_impl(
+    plugin: &Plugin,
+    fp: crate::ffi::FlightPlan,
+) -> Result<config::Action, &'static str> {
+    plugin.checker.check(&fp)
+}
+
+pub fn explain_flightplan(fp: crate::ffi::FlightPlan) -> Vec<String> {
+    explain_flightplan_impl(plugin(), fp)
+}
+
+/// Chat lines for the "Check FP" function, see [`Checker::explain_lines`].
+pub fn explain_flightplan_impl(plugin: &Plugin, fp: crate::ffi::FlightPlan) -> Vec<String> {
+    plugin.checker.explain_lines(&fp)
+}
+
+#[export_name = "?EuroScopePlugInInit@@YAXPAPAVCPlugIn@EuroScopePlugIn@@@Z"]
+fn plugin_init(plugin_instance: *mut *mut ffi::CPlugIn) {
+    // Safeguard, that the EuroScope pointer can be dereferenced later.
+    assert!(!plugin_instance.is_null());
+
+    // Create a new C++ plugin instance on the heap as unique pointer.
+    let cpp_plugin = ffi::create_plugin();
+
+    // Turn the unique pointer into a raw pointer.
+    // This is required, because we have to set the EuroScope plugin pointer to the same instance.
+    // We are now responsible again to free the memory of cpp_plugin.
+    let cpp_plugin_ptr = cpp_plugin.into_raw();
+
+    // Recreate a unique pointer to handle the deallocation on drop.
+    // When drop is called, EsPlugin is freed and we are no longer responsible.
+    let cpp_plugin = unsafe { cxx::UniquePtr::from_raw(cpp_plugin_ptr) };
+
+    match Plugin::start(cpp_plugin) {
+        Ok(plugin) => unsafe {
+            // Initialize static variable with out plugin.
+            PLUGIN = Some(plugin);
+            // Set the EuroScope plugin pointer.
+            *plugin_instance = cpp_plugin_ptr as *mut ffi::CPlugIn;
+        },
+        Err(_) => unsafe {
+            // Set the EuroScope plugin pointer to null, as the plugin creation failed.
+            *plugin_instance = std::ptr::null_mut();
+        },
+    }
+}
+
+#[export_name = "?EuroScopePlugInExit@@YAXXZ"]
+fn plugin_exit() {
+    unsafe { PLUGIN = None };
+}
@@ -0,0 +1,490 @@
+//! ICAO item 15 route parser.
+
+use std::fmt::Display;
+
+use crate::ffi;
+
+/// Cruising speed of a speed/level group.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Speed {
+    /// `N0450` - knots
+    Knots(u32),
+    /// `K0830` - kilometers per hour
+    Kmh(u32),
+    /// `M082` - Mach number in hundredths
+    Mach(u32),
+}
+
+/// Cruising level of a speed/level group.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    /// `F350` - flight level in hundreds of feet
+    FlightLevel(u32),
+    /// `A045` - altitude in hundreds of feet
+    Altitude(u32),
+    /// `S1130` - standard metric level in tens of meters
+    MetricLevel(u32),
+    /// `M0610` - altitude in tens of meters
+    MetricAltitude(u32),
+    /// `VFR` - uncontrolled VFR flight
+    Vfr,
+}
+
+impl Level {
+    /// Level in feet, or `None` for VFR.
+    pub fn feet(&self) -> Option<u32> {
+        match *self {
+            Self::FlightLevel(l) | Self::Altitude(l) => Some(l * 100),
+            Self::MetricLevel(l) | Self::MetricAltitude(l) => {
+                Some((f64::from(l) * 10.0 * 3.28084).round() as u32)
+            }
+            Self::Vfr => None,
+        }
+    }
+}
+
+/// Speed/level group, e.g. `N0450F350`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpeedLevel {
+    pub speed: Speed,
+    pub level: Level,
+}
+
+impl SpeedLevel {
+    pub fn parse(s: &str) -> Option<Self> {
+        let digits = |s: &str, n: usize| -> Option<u32> {
+            (s.len() == n && s.chars().all(|c| c.is_ascii_digit()))
+                .then(|| s.parse().ok())
+                .flatten()
+        };
+        let (speed, rest) = match s.get(..1)? {
+            "N" => (Speed::Knots(digits(s.get(1..5)?, 4)?), &s[5..]),
+            "K" => (Speed::Kmh(digits(s.get(1..5)?, 4)?), &s[5..]),
+            "M" => (Speed::Mach(digits(s.get(1..4)?, 3)?), &s[4..]),
+            _ => return None,
+        };
+        let level = match rest.get(..1)? {
+            "F" => Level::FlightLevel(digits(&rest[1..], 3)?),
+            "A" => Level::Altitude(digits(&rest[1..], 3)?),
+            "S" => Level::MetricLevel(digits(&rest[1..], 4)?),
+            "M" => Level::MetricAltitude(digits(&rest[1..], 4)?),
+            _ if rest == "VFR" => Level::Vfr,
+            _ => return None,
+        };
+        Some(Self { speed, level })
+    }
+}
+
+impl Display for SpeedLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.speed {
+            Speed::Knots(s) => write!(f, "N{:04}", s)?,
+            Speed::Kmh(s) => write!(f, "K{:04}", s)?,
+            Speed::Mach(s) => write!(f, "M{:03}", s)?,
+        }
+        match self.level {
+            Level::FlightLevel(l) => write!(f, "F{:03}", l),
+            Level::Altitude(l) => write!(f, "A{:03}", l),
+            Level::MetricLevel(l) => write!(f, "S{:04}", l),
+            Level::MetricAltitude(l) => write!(f, "M{:04}", l),
+            Level::Vfr => write!(f, "VFR"),
+        }
+    }
+}
+
+/// Single element of a route.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Element {
+    /// `N0450F350`
+    SpeedLevel(SpeedLevel),
+    /// Significant point, optionally with a change of speed and level, e.g. `HAREM/N0440F360`.
+    Point {
+        name: String,
+        change: Option<SpeedLevel>,
+    },
+    /// ATS route, e.g. `T104`.
+    Airway(String),
+    /// SID or STAR, optionally with a runway, e.g. `TOBAK7M/25C`.
+    Procedure {
+        name: String,
+        runway: Option<String>,
+    },
+    /// `DCT`
+    Direct,
+    /// `IFR`
+    Ifr,
+    /// `VFR`
+    Vfr,
+    /// Anything that could not be classified.
+    Unknown(String),
+}
+
+impl Element {
+    /// Parse a single word. Words like `DF155` are parsed as airways, [`Route::parse`] only
+    /// keeps them as airways if they are between two points.
+    pub fn parse(word: &str) -> Self {
+        match word {
+            "DCT" => return Self::Direct,
+            "IFR" => return Self::Ifr,
+            "VFR" => return Self::Vfr,
+            _ => {}
+        }
+        if let Some(speed_level) = SpeedLevel::parse(word) {
+            return Self::SpeedLevel(speed_level);
+        }
+
+        let (name, suffix) = match word.split_once('/') {
+            Some((name, suffix)) => (name, Some(suffix)),
+            None => (word, None),
+        };
+        if is_airway(name) && suffix.is_none() {
+            Self::Airway(name.into())
+        } else if is_procedure(name) {
+            Self::Procedure {
+                name: name.into(),
+                runway: suffix.map(String::from),
+            }
+        } else if is_point(name) {
+            match suffix.map(SpeedLevel::parse) {
+                None => Self::Point {
+                    name: name.into(),
+                    change: None,
+                },
+                Some(Some(change)) => Self::Point {
+                    name: name.into(),
+                    change: Some(change),
+                },
+                Some(None) => Self::Unknown(word.into()),
+            }
+        } else {
+            Self::Unknown(word.into())
+        }
+    }
+}
+
+impl Display for Element {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::SpeedLevel(speed_level) => write!(f, "{}", speed_level),
+            Self::Point { name, change: None } => write!(f, "{}", name),
+            Self::Point {
+                name,
+                change: Some(change),
+            } => write!(f, "{}/{}", name, change),
+            Self::Airway(name) => write!(f, "{}", name),
+            Self::Procedure { name, runway: None } => write!(f, "{}", name),
+            Self::Procedure {
+                name,
+                runway: Some(runway),
+            } => write!(f, "{}/{}", name, runway),
+            Self::Direct => write!(f, "DCT"),
+            Self::Ifr => write!(f, "IFR"),
+            Self::Vfr => write!(f, "VFR"),
+            Self::Unknown(word) => write!(f, "{}", word),
+        }
+    }
+}
+
+/// `T104`, `UN850`, `Z74`
+fn is_airway(s: &str) -> bool {
+    let letters = s.chars().take_while(|c| c.is_ascii_uppercase()).count();
+    let digits = s[letters..]
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .count();
+    let suffix = s.len() - letters - digits;
+    (1..=2).contains(&letters)
+        && (1..=3).contains(&digits)
+        && suffix <= 1
+        && s[letters + digits..]
+            .chars()
+            .all(|c| c.is_ascii_uppercase())
+}
+
+/// `CINDY4S`, `MTR5C`
+fn is_procedure(s: &str) -> bool {
+    let letters = s.chars().take_while(|c| c.is_ascii_uppercase()).count();
+    let rest = &s[letters..];
+    (3..=5).contains(&letters)
+        && rest.len() == 2
+        && rest.starts_with(|c: char| c.is_ascii_digit())
+        && rest.ends_with(|c: char| c.is_ascii_uppercase())
+}
+
+/// `HAREM`, `FFM`, `EDDM`, `5030N00830E`, `FFM090020`
+fn is_point(s: &str) -> bool {
+    !s.is_empty() && s.len() <= 11 && s.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+/// Whether a point is given as coordinates, e.g. `50N008E` or `5030N00830E`.
+pub fn is_coordinate(name: &str) -> bool {
+    let is_half = |s: &str, deg: usize, hemispheres: [char; 2]| {
+        let digits = &s[..s.len().saturating_sub(1)];
+        (digits.len() == deg || digits.len() == deg + 2)
+            && digits.chars().all(|c| c.is_ascii_digit())
+            && s.ends_with(hemispheres)
+    };
+    match name.find(['N', 'S']) {
+        Some(i) => is_half(&name[..=i], 2, ['N', 'S']) && is_half(&name[i + 1..], 3, ['E', 'W']),
+        None => false,
+    }
+}
+
+/// Navaid of a point given as bearing and distance, e.g. `FFM` for `FFM090020`.
+pub fn bearing_distance_navaid(name: &str) -> Option<&str> {
+    let navaid = name.trim_end_matches(|c: char| c.is_ascii_digit());
+    let digits = name.len() - navaid.len();
+    (digits == 6 && (2..=5).contains(&navaid.len())).then_some(navaid)
+}
+
+/// A part of the route flown under a single flight rule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Portion {
+    /// Either [`ffi::FlightRule::Ifr`] or [`ffi::FlightRule::Vfr`].
+    pub rule: ffi::FlightRule,
+    pub elements: Vec<Element>,
+    /// Level in feet at the start of the portion, if known.
+    pub level: Option<u32>,
+}
+
+impl Portion {
+    pub fn text(&self) -> String {
+        let words: Vec<String> = self.elements.iter().map(|e| e.to_string()).collect();
+        words.join(" ")
+    }
+}
+
+/// Parsed ICAO item 15 route.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Route {
+    pub elements: Vec<Element>,
+}
+
+impl Route {
+    pub fn parse(route: &str) -> Self {
+        let mut elements: Vec<Element> = route.split_whitespace().map(Element::parse).collect();
+        // Fix names like `DF155` or `AB12` look like airways, which are only filed after a point,
+        // SID or the initial speed/level and before a point or STAR
+        for i in 0..elements.len() {
+            let Element::Airway(name) = &elements[i] else {
+                continue;
+            };
+            let after_point = matches!(
+                i.checked_sub(1).map(|j| &elements[j]),
+                Some(Element::Point { .. } | Element::Procedure { .. } | Element::SpeedLevel(_))
+            );
+            let before_point = matches!(
+                elements.get(i + 1),
+                Some(Element::Point { .. } | Element::Procedure { .. } | Element::Airway(_))
+            );
+            if !after_point || !before_point {
+                elements[i] = Element::Point {
+                    name: name.clone(),
+                    change: None,
+                };
+            }
+        }
+        Self { elements }
+    }
+
+    /// Split the route at its `IFR` and `VFR` elements.
+    ///
+    /// The first portion is flown under the initial flight rule of the flight plan (IFR for I and
+    /// Y, VFR for V and Z) at the requested level `rfl`. Each following portion starts at the
+    /// level of the last speed/level group before the switch, if there is one.
+    pub fn portions(&self, rule: &ffi::FlightRule, rfl: u32) -> Vec<Portion> {
+        let initial_rule = match *rule {
+            ffi::FlightRule::Vfr | ffi::FlightRule::Zulu => ffi::FlightRule::Vfr,
+            _ => ffi::FlightRule::Ifr,
+        };
+        let mut portions = vec![Portion {
+            rule: initial_rule,
+            elements: vec![],
+            level: Some(rfl),
+        }];
+        let mut last_level: Option<Level> = None;
+        for element in &self.elements {
+            let switch_to = match element {
+                Element::Ifr => Some(ffi::FlightRule::Ifr),
+                Element::Vfr => Some(ffi::FlightRule::Vfr),
+                _ => None,
+            };
+            let current = portions.last_mut().expect("There is always a portion");
+            match switch_to {
+                Some(rule) if rule != current.rule => {
+                    portions.push(Portion {
+                        rule,
+                        elements: vec![],
+                        level: last_level.take().and_then(|l| l.feet()),
+                    });
+                }
+                Some(_) => {}
+                None => {
+                    match element {
+                        Element::SpeedLevel(speed_level)
+                        | Element::Point {
+                            change: Some(speed_level),
+                            ..
+                        } => last_level = Some(speed_level.level),
+                        _ => {}
+                    }
+                    current.elements.push(element.clone());
+                }
+            }
+        }
+        portions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn speed_level() {
+        assert_eq!(
+            Some(SpeedLevel {
+                speed: Speed::Knots(450),
+                level: Level::FlightLevel(350)
+            }),
+            SpeedLevel::parse("N0450F350")
+        );
+        assert_eq!(
+            Some(SpeedLevel {
+                speed: Speed::Mach(82),
+                level: Level::MetricLevel(1130)
+            }),
+            SpeedLevel::parse("M082S1130")
+        );
+        assert_eq!(
+            Some(SpeedLevel {
+                speed: Speed::Kmh(180),
+                level: Level::Vfr
+            }),
+            SpeedLevel::parse("K0180VFR")
+        );
+        assert_eq!(None, SpeedLevel::parse("N450F350"));
+        assert_eq!(None, SpeedLevel::parse("NOSEX"));
+        assert_eq!(
+            Some(5000),
+            SpeedLevel::parse("N0120A050").unwrap().level.feet()
+        );
+    }
+
+    #[test]
+    fn elements() {
+        let route = Route::parse("N0450F350 TOBAK7M/25C TOBAK N858 NOSEX/N0440F360 DCT KLF");
+        assert_eq!(
+            vec![
+                Element::SpeedLevel(SpeedLevel::parse("N0450F350").unwrap()),
+                Element::Procedure {
+                    name: "TOBAK7M".into(),
+                    runway: Some("25C".into())
+                },
+                Element::Point {
+                    name: "TOBAK".into(),
+                    change: None
+                },
+                Element::Airway("N858".into()),
+                Element::Point {
+                    name: "NOSEX".into(),
+                    change: SpeedLevel::parse("N0440F360")
+                },
+                Element::Direct,
+                Element::Point {
+                    name: "KLF".into(),
+                    change: None
+                },
+            ],
+            route.elements
+        );
+    }
+
+    #[test]
+    fn airway_position() {
+        let airways = |route: &str| -> Vec<String> {
+            Route::parse(route)
+                .elements
+                .iter()
+                .filter_map(|e| match e {
+                    Element::Airway(name) => Some(name.clone()),
+                    _ => None,
+                })
+                .collect()
+        };
+        assert_eq!(
+            vec!["Z74"],
+            airways("N0450F350 DCT DF155 DCT AB12 Z74 CINDY")
+        );
+        assert_eq!(vec!["Z74", "T104"], airways("CINDY Z74 DF155 T104 ROKIL"));
+        assert_eq!(vec!["T104"], airways("HAREM T104 AB12"));
+        assert_eq!(vec!["UN850"], airways("N0450F350 UN850 HAREM"));
+        assert_eq!(
+            Element::Point {
+                name: "DF155".into(),
+                change: None
+            },
+            Route::parse("DCT DF155").elements[1]
+        );
+    }
+
+    #[test]
+    fn display() {
+        let route = "N0450F350 TOBAK7M/25C TOBAK N858 NOSEX/N0440F360 DCT KLF VFR";
+        let words: Vec<String> = Route::parse(route)
+            .elements
+            .iter()
+            .map(|e| e.to_string())
+            .collect();
+        assert_eq!(route, words.join(" "));
+    }
+
+    #[test]
+    fn coordinates() {
+        assert!(is_coordinate("50N008E"));
+        assert!(is_coordinate("5030N00830E"));
+        assert!(is_coordinate("4530S17000W"));
+        assert!(!is_coordinate("NOSEX"));
+        assert!(!is_coordinate("50N08E"));
+        assert!(!is_coordinate("50N"));
+        assert_eq!(Some("FFM"), bearing_distance_navaid("FFM090020"));
+        assert_eq!(None, bearing_distance_navaid("FFM"));
+        assert_eq!(None, bearing_distance_navaid("5030N00830E"));
+    }
+
+    #[test]
+    fn ifr_only() {
+        let route = Route::parse("CINDY Z74 HAREM T104 ROKIL");
+        let portions = route.portions(&ffi::FlightRule::Ifr, 23000);
+        assert_eq!(1, portions.len());
+        assert_eq!(ffi::FlightRule::Ifr, portions[0].rule);
+        assert_eq!(Some(23000), portions[0].level);
+        assert_eq!("CINDY Z74 HAREM T104 ROKIL", portions[0].text());
+    }
+
+    #[test]
+    fn yankee() {
+        let route = Route::parse("CINDY Z74 HAREM/N0120A045 VFR DCT ROKIL");
+        let portions = route.portions(&ffi::FlightRule::Yankee, 23000);
+        assert_eq!(2, portions.len());
+        assert_eq!(ffi::FlightRule::Ifr, portions[0].rule);
+        assert_eq!("CINDY Z74 HAREM/N0120A045", portions[0].text());
+        assert_eq!(Some(23000), portions[0].level);
+        assert_eq!(ffi::FlightRule::Vfr, portions[1].rule);
+        assert_eq!("DCT ROKIL", portions[1].text());
+        assert_eq!(Some(4500), portions[1].level);
+    }
+
+    #[test]
+    fn zulu() {
+        let route = Route::parse("DCT HAREM IFR T104 ROKIL");
+        let portions = route.portions(&ffi::FlightRule::Zulu, 4500);
+        assert_eq!(2, portions.len());
+        assert_eq!(ffi::FlightRule::Vfr, portions[0].rule);
+        assert_eq!("DCT HAREM", portions[0].text());
+        assert_eq!(Some(4500), portions[0].level);
+        assert_eq!(ffi::FlightRule::Ifr, portions[1].rule);
+        assert_eq!("T104 ROKIL", portions[1].text());
+        assert_eq!(None, portions[1].level);
+    }
+}
@@ -0,0 +1,89 @@
+//! Runs the test cases of rule files, which give flight plans with the finding expected for them.
+
+use std::fmt::Display;
+use std::path::Path;
+
+use crate::config::{ActionType, Expected, Test};
+use crate::Checker;
+
+pub struct TestResult<'a> {
+    pub file: &'a Path,
+    /// 1-based number of the test in its file.
+    pub number: usize,
+    pub test: &'a Test,
+    /// First finding, or the error of a rule which could not be evaluated.
+    pub actual: Result<Expected, &'static str>,
+}
+
+impl TestResult<'_> {
+    pub fn passed(&self) -> bool {
+        let expected = &self.test.expect;
+        match &self.actual {
+            Ok(actual) => {
+                actual.typ == expected.typ
+                    && actual.msg == expected.msg
+                    && (expected.name.is_none() || actual.name == expected.name)
+            }
+            Err(_) => false,
+        }
+    }
+}
+
+fn fmt_finding(finding: &Expected) -> String {
+    match &finding.name {
+        Some(name) => format!("{} {} ({})", finding.typ, finding.msg, name),
+        None => format!("{} {}", finding.typ, finding.msg),
+    }
+}
+
+/// ```text
+/// rules/eddf.json: test 2 (CINDY via T104 to EDDS) failed
+///   - error DST (CINDY destination)
+///   + success OK (EDDF valid)
+/// ```
+impl Display for TestResult<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: test {}", self.file.display(), self.number)?;
+        if let Some(name) = &self.test.name {
+            write!(f, " ({})", name)?;
+        }
+        if self.passed() {
+            return write!(f, " passed");
+        }
+        writeln!(f, " failed")?;
+        writeln!(f, "  - {}", fmt_finding(&self.test.expect))?;
+        match &self.actual {
+            Ok(actual) => write!(f, "  + {}", fmt_finding(actual)),
+            Err(e) => write!(f, "  + failed: {}", e),
+        }
+    }
+}
+
+/// Runs the tests of all rule files against all rules, in the order of the files.
+pub fn run(checker: &Checker) -> Vec<TestResult<'_>> {
+    let mut results = vec![];
+    for (config, file) in checker.configs().iter().zip(checker.files()) {
+        for (i, test) in config.tests.iter().enumerate() {
+            let actual = match checker.findings(&test.fp).into_iter().next() {
+                Some(finding) => finding.action.map(|action| Expected {
+                    typ: action.typ,
+                    msg: action.msg,
+                    name: finding.name,
+                }),
+                // Nothing matched, see `check_configs`
+                None => Ok(Expected {
+                    typ: ActionType::Warning,
+                    msg: "UNK".into(),
+                    name: None,
+                }),
+            };
+            results.push(TestResult {
+                file,
+                number: i + 1,
+                test,
+                actual,
+            });
+        }
+    }
+    results
+}
@@ -0,0 +1,180 @@
+//! JSON form of flight plans, which is shared by test fixtures, CLI input and recorded traffic.
+//!
+//! Enums are (de)serialized by the letter codes of their `Display` and `FromStr` impls, e.g.
+//! `"J"` for [`ffi::EngineType::Jet`]. Omitted fields are empty, zero or unknown, the flight
+//! rule defaults to IFR.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::ffi;
+
+macro_rules! letter_code {
+    ($($typ:ty),*) => {
+        $(
+            impl Serialize for $typ {
+                fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                    serializer.collect_str(self)
+                }
+            }
+
+            impl<'de> Deserialize<'de> for $typ {
+                fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                    let s = String::deserialize(deserializer)?;
+                    s.parse().map_err(serde::de::Error::custom)
+                }
+            }
+        )*
+    };
+}
+
+letter_code!(
+    ffi::AircraftType,
+    ffi::WakeTurbulenceCategory,
+    ffi::FaaEquipmentCode,
+    ffi::EngineType,
+    ffi::FlightRule
+);
+
+fn unknown_aircraft_type() -> ffi::AircraftType {
+    ffi::AircraftType::Unknown
+}
+
+fn unknown_wtc() -> ffi::WakeTurbulenceCategory {
+    ffi::WakeTurbulenceCategory::Unknown
+}
+
+fn unknown_faa_equip_code() -> ffi::FaaEquipmentCode {
+    ffi::FaaEquipmentCode::Unknown
+}
+
+fn unknown_eng_typ() -> ffi::EngineType {
+    ffi::EngineType::Unknown
+}
+
+fn ifr() -> ffi::FlightRule {
+    ffi::FlightRule::Ifr
+}
+
+fn unknown_aircraft() -> ffi::Aircraft {
+    ffi::Aircraft {
+        typ: unknown_aircraft_type(),
+        icao: String::new(),
+        wtc: unknown_wtc(),
+        faa_equip_code: unknown_faa_equip_code(),
+        equip: String::new(),
+        eng_typ: unknown_eng_typ(),
+        eng_count: 0,
+        is_rvsm_capable: false,
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "ffi::Aircraft", deny_unknown_fields)]
+struct Aircraft {
+    #[serde(default = "unknown_aircraft_type")]
+    typ: ffi::AircraftType,
+    #[serde(default)]
+    icao: String,
+    #[serde(default = "unknown_wtc")]
+    wtc: ffi::WakeTurbulenceCategory,
+    #[serde(default = "unknown_faa_equip_code")]
+    faa_equip_code: ffi::FaaEquipmentCode,
+    #[serde(default)]
+    equip: String,
+    #[serde(default = "unknown_eng_typ")]
+    eng_typ: ffi::EngineType,
+    #[serde(default)]
+    eng_count: u8,
+    #[serde(default)]
+    is_rvsm_capable: bool,
+}
+
+impl Serialize for ffi::Aircraft {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Aircraft::serialize(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ffi::Aircraft {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Aircraft::deserialize(deserializer)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "ffi::FlightPlan", deny_unknown_fields)]
+struct FlightPlan {
+    #[serde(default = "unknown_aircraft")]
+    ac: ffi::Aircraft,
+    #[serde(default = "ifr")]
+    rule: ffi::FlightRule,
+    #[serde(default)]
+    cfl: u32,
+    #[serde(default)]
+    rfl: u32,
+    #[serde(default)]
+    dep: String,
+    #[serde(default)]
+    dep_rwy: String,
+    #[serde(default)]
+    arr: String,
+    #[serde(default)]
+    sid: String,
+    #[serde(default)]
+    route: String,
+    #[serde(default)]
+    remarks: String,
+}
+
+impl Serialize for ffi::FlightPlan {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        FlightPlan::serialize(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ffi::FlightPlan {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        FlightPlan::deserialize(deserializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let fp = ffi::FlightPlan::default();
+        let json = serde_json::to_value(&fp).unwrap();
+        assert_eq!("L", json["ac"]["typ"]);
+        assert_eq!("M", json["ac"]["wtc"]);
+        assert_eq!("Q", json["ac"]["faa_equip_code"]);
+        assert_eq!("J", json["ac"]["eng_typ"]);
+        assert_eq!("I", json["rule"]);
+        assert_eq!(35000, json["rfl"]);
+
+        let parsed: ffi::FlightPlan = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(json, serde_json::to_value(parsed).unwrap());
+    }
+
+    #[test]
+    fn defaults() {
+        let fp: ffi::FlightPlan =
+            serde_json::from_str(r#"{ "dep": "EDDF", "ac": { "eng_typ": "T" } }"#).unwrap();
+        assert_eq!("EDDF", fp.dep);
+        assert_eq!(ffi::FlightRule::Ifr, fp.rule);
+        assert_eq!(0, fp.rfl);
+        assert_eq!(ffi::EngineType::Turboprop, fp.ac.eng_typ);
+        assert_eq!(ffi::AircraftType::Unknown, fp.ac.typ);
+        assert_eq!(ffi::WakeTurbulenceCategory::Unknown, fp.ac.wtc);
+    }
+
+    #[test]
+    fn errors() {
+        let invalid = |json| serde_json::from_str::<ffi::FlightPlan>(json).is_err();
+        assert!(invalid(r#"{ "rule": "X" }"#));
+        assert!(invalid(r#"{ "ac": { "wtc": "Medium" } }"#));
+        assert!(invalid(r#"{ "departure": "EDDF" }"#));
+        assert!(invalid(r#"{ "ac": { "engines": 2 } }"#));
+    }
+}
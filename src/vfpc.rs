@@ -0,0 +1,342 @@
+//! Import of departure restrictions in the `Sid.json` format of the VFPC plugin.
+//!
+//! The file is an array of airports, each with the SIDs by their first waypoint. A flight is valid
+//! if it satisfies any of the constraints of its SID:
+//!
+//! ```json
+//! [{
+//!     "icao": "EDDF",
+//!     "sids": [{
+//!         "point": "CINDY",
+//!         "constraints": [
+//!             { "dests": ["EDDM", "EDDN"], "airways": ["T104"], "direction": "ODD" },
+//!             { "dests": ["ED"], "nodests": ["EDDM"], "engine": ["P", "T"], "max_fl": 230 }
+//!         ]
+//!     }]
+//! }]
+//! ```
+//!
+//! Destinations are ICAO prefixes. Fields of constraints which are not supported are ignored,
+//! which makes the constraint less strict, and reported by [`unsupported_fields`].
+//!
+//! Every airport is converted to rules, which report the first violated aspect of the closest
+//! constraint: `SID` for unknown SID waypoints, then `DST`, `RTE`, `ENG` and `RFL`. Valid flights
+//! match no rule, so that rule files checked later decide their action, e.g. a fallback rule with
+//! the condition `true`.
+
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+
+use crate::config::{Action, ActionType, Checks, Config, Rule};
+use crate::lang::ast::{BinOp, Expr, Lit, UnOp};
+
+#[derive(Deserialize)]
+struct Airport {
+    icao: String,
+    sids: Vec<Sid>,
+}
+
+#[derive(Deserialize)]
+struct Sid {
+    point: String,
+    constraints: Vec<Constraint>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct Constraint {
+    /// Destination prefixes, any of which has to match.
+    dests: Vec<String>,
+    /// Destination prefixes, none of which may match.
+    nodests: Vec<String>,
+    /// Airways, any of which has to be in the route.
+    airways: Vec<String>,
+    /// Airways, none of which may be in the route.
+    noairways: Vec<String>,
+    /// Engine types, e.g. `J`.
+    engine: Vec<String>,
+    min_fl: Option<i64>,
+    max_fl: Option<i64>,
+    direction: Option<Direction>,
+    /// Fields which are not supported.
+    #[serde(flatten)]
+    unsupported: BTreeMap<String, serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+enum Direction {
+    /// Westbound levels.
+    Even,
+    /// Eastbound levels.
+    Odd,
+}
+
+/// Whether a JSON file is a `Sid.json` file, which is an array of airports, not a rule file.
+pub fn is_sid_json(input: &str) -> bool {
+    input.trim_start().starts_with('[')
+}
+
+pub fn parse(input: &str) -> Result<Config, serde_json::Error> {
+    let airports: Vec<Airport> = serde_json::from_str(input)?;
+    Ok(Config {
+        checks: Checks::default(),
+        tables: Default::default(),
+        priority: 0,
+        tests: vec![],
+        rules: airports.iter().flat_map(airport_rules).collect(),
+    })
+}
+
+/// Fields of constraints which are ignored, as they are not supported, with the airport and SID
+/// waypoint, e.g. `("EDDF CINDY", "navigation")`.
+pub fn unsupported_fields(input: &str) -> Result<Vec<(String, String)>, serde_json::Error> {
+    let airports: Vec<Airport> = serde_json::from_str(input)?;
+    let mut fields = vec![];
+    for airport in &airports {
+        for sid in &airport.sids {
+            for constraint in &sid.constraints {
+                for field in constraint.unsupported.keys() {
+                    let field = (format!("{} {}", airport.icao, sid.point), field.clone());
+                    if !fields.contains(&field) {
+                        fields.push(field);
+                    }
+                }
+            }
+        }
+    }
+    Ok(fields)
+}
+
+/// Convert a `Sid.json` file into a JSON rule file.
+pub fn convert(input: &str) -> Result<String, serde_json::Error> {
+    let config = parse(input)?;
+    let rules: Vec<serde_json::Value> = config
+        .rules
+        .iter()
+        .map(|rule| {
+            serde_json::json!({
+                "name": rule.name,
+                "condition": rule.condition.to_string(),
+                "action": rule.action,
+            })
+        })
+        .collect();
+    serde_json::to_string_pretty(&serde_json::json!({ "rules": rules }))
+}
+
+fn ident(name: &str) -> Box<Expr> {
+    Box::new(Expr::Ident(name.into()))
+}
+
+fn text(s: &str) -> Box<Expr> {
+    Box::new(Expr::Lit(Lit::Text(s.into())))
+}
+
+fn int(i: i64) -> Box<Expr> {
+    Box::new(Expr::Lit(Lit::Int(i)))
+}
+
+fn not(expr: Expr) -> Expr {
+    Expr::Unary(UnOp::Not, Box::new(expr))
+}
+
+fn all(exprs: impl IntoIterator<Item = Expr>) -> Expr {
+    exprs
+        .into_iter()
+        .reduce(|lhs, rhs| Expr::Binary(BinOp::And, Box::new(lhs), Box::new(rhs)))
+        .unwrap_or(Expr::Lit(Lit::Bool(true)))
+}
+
+fn any(exprs: impl IntoIterator<Item = Expr>) -> Expr {
+    exprs
+        .into_iter()
+        .reduce(|lhs, rhs| Expr::Binary(BinOp::Or, Box::new(lhs), Box::new(rhs)))
+        .unwrap_or(Expr::Lit(Lit::Bool(false)))
+}
+
+fn dest_prefix(prefix: &str) -> Expr {
+    Expr::Call(
+        ident("matches"),
+        vec![*ident("arr"), Expr::Lit(Lit::Text(format!("{}*", prefix)))],
+    )
+}
+
+fn has_airway(airway: &str) -> Expr {
+    Expr::Binary(BinOp::Has, ident("airways"), text(airway))
+}
+
+impl Constraint {
+    /// Predicates of the constraint, grouped by the message reported if they fail.
+    fn predicates(&self) -> [(&'static str, Vec<Expr>); 4] {
+        let mut dst = vec![];
+        if !self.dests.is_empty() {
+            dst.push(any(self.dests.iter().map(|d| dest_prefix(d))));
+        }
+        if !self.nodests.is_empty() {
+            dst.push(not(any(self.nodests.iter().map(|d| dest_prefix(d)))));
+        }
+
+        let mut rte = vec![];
+        if !self.airways.is_empty() {
+            rte.push(any(self.airways.iter().map(|a| has_airway(a))));
+        }
+        if !self.noairways.is_empty() {
+            rte.push(not(any(self.noairways.iter().map(|a| has_airway(a)))));
+        }
+
+        let mut eng = vec![];
+        if !self.engine.is_empty() {
+            let engines = self.engine.iter().map(|e| *text(e)).collect();
+            eng.push(Expr::Binary(
+                BinOp::In,
+                ident("ac_eng_type"),
+                Box::new(Expr::Array(engines)),
+            ));
+        }
+
+        let mut rfl = vec![];
+        if let Some(min) = self.min_fl {
+            rfl.push(Expr::Binary(BinOp::Ge, ident("rfl"), int(min * 100)));
+        }
+        if let Some(max) = self.max_fl {
+            rfl.push(Expr::Binary(BinOp::Le, ident("rfl"), int(max * 100)));
+        }
+        if let Some(direction) = &self.direction {
+            let direction = match direction {
+                Direction::Even => "W",
+                Direction::Odd => "E",
+            };
+            rfl.push(Expr::Call(
+                ident("valid_cruise_level"),
+                vec![*text(direction)],
+            ));
+        }
+
+        [("DST", dst), ("RTE", rte), ("ENG", eng), ("RFL", rfl)]
+    }
+}
+
+fn rule(name: String, condition: Expr, msg: &str) -> Rule {
+    Rule {
+        name: Some(name),
+        condition,
+        action: Action::new(ActionType::Error, msg.into()),
+        priority: 0,
+    }
+}
+
+fn airport_rules(airport: &Airport) -> Vec<Rule> {
+    let dep = || Expr::Binary(BinOp::Eq, ident("dep"), text(&airport.icao));
+    let points = airport.sids.iter().map(|sid| *text(&sid.point)).collect();
+    let mut rules = vec![rule(
+        format!("{} SID waypoint", airport.icao),
+        all([
+            dep(),
+            not(Expr::Binary(
+                BinOp::In,
+                ident("sidwpt"),
+                Box::new(Expr::Array(points)),
+            )),
+        ]),
+        "SID",
+    )];
+
+    for sid in &airport.sids {
+        let base = || {
+            all([
+                dep(),
+                Expr::Binary(BinOp::Eq, ident("sidwpt"), text(&sid.point)),
+            ])
+        };
+        // A flight fails an aspect, if no constraint is satisfied up to and including it
+        let predicates: Vec<_> = sid.constraints.iter().map(|c| c.predicates()).collect();
+        for (aspect, (msg, _)) in Constraint::default().predicates().iter().enumerate() {
+            let name = match *msg {
+                "DST" => "destination",
+                "RTE" => "route",
+                "ENG" => "engine type",
+                _ => "route flight level",
+            };
+            let satisfied = predicates.iter().map(|groups| {
+                all(groups[..=aspect]
+                    .iter()
+                    .flat_map(|(_, exprs)| exprs.iter().cloned()))
+            });
+            rules.push(rule(
+                format!("{} {} {}", airport.icao, sid.point, name),
+                all([base(), not(any(satisfied))]),
+                msg,
+            ));
+        }
+    }
+    rules
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lang::parser;
+
+    #[test]
+    fn rules() {
+        let config = parse(
+            r#"[{ "icao": "EDDF", "sids": [{ "point": "CINDY", "constraints": [
+                { "dests": ["EDDM"], "airways": ["T104"], "direction": "ODD" },
+                { "nodests": ["ED", "ET"], "engine": ["J"], "max_fl": 245 }
+            ] }] }]"#,
+        )
+        .unwrap();
+        let conditions: Vec<String> = config
+            .rules
+            .iter()
+            .map(|r| format!("{} {}", r.action.msg, r.condition))
+            .collect();
+        let base = "dep == 'EDDF' and sidwpt == 'CINDY'";
+        let dst1 = "matches(arr, 'EDDM*')";
+        let dst2 = "!(matches(arr, 'ED*') or matches(arr, 'ET*'))";
+        assert_eq!(
+            vec![
+                "SID dep == 'EDDF' and !(sidwpt in ['CINDY'])".to_string(),
+                format!("DST {base} and !({dst1} or {dst2})"),
+                format!("RTE {base} and !({dst1} and airways has 'T104' or {dst2})"),
+                format!("ENG {base} and !({dst1} and airways has 'T104' or {dst2} and ac_eng_type in ['J'])"),
+                format!("RFL {base} and !({dst1} and airways has 'T104' and valid_cruise_level('E') or {dst2} and ac_eng_type in ['J'] and rfl <= 24500)"),
+            ],
+            conditions
+        );
+        for rule in &config.rules {
+            assert_eq!(
+                Ok(rule.condition.clone()),
+                parser::parse(rule.condition.to_string())
+            );
+        }
+        assert_eq!(Some("EDDF CINDY engine type".into()), config.rules[3].name);
+    }
+
+    #[test]
+    fn unsupported_field() {
+        let input = r#"[{ "icao": "EDDF", "sids": [{ "point": "CINDY", "constraints": [
+            { "dests": ["EDDM"], "navigation": "RNAV" },
+            { "dests": ["EDDN"], "navigation": "RNAV" }
+        ] }] }]"#;
+        assert_eq!(5, parse(input).unwrap().rules.len());
+        assert_eq!(
+            vec![("EDDF CINDY".to_string(), "navigation".to_string())],
+            unsupported_fields(input).unwrap()
+        );
+    }
+
+    #[test]
+    fn convert_to_rule_file() {
+        let json = convert(r#"[{ "icao": "EDDF", "sids": [] }]"#).unwrap();
+        let config: Config = serde_json::from_str(&json).unwrap();
+        assert_eq!(1, config.rules.len());
+        assert_eq!(
+            Action::new(ActionType::Error, "SID".into()),
+            config.rules[0].action
+        );
+        assert_eq!(Some("EDDF SID waypoint".into()), config.rules[0].name);
+    }
+}
@@ -0,0 +1,23 @@
+use std::path::Path;
+
+use esfpc::aircraft::AircraftDatabase;
+
+#[test]
+fn load_aircraft_files() {
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let mut db = AircraftDatabase::default();
+    db.load(&fixtures.join("aircraft.csv")).unwrap();
+    db.load(&fixtures.join("aircraft.json")).unwrap();
+
+    assert_eq!(25000, db.get("AT76").unwrap().ceiling);
+    assert_eq!("B", db.get("B744").unwrap().recat);
+    assert_eq!(14000, db.get("C172").unwrap().ceiling);
+    assert_eq!("A", db.get("C172").unwrap().category);
+    assert!(db.get("icao").is_none());
+}
+
+#[test]
+fn unknown_file_type() {
+    let mut db = AircraftDatabase::default();
+    assert!(db.load(Path::new("tests/fixtures/eddf.sct")).is_err());
+}
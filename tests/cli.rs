@@ -0,0 +1,314 @@
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+fn esfpc(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_esfpc"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+#[test]
+fn check_fpl_messages() {
+    let output = esfpc(&["check", "tests/fixtures/cli/eddf.fpl"]);
+    let stdout = stdout(&output);
+    assert!(stdout.contains("DLH123 EDDF-EDDS: success OK"), "{stdout}");
+    assert!(stdout.contains("DLH456 EDDF-EDDS: error RFL"), "{stdout}");
+    assert!(stdout.contains("Route flight level direction"), "{stdout}");
+    assert_eq!(Some(2), output.status.code());
+}
+
+#[test]
+fn check_json() {
+    let output = esfpc(&["check", "tests/fixtures/cli/eddf.json"]);
+    assert!(stdout(&output).contains("DLH123 EDDF-EDDS: success OK"));
+    assert_eq!(Some(0), output.status.code());
+
+    // Options replace the fields of the flight plans read
+    let output = esfpc(&["check", "tests/fixtures/cli/eddf.json", "--arr", "EDDM"]);
+    assert!(stdout(&output).contains("DLH123 EDDF-EDDM: error DST"));
+    assert_eq!(Some(2), output.status.code());
+}
+
+#[test]
+fn check_flags() {
+    let output = esfpc(&[
+        "check",
+        "--callsign=TEST",
+        "--dep",
+        "EDDF",
+        "--arr",
+        "EDDS",
+        "--sid",
+        "ANEKI1L",
+        "--rfl",
+        "FL350",
+        "--ac-is-rvsm-capable",
+        "true",
+    ]);
+    assert!(stdout(&output).contains("TEST EDDF-EDDS: success OK"));
+    assert_eq!(Some(0), output.status.code());
+
+    let output = esfpc(&["check", "--dep", "LOWW"]);
+    assert!(stdout(&output).contains("LOWW-: warning UNK"));
+    assert_eq!(Some(1), output.status.code());
+}
+
+#[test]
+fn check_explain() {
+    let output = esfpc(&[
+        "check",
+        "--dep=EDDF",
+        "--arr=EDDS",
+        "--sid=ANEKI1L",
+        "--rfl=FL340",
+        "--ac-is-rvsm-capable=true",
+        "--explain=true",
+    ]);
+    let stdout = stdout(&output);
+    assert!(
+        stdout.contains("  Route flight level direction (error RFL):\n"),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains("          sid_direction[sidwpt] -> 'E'"),
+        "{stdout}"
+    );
+    assert_eq!(Some(2), output.status.code());
+}
+
+#[test]
+fn usage_errors() {
+    assert_eq!(Some(4), esfpc(&[]).status.code());
+    assert_eq!(Some(4), esfpc(&["check", "--unknown", "x"]).status.code());
+    assert_eq!(Some(4), esfpc(&["check", "--rfl", "high"]).status.code());
+    assert_eq!(Some(4), esfpc(&["check", "missing.json"]).status.code());
+    assert_eq!(
+        Some(4),
+        esfpc(&["check", "--rules", "tests/fixtures/missing"])
+            .status
+            .code()
+    );
+}
+
+#[test]
+fn lint() {
+    let output = esfpc(&["lint"]);
+    assert_eq!("", stdout(&output));
+    assert_eq!(Some(0), output.status.code());
+
+    let output = esfpc(&["lint", "tests/fixtures/cli/lint"]);
+    let stdout = stdout(&output);
+    assert!(
+        stdout.contains("rule 1 (Destination): Unknown identifier destination"),
+        "{stdout}"
+    );
+    assert!(stdout.contains("[duplicate-name]"), "{stdout}");
+    assert!(stdout.contains("[long-message]"), "{stdout}");
+    assert!(
+        stdout.contains("max_rfl: Table is not used by any rule [unused-table]"),
+        "{stdout}"
+    );
+    assert_eq!(Some(2), output.status.code());
+
+    let output = esfpc(&["lint", "--format", "json", "tests/fixtures/cli/lint"]);
+    let diagnostics: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(4, diagnostics.as_array().unwrap().len());
+    assert_eq!("unused-table", diagnostics[0]["code"]);
+    assert_eq!(1, diagnostics[1]["rule"]);
+    assert_eq!("error", diagnostics[1]["severity"]);
+
+    assert_eq!(Some(4), esfpc(&["lint", "--format", "xml"]).status.code());
+}
+
+#[test]
+fn test() {
+    let output = esfpc(&["test"]);
+    assert!(stdout(&output).contains("32 passed, 0 failed"));
+    assert_eq!(Some(0), output.status.code());
+
+    let output = esfpc(&["test", "tests/fixtures/cli/test", "--verbose=true"]);
+    let stdout = stdout(&output);
+    assert!(
+        stdout.contains("eddf.yaml: test 1 (EDDS) passed"),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains(
+            "eddf.yaml: test 2 (EDDM) failed\n  - success OK\n  + error DST (Destination)\n"
+        ),
+        "{stdout}"
+    );
+    assert!(stdout.contains("1 passed, 1 failed"), "{stdout}");
+    assert_eq!(Some(1), output.status.code());
+}
+
+#[test]
+fn coverage() {
+    // Without flight plans, only the conditions are compared
+    let output = esfpc(&["coverage", "--format", "json"]);
+    let coverage: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(0, coverage["flight_plans"]);
+    assert_eq!(10, coverage["rules"].as_array().unwrap().len());
+    let diagnostics = coverage["diagnostics"].as_array().unwrap();
+    assert_eq!(1, diagnostics.len());
+    assert_eq!("overlap", diagnostics[0]["code"]);
+    assert_eq!(9, diagnostics[0]["rule"]);
+    assert_eq!(Some(1), output.status.code());
+
+    let output = esfpc(&["coverage", "tests/fixtures/cli/eddf.fpl"]);
+    let stdout = stdout(&output);
+    assert!(stdout.starts_with("2 flight plans\n"), "{stdout}");
+    assert!(
+        stdout
+            .contains("        1       1  rules/eddf.json: rule 1 (Route flight level direction)"),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains(
+            "rule 9 (Non-RNAV SIDs equipment code): Some flight plans match it and rule 8 \
+             (Non-RNAV SIDs max route flight level) in rules/eddf.json, which has a different \
+             action [overlap]"
+        ),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains(
+            "rule 2 (ANEKI destination): Matches none of the 2 flight plans [never-fires]"
+        ),
+        "{stdout}"
+    );
+    assert_eq!(Some(1), output.status.code());
+}
+
+#[test]
+fn datafeed() {
+    let output = esfpc(&["check", "tests/fixtures/cli/datafeed.json"]);
+    let checked = stdout(&output);
+    assert!(
+        checked.contains("DLH456 EDDF-EDDS: error RFL\n"),
+        "{checked}"
+    );
+    assert_eq!(Some(2), output.status.code());
+
+    let output = esfpc(&["datafeed", "tests/fixtures/cli/datafeed.json"]);
+    let stdout = stdout(&output);
+    assert!(stdout.contains("3 flight plans"), "{stdout}");
+    assert!(
+        stdout.contains("1  error RFL (Route flight level direction)"),
+        "{stdout}"
+    );
+    assert!(stdout.contains("1  warning UNK"), "{stdout}");
+    assert_eq!(Some(0), output.status.code());
+
+    let output = esfpc(&[
+        "datafeed",
+        "--format=json",
+        "tests/fixtures/cli/datafeed.json",
+    ]);
+    let summary: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(1, summary["airports"]["EDDF"]["success"]);
+    assert_eq!(1, summary["airports"]["EDDF"]["error"]);
+    assert_eq!(1, summary["airports"]["LOWW"]["warning"]);
+
+    assert_eq!(Some(4), esfpc(&["datafeed"]).status.code());
+}
+
+#[test]
+fn diff() {
+    let output = esfpc(&["diff", "rules", "rules", "tests/fixtures/cli/eddf.fpl"]);
+    assert!(stdout(&output).contains("0 of 2 flight plans changed"));
+    assert_eq!(Some(0), output.status.code());
+
+    let output = esfpc(&[
+        "diff",
+        "rules",
+        "tests/fixtures/cli/diff",
+        "tests/fixtures/cli/eddf.fpl",
+    ]);
+    let stdout = stdout(&output);
+    assert!(
+        stdout.contains("DLH123 EDDF-EDDS: success OK -> error DST\n"),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains("DLH456 EDDF-EDDS: error RFL -> error DST\n"),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains("  - error    RFL    Route flight level direction\n"),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains("  + error    DST    EDDS closed\n"),
+        "{stdout}"
+    );
+    assert!(stdout.contains("2 of 2 flight plans changed"), "{stdout}");
+    assert_eq!(Some(1), output.status.code());
+
+    assert_eq!(Some(4), esfpc(&["diff", "rules", "rules"]).status.code());
+}
+
+#[test]
+fn repl() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_esfpc"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .args(["repl", "tests/fixtures/fp/eddf_cindy.json"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let input = "\
+sidwpt
+sid_direction[sidwpt]
+rfl > 24000 and unknown == 1
+dep == 'EDDF' and
+:set rfl 24000
+rfl
+:set arr EDDS
+:rules
+:set altitude 1
+:quit
+rfl
+";
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    let stdout = stdout(&output);
+    assert!(stdout.starts_with("'CINDY'\n'E'\n"), "{stdout}");
+    assert!(
+        stdout.contains("error: Identifier is not implemented\n  in unknown\n"),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains(
+            "\
+error: Got EOF but expected further token
+  dep == 'EDDF' and
+                   ^
+"
+        ),
+        "{stdout}"
+    );
+    assert!(stdout.contains("\n24000\n"), "{stdout}");
+    assert!(stdout.contains("EDDF-EDDS: error RFL\n"), "{stdout}");
+    assert!(
+        stdout.contains("error: Unknown field altitude\n"),
+        "{stdout}"
+    );
+    // Nothing is evaluated after :quit
+    assert!(
+        stdout.ends_with("error: Unknown field altitude\n"),
+        "{stdout}"
+    );
+    assert_eq!(Some(0), output.status.code());
+}
@@ -0,0 +1,108 @@
+use std::path::{Path, PathBuf};
+
+use esfpc::config::{self, Action, ActionType, Config};
+use esfpc::{ffi, Checker};
+
+fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/rules")
+        .join(name)
+}
+
+#[test]
+fn file_formats() {
+    let json = Config::load(&fixture("eddf.json")).unwrap();
+    for name in ["eddf.yaml", "eddf.toml"] {
+        let config = Config::load(&fixture(name)).unwrap();
+        assert!(config.checks.rvsm.is_some(), "{name}");
+        assert_eq!(json.tables, config.tables, "{name}");
+        assert_eq!(json.rules.len(), config.rules.len(), "{name}");
+        for (expected, rule) in json.rules.iter().zip(&config.rules) {
+            assert_eq!(expected.condition, rule.condition, "{name}");
+            assert_eq!(expected.action, rule.action, "{name}");
+        }
+    }
+}
+
+#[test]
+fn unknown_file_type() {
+    let err = Config::load(&fixture("eddf.txt")).err().unwrap();
+    assert_eq!(std::io::ErrorKind::InvalidInput, err.kind());
+}
+
+#[test]
+fn invalid_condition() {
+    let dir = std::env::temp_dir().join("esfpc-config-test");
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("invalid.yaml");
+    std::fs::write(
+        &path,
+        "rules:\n  - condition: rfl >\n    action: { typ: error, msg: RFL }\n",
+    )
+    .unwrap();
+    let err = Config::load(&path).err().unwrap();
+    assert_eq!(std::io::ErrorKind::InvalidData, err.kind());
+    assert!(err.to_string().contains("invalid.yaml"));
+}
+
+#[test]
+fn table_named_like_identifier() {
+    let err = serde_json::from_str::<Config>(
+        r#"{ "tables": { "rfl": { "EDDS": 24000 } }, "rules": [] }"#,
+    )
+    .err()
+    .unwrap();
+    assert!(
+        err.to_string()
+            .contains("Table rfl is named like a built-in identifier"),
+        "{err}"
+    );
+    assert!(
+        serde_json::from_str::<Config>(r#"{ "tables": { "max_rfl": {} }, "rules": [] }"#).is_ok()
+    );
+}
+
+#[test]
+fn priorities() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/priority");
+    let names = |files: &[PathBuf]| -> Vec<String> {
+        files
+            .iter()
+            .map(|f| f.file_name().unwrap().to_string_lossy().into_owned())
+            .collect()
+    };
+    assert_eq!(
+        vec!["a.json", "b.json", "c.json"],
+        names(&config::rule_files(&dir).unwrap())
+    );
+
+    // c.json has a higher priority, a.json and b.json are ordered by name
+    let checker = Checker::load(&dir).unwrap();
+    assert_eq!(vec!["c.json", "a.json", "b.json"], names(checker.files()));
+
+    // Rule 2 of c.json has a higher priority than rule 1
+    let fp: ffi::FlightPlan = serde_json::from_str(r#"{ "arr": "EDDS", "rfl": 35000 }"#).unwrap();
+    let findings: Vec<_> = checker
+        .findings(&fp)
+        .into_iter()
+        .map(|finding| finding.name.unwrap())
+        .collect();
+    assert_eq!(vec!["C EDDS", "C RFL", "A fallback"], findings);
+    assert_eq!(
+        Ok(Action::new(ActionType::Error, "DST".into())),
+        checker.check(&fp)
+    );
+
+    // The fallback of a.json is checked before b.json
+    let fp: ffi::FlightPlan = serde_json::from_str(r#"{ "arr": "EDDM" }"#).unwrap();
+    assert_eq!(
+        Ok(Action::new(ActionType::Success, "OK".into())),
+        checker.check(&fp)
+    );
+
+    // Loading again gives the same order
+    assert_eq!(
+        names(checker.files()),
+        names(Checker::load(&dir).unwrap().files())
+    );
+}
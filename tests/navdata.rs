@@ -0,0 +1,120 @@
+use std::path::Path;
+
+use esfpc::geo;
+use esfpc::navdata::{NavDatabase, PointKind, ProcedureKind, SegmentError};
+use esfpc::route::Route;
+
+fn fixture_db() -> NavDatabase {
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let mut db = NavDatabase::default();
+    db.load(&fixtures.join("eddf.sct")).unwrap();
+    db.load(&fixtures.join("eddf.ese")).unwrap();
+    db
+}
+
+#[test]
+fn load_sector_file() {
+    let db = fixture_db();
+
+    let ffm = &db.points("FFM")[0];
+    assert_eq!(PointKind::Vor, ffm.kind);
+    assert!((ffm.coord.lat - 50.0536).abs() < 1e-3);
+    assert!((ffm.coord.lon - 8.6372).abs() < 1e-3);
+
+    assert_eq!(PointKind::Ndb, db.points("KLF")[0].kind);
+    assert_eq!(PointKind::Fix, db.points("CINDY")[0].kind);
+    assert!(db.contains_point("ROKIL"));
+    assert!(!db.contains_point("CINDI"));
+
+    let eddf = db.airport("EDDF").unwrap();
+    assert!((eddf.coord.lat - 50.0331).abs() < 1e-3);
+    assert!(db.airport("FFM").is_none());
+
+    let runways: Vec<_> = db.runways("EDDF").map(|r| r.ident.as_str()).collect();
+    assert_eq!(vec!["07C", "25C", "07L", "25R", "18", "36"], runways);
+}
+
+#[test]
+fn load_extended_sector_file() {
+    let db = fixture_db();
+
+    let sid = db.procedure("EDDF", "CINDY4S").unwrap();
+    assert_eq!(ProcedureKind::Sid, sid.kind);
+    assert_eq!("18", sid.runway);
+    assert_eq!(vec!["CINDY"], sid.points);
+    assert!(db.procedure("EDDF", "ROKIL1A").is_none());
+}
+
+#[test]
+fn unknown_file_type() {
+    let mut db = NavDatabase::default();
+    assert!(db.load(Path::new("tests/fixtures/eddf.txt")).is_err());
+}
+
+#[test]
+fn unknown_route_points() {
+    let db = fixture_db();
+
+    let route = Route::parse("CINDY Z74 HAREM T104 ROKIL");
+    assert!(db.unknown_points(&route).is_empty());
+    assert!(db.unknown_airways(&route).is_empty());
+
+    let route = Route::parse("CINDI Z47 HAREM T104 ROKIL DCT 4930N01030E DCT FFM090020");
+    assert_eq!(vec!["CINDI"], db.unknown_points(&route));
+    assert_eq!(vec!["Z47"], db.unknown_airways(&route));
+}
+
+#[test]
+fn route_geometry() {
+    let db = fixture_db();
+    assert_eq!(3.0, db.magnetic_variation());
+
+    let route = Route::parse("CINDY Z74 HAREM T104 ROKIL");
+    let coords = db.route_coords("EDDF", &route, "EDDM").unwrap();
+    assert_eq!(5, coords.len());
+    assert_eq!(db.airport("EDDF").unwrap().coord, coords[0]);
+    assert_eq!(db.points("ROKIL")[0].coord, coords[3]);
+
+    let route_distance = geo::path_distance_nm(&coords);
+    let direct_distance = geo::distance_nm(coords[0], coords[4]);
+    assert!((route_distance - 164.5).abs() < 0.1);
+    assert!((direct_distance - 161.5).abs() < 0.1);
+
+    // Bearing and distance from a navaid, magnetic bearing corrected by the variation
+    let coords = db
+        .route_coords("EDDF", &Route::parse("FFM090020"), "EDDM")
+        .unwrap();
+    let ffm = db.points("FFM")[0].coord;
+    assert!((geo::distance_nm(ffm, coords[1]) - 20.0).abs() < 1e-6);
+    assert!((geo::initial_track(ffm, coords[1]) - 93.0).abs() < 0.1);
+
+    assert!(db.route_coords("EDDF", &route, "KJFK").is_none());
+}
+
+#[test]
+fn airway_segments() {
+    let mut db = fixture_db();
+    db.load(
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/airways.csv")
+            .as_path(),
+    )
+    .unwrap();
+
+    let broken = |route: &str| db.broken_segment(&Route::parse(route));
+    assert_eq!(None, broken("CINDY Z74 HAREM T104 ROKIL"));
+    assert_eq!(None, broken("ROKIL T104 HAREM L603 CINDY"));
+    assert_eq!(None, broken("TOBAK T161 ULKIG"));
+    assert_eq!(None, broken("TOBAK N858 NOSEX"));
+    assert_eq!(None, broken("CINDY DCT DF155 DCT HAREM"));
+
+    let segment = broken("CINDY Z74 HAREM T104 NOSEX").unwrap();
+    assert_eq!("HAREM T104 NOSEX", segment.to_string());
+    assert_eq!(SegmentError::NotOnAirway("NOSEX".into()), segment.error);
+
+    let errors = |route: &str| broken(route).unwrap().error;
+    assert_eq!(SegmentError::WrongDirection, errors("ULKIG T161 TOBAK"));
+    assert_eq!(SegmentError::UnknownAirway, errors("CINDY Z47 HAREM"));
+    assert_eq!(SegmentError::MissingPoint, errors("T104 ROKIL"));
+    assert_eq!(SegmentError::MissingPoint, errors("HAREM T104 DCT ROKIL"));
+}
@@ -1,264 +1,83 @@
-use esfpc::check_flightplan_impl;
+use std::path::Path;
+
 use esfpc::config::Action;
 use esfpc::config::ActionType::*;
 use esfpc::ffi::*;
-use esfpc::Plugin;
+use esfpc::{rule_tests, Checker, RuleTrace};
 
-fn default_ac() -> Aircraft {
-    Aircraft {
-        typ: AircraftType::Landplane,
-        wtc: WakeTurbulenceCategory::Medium,
-        faa_equip_code: FaaEquipmentCode::Q,
-        eng_typ: EngineType::Jet,
-        eng_count: 2,
-        is_rvsm_capable: true,
-    }
+fn checker() -> Checker {
+    Checker::load(Path::new("rules")).unwrap()
 }
 
 fn default_fp() -> FlightPlan {
-    FlightPlan {
-        ac: default_ac(),
-        rule: FlightRule::Ifr,
-        cfl: 4000,
-        rfl: 35000,
-        dep: "EDDF".to_string(),
-        dep_rwy: "18".to_string(),
-        arr: "EDDM".to_string(),
-        sid: "CINDY4S".to_string(),
-        route: "CINDY Z74 HAREM T104 ROKIL".to_string(),
+    serde_json::from_str(include_str!("fixtures/fp/eddf_cindy.json")).unwrap()
+}
+
+#[test]
+fn rule_file_tests() {
+    let checker = checker();
+    let results = rule_tests::run(&checker);
+    assert!(!results.is_empty());
+    for result in results {
+        assert!(result.passed(), "{result}");
     }
 }
 
 #[test]
-fn eddf_aneki() {
-    let fp_valid = FlightPlan {
-        rule: FlightRule::Ifr,
-        rfl: 35000,
-        dep: "EDDF".into(),
+fn explain_rfl() {
+    let fp = FlightPlan {
+        rfl: 34000,
         arr: "EDDS".into(),
         sid: "ANEKI1L".into(),
         ..default_fp()
     };
-    let fp_invalid_rfl = FlightPlan {
-        rfl: 34000,
-        ..fp_valid.clone()
-    };
-    let fp_invalid_dst = FlightPlan {
-        arr: "EDDM".into(),
-        ..fp_valid.clone()
-    };
 
-    let plugin = Plugin::start(create_plugin()).unwrap();
+    let checker = checker();
 
-    // Odd RFL
-    assert_eq!(
-        Ok(Action::new(Success, "OK".into())),
-        check_flightplan_impl(&plugin, fp_valid)
-    );
-
-    // Even RFL
-    assert_eq!(
-        Ok(Action::new(Error, "RFL".into())),
-        check_flightplan_impl(&plugin, fp_invalid_rfl)
-    );
+    let finding = &checker.findings(&fp)[0];
+    assert_eq!(Some("Route flight level direction".into()), finding.name);
+    assert_eq!(Ok(Action::new(Error, "RFL".into())), finding.action);
 
-    // Wrong DST
-    assert_eq!(
-        Ok(Action::new(Error, "DST".into())),
-        check_flightplan_impl(&plugin, fp_invalid_dst)
+    let rule = checker.explain(&fp).pop().filter(RuleTrace::fired).unwrap();
+    let lines = rule.trace.lines();
+    assert!(lines[0].ends_with("-> true"), "{lines:?}");
+    assert!(lines.contains(&"    sidwpt -> 'ANEKI'".into()), "{lines:?}");
+    assert!(
+        lines.contains(&"  !valid_cruise_level(sid_direction[sidwpt]) -> true".into()),
+        "{lines:?}"
     );
 }
 
 #[test]
-fn eddf_cindy() {
-    let fp_valid = FlightPlan {
-        ac: Aircraft {
-            eng_typ: EngineType::Turboprop,
-            ..default_ac()
-        },
-        rfl: 23000,
-        sid: "CINDY4S".into(),
-        route: "CINDY Z74 HAREM T104 ROKIL".into(),
-        arr: "EDDM".into(),
+fn explain_lines() {
+    let fp = FlightPlan {
+        rfl: 34000,
+        arr: "EDDS".into(),
+        sid: "ANEKI1L".into(),
         ..default_fp()
     };
-    let fp_invalid_rfl = FlightPlan {
-        rfl: 10000,
-        ..fp_valid.clone()
-    };
-    let fp_invalid_dst = FlightPlan {
-        arr: "LSZH".into(),
-        ..fp_valid.clone()
-    };
-    let fp_invalid_ac = FlightPlan {
+    let fp_non_rvsm = FlightPlan {
+        rfl: 35000,
         ac: Aircraft {
-            eng_typ: EngineType::Jet,
-            ..default_ac()
+            is_rvsm_capable: false,
+            ..fp.ac.clone()
         },
-        route: "CINDY L603 HAREM DCT ROKIL".into(),
-        ..fp_valid.clone()
-    };
-    let fp_invalid_rfl_prop = FlightPlan {
-        rfl: 25000,
-        route: "CINDY L603 HAREM DCT ROKIL".into(),
-        ..fp_valid.clone()
+        ..fp.clone()
     };
 
-    let plugin = Plugin::start(create_plugin()).unwrap();
-
-    assert_eq!(
-        Ok(Action::new(Success, "OK".into())),
-        check_flightplan_impl(&plugin, fp_valid)
-    );
-
-    // Even RFL
-    assert_eq!(
-        Ok(Action::new(Error, "RFL".into())),
-        check_flightplan_impl(&plugin, fp_invalid_rfl)
-    );
-
-    // Invalid DST
-    assert_eq!(
-        Ok(Action::new(Error, "DST".into())),
-        check_flightplan_impl(&plugin, fp_invalid_dst)
-    );
+    let checker = checker();
 
-    // Invalid engine type
+    // The action of the rule, followed by its evaluated condition
+    let lines = checker.explain_lines(&fp);
+    assert_eq!("error RFL (Route flight level direction)", lines[0]);
     assert_eq!(
-        Ok(Action::new(Error, "ENG".into())),
-        check_flightplan_impl(&plugin, fp_invalid_ac)
+        checker.explain(&fp).pop().unwrap().trace.lines(),
+        lines[1..]
     );
 
-    // Invalid RFL with prop only route
+    // A failed check decides the action without any rule
     assert_eq!(
-        Ok(Action::new(Error, "RFL".into())),
-        check_flightplan_impl(&plugin, fp_invalid_rfl_prop)
+        vec!["error RVSM (rvsm)".to_string()],
+        checker.explain_lines(&fp_non_rvsm)
     );
 }
-
-#[test]
-fn eddf_komib() {
-    let fp_valid = FlightPlan {
-        rule: FlightRule::Ifr,
-        rfl: 35000,
-        dep: "EDDF".into(),
-        arr: "EDDN".into(),
-        sid: "KOMIB3D".into(),
-        ..default_fp()
-    };
-    let fp_invalid_rfl = FlightPlan {
-        rfl: 34000,
-        ..fp_valid.clone()
-    };
-    let fp_invalid_dst = FlightPlan {
-        arr: "EDDM".into(),
-        ..fp_valid.clone()
-    };
-
-    let plugin = Plugin::start(create_plugin()).unwrap();
-
-    // Odd RFL
-    assert_eq!(
-        Ok(Action::new(Success, "OK".into())),
-        check_flightplan_impl(&plugin, fp_valid)
-    );
-
-    // Even RFL
-    assert_eq!(
-        Ok(Action::new(Error, "RFL".into())),
-        check_flightplan_impl(&plugin, fp_invalid_rfl)
-    );
-
-    // Wrong DST
-    assert_eq!(
-        Ok(Action::new(Error, "DST".into())),
-        check_flightplan_impl(&plugin, fp_invalid_dst)
-    );
-}
-
-#[test]
-fn eddf_tobak() {
-    let fp_valid = FlightPlan {
-        rule: FlightRule::Ifr,
-        rfl: 35000,
-        dep: "EDDF".into(),
-        arr: "EDDN".into(),
-        sid: "TOBAK7M".into(),
-        route: "TOBAK7M/25C TOBAK N858 NOSEX DCT KLF".into(),
-        ..default_fp()
-    };
-    let fp_invalid_rfl = FlightPlan {
-        rfl: 34000,
-        ..fp_valid.clone()
-    };
-    let fp_invalid_route = FlightPlan {
-        route: "TOBAK7M/25C TOBAK Z10 NOSEX DCT KLF".into(),
-        ..fp_valid.clone()
-    };
-
-    let plugin = Plugin::start(create_plugin()).unwrap();
-
-    // Odd RFL
-    assert_eq!(
-        Ok(Action::new(Success, "OK".into())),
-        check_flightplan_impl(&plugin, fp_valid)
-    );
-
-    // Even RFL
-    assert_eq!(
-        Ok(Action::new(Error, "RFL".into())),
-        check_flightplan_impl(&plugin, fp_invalid_rfl)
-    );
-
-    // Wrong RTE
-    assert_eq!(
-        Ok(Action::new(Error, "RTE".into())),
-        check_flightplan_impl(&plugin, fp_invalid_route)
-    );
-}
-
-#[test]
-fn eddf_non_rnav_sids() {
-    for sid in ["MTR5C", "RID8C", "RID3Q", "TAU2Q"] {
-        let fp_valid = FlightPlan {
-            rule: FlightRule::Ifr,
-            rfl: 9000,
-            sid: sid.into(),
-            ac: Aircraft {
-                faa_equip_code: FaaEquipmentCode::A,
-                ..default_ac()
-            },
-            ..default_fp()
-        };
-        let fp_invalid_rfl = FlightPlan {
-            rfl: 10000,
-            ..fp_valid.clone()
-        };
-        let fp_invalid_equip = FlightPlan {
-            ac: Aircraft {
-                faa_equip_code: FaaEquipmentCode::G,
-                ..default_ac()
-            },
-            ..fp_valid.clone()
-        };
-
-        let plugin = Plugin::start(create_plugin()).unwrap();
-
-        assert_eq!(
-            Ok(Action::new(Success, "OK".into())),
-            check_flightplan_impl(&plugin, fp_valid)
-        );
-
-        // Too high FL
-        assert_eq!(
-            Ok(Action::new(Error, "RFL".into())),
-            check_flightplan_impl(&plugin, fp_invalid_rfl)
-        );
-
-        // RNAV capable
-        assert_eq!(
-            Ok(Action::new(Error, "RNV".into())),
-            check_flightplan_impl(&plugin, fp_invalid_equip)
-        );
-    }
-}
@@ -0,0 +1,173 @@
+use std::path::Path;
+
+use esfpc::check_configs;
+use esfpc::config::{Action, ActionType, Config};
+use esfpc::ffi::*;
+
+fn fp(dep: &str, sid: &str, route: &str, arr: &str, rfl: u32, eng_typ: EngineType) -> FlightPlan {
+    FlightPlan {
+        ac: Aircraft {
+            typ: AircraftType::Landplane,
+            icao: "A320".into(),
+            wtc: WakeTurbulenceCategory::Medium,
+            faa_equip_code: FaaEquipmentCode::Q,
+            equip: "SDE2E3FGIJ1RWY/LB1".into(),
+            eng_typ,
+            eng_count: 2,
+            is_rvsm_capable: true,
+        },
+        rule: FlightRule::Ifr,
+        cfl: 5000,
+        rfl,
+        dep: dep.into(),
+        dep_rwy: "18".into(),
+        arr: arr.into(),
+        sid: sid.into(),
+        route: route.into(),
+        remarks: "".into(),
+    }
+}
+
+#[test]
+fn sid_json_corpus() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/vfpc/Sid.json");
+    // Valid flights match no rule of the Sid.json file, but the fallback of a later file
+    let fallback: Config = serde_json::from_str(
+        r#"{ "rules": [{ "condition": "true", "action": { "typ": "success", "msg": "OK" } }] }"#,
+    )
+    .unwrap();
+    let configs = vec![Config::load(&path).unwrap(), fallback];
+
+    let jet = || EngineType::Jet;
+    let corpus = [
+        // ANEKI: not to EDDM/EDDN, odd levels
+        (
+            fp("EDDF", "ANEKI1L", "ANEKI Y163 NATOR", "EDDS", 35000, jet()),
+            "OK",
+        ),
+        (
+            fp("EDDF", "ANEKI1L", "ANEKI Y163 NATOR", "EDDM", 35000, jet()),
+            "DST",
+        ),
+        (
+            fp("EDDF", "ANEKI1L", "ANEKI Y163 NATOR", "LFSB", 34000, jet()),
+            "RFL",
+        ),
+        // CINDY: T104 to EDDM/EDDN, or L603 for props up to FL230
+        (
+            fp(
+                "EDDF",
+                "CINDY4S",
+                "CINDY Z74 HAREM T104 ROKIL",
+                "EDDM",
+                35000,
+                jet(),
+            ),
+            "OK",
+        ),
+        (
+            fp(
+                "EDDF",
+                "CINDY4S",
+                "CINDY Z74 HAREM T104 ROKIL",
+                "EDDM",
+                36000,
+                jet(),
+            ),
+            "RFL",
+        ),
+        (
+            fp(
+                "EDDF",
+                "CINDY4S",
+                "CINDY Z74 HAREM T104 ROKIL",
+                "LOWW",
+                35000,
+                jet(),
+            ),
+            "RTE",
+        ),
+        (
+            fp(
+                "EDDF",
+                "CINDY4S",
+                "CINDY L603 HAREM",
+                "LOWW",
+                20000,
+                EngineType::Turboprop,
+            ),
+            "OK",
+        ),
+        (
+            fp("EDDF", "CINDY4S", "CINDY L603 HAREM", "LOWW", 20000, jet()),
+            "ENG",
+        ),
+        (
+            fp(
+                "EDDF",
+                "CINDY4S",
+                "CINDY L603 HAREM",
+                "LOWW",
+                24000,
+                EngineType::Piston,
+            ),
+            "RFL",
+        ),
+        (
+            fp("EDDF", "CINDY4S", "CINDY DCT HAREM", "EDDM", 35000, jet()),
+            "RTE",
+        ),
+        // MARUN: Benelux and UK, even levels from FL250
+        (
+            fp("EDDF", "MARUN7F", "MARUN Y180 BITBU", "EHAM", 26000, jet()),
+            "OK",
+        ),
+        (
+            fp("EDDF", "MARUN7F", "MARUN Y180 BITBU", "EGLL", 24000, jet()),
+            "RFL",
+        ),
+        (
+            fp("EDDF", "MARUN7F", "MARUN Y180 BITBU", "LFPG", 26000, jet()),
+            "DST",
+        ),
+        // Unknown SIDs
+        (
+            fp("EDDF", "TOBAK7M", "TOBAK Z10 SPESA", "EDDM", 35000, jet()),
+            "SID",
+        ),
+        (fp("EDDM", "ANKER1S", "ANKER", "EDDF", 35000, jet()), "SID"),
+        // Other airports are not checked
+        (fp("EDDS", "", "", "EDDF", 35000, jet()), "OK"),
+    ];
+    for (fp, msg) in corpus {
+        let typ = match msg {
+            "OK" => ActionType::Success,
+            _ => ActionType::Error,
+        };
+        let route = fp.route.clone();
+        assert_eq!(
+            Ok(Action::new(typ, msg.into())),
+            check_configs(&configs, &fp, None, None),
+            "{route} to {}",
+            fp.arr
+        );
+    }
+}
+
+#[test]
+fn valid_flights_match_no_rule() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/vfpc/Sid.json");
+    let configs = vec![Config::load(&path).unwrap()];
+    let fp = fp(
+        "EDDF",
+        "MARUN7F",
+        "MARUN Y180 BITBU",
+        "EHAM",
+        26000,
+        EngineType::Jet,
+    );
+    assert_eq!(
+        Ok(Action::new(ActionType::Warning, "UNK".into())),
+        check_configs(&configs, &fp, None, None)
+    );
+}